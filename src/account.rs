@@ -0,0 +1,422 @@
+use serde::Serialize;
+use std::{io::IsTerminal, path::Path};
+use tokio_util::sync::CancellationToken;
+
+use crate::{
+    audit::{self, AuditEntry, FieldChange},
+    cli::{AccountArgs, AccountCommand, OutputArgs, ShowRequestArgs},
+    config::{Account, ArchivedAccount, Config, ProviderConfig},
+    config_edit::{self, AccountList},
+    cookie_pool,
+    lint,
+    output::{self, TableRow},
+    runner,
+    secret::SecretString,
+    state_store::{self, StateStore},
+};
+
+#[derive(Debug, Serialize)]
+pub struct AccountRow {
+    pub account: String,
+    pub provider: String,
+    pub email: String,
+    pub cookie_updated_at: Option<String>,
+    pub last_success: Option<String>,
+    pub failure_streak: u32,
+    pub flagged: bool,
+    pub disabled: bool,
+    pub archived: bool,
+    pub tags: Vec<String>,
+}
+
+impl TableRow for AccountRow {
+    const HEADERS: &'static [&'static str] = &["account", "provider", "email", "cookie_updated_at", "last_success", "failure_streak", "flagged", "disabled", "archived", "tags"];
+
+    fn cells(&self) -> Vec<String> {
+        vec![
+            self.account.clone(),
+            self.provider.clone(),
+            self.email.clone(),
+            output::cell(self.cookie_updated_at.as_deref()),
+            output::cell(self.last_success.as_deref()),
+            self.failure_streak.to_string(),
+            if self.flagged { "yes" } else { "no" }.to_string(),
+            if self.disabled { "yes" } else { "no" }.to_string(),
+            if self.archived { "yes" } else { "no" }.to_string(),
+            if self.tags.is_empty() { "-".to_string() } else { self.tags.join(",") },
+        ]
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct FlagRow {
+    pub account: String,
+    pub flagged_at: String,
+    pub reason: String,
+}
+
+impl TableRow for FlagRow {
+    const HEADERS: &'static [&'static str] = &["account", "flagged_at", "reason"];
+
+    fn cells(&self) -> Vec<String> {
+        vec![self.account.clone(), self.flagged_at.clone(), self.reason.clone()]
+    }
+}
+
+pub fn list(config: &Config, args: &OutputArgs) -> Result<(), Box<dyn std::error::Error>> {
+    let store = state_store::open(&config.state_store)?;
+    let mut rows = Vec::new();
+    for instance in config.provider_instances() {
+        for account in instance.accounts()? {
+            let account = account?;
+            let key = instance.key(&account.email);
+            let state = store.load(&key)?;
+            rows.push(AccountRow {
+                account: key,
+                provider: instance.label().to_string(),
+                email: account.email,
+                cookie_updated_at: account.cookie_updated_at,
+                last_success: state.last_success,
+                failure_streak: state.failure_streak,
+                flagged: state.flag.is_some(),
+                disabled: account.disabled,
+                archived: false,
+                tags: account.tags.clone(),
+            });
+        }
+    }
+    for account in &config.archived_accounts {
+        let key = account.key();
+        let state = store.load(&key)?;
+        rows.push(AccountRow {
+            account: key,
+            provider: account.provider.clone().unwrap_or_else(|| "default".to_string()),
+            email: account.email.clone(),
+            cookie_updated_at: account.cookie_updated_at.clone(),
+            last_success: state.last_success,
+            failure_streak: state.failure_streak,
+            flagged: state.flag.is_some(),
+            disabled: false,
+            archived: true,
+            tags: Vec::new(),
+        });
+    }
+    output::print(&rows, args.format)
+}
+
+pub async fn run(config: &Config, config_path: &str, args: &AccountArgs) -> Result<(), Box<dyn std::error::Error>> {
+    let store = state_store::open(&config.state_store)?;
+    match &args.command {
+        AccountCommand::List(args) => list(config, args)?,
+        AccountCommand::Add { email, cookie, no_verify } => {
+            add(config, config_path, email, cookie.as_deref(), *no_verify).await?;
+        }
+        AccountCommand::Remove { account, archive } => remove(config, config_path, account, *archive)?,
+        AccountCommand::Restore { account } => restore(config, config_path, account)?,
+        AccountCommand::Disable { account } => set_disabled(config, config_path, account, true)?,
+        AccountCommand::Enable { account } => set_disabled(config, config_path, account, false)?,
+        AccountCommand::Flags(args) => {
+            let rows: Vec<FlagRow> = store.all()?.into_iter()
+                .filter_map(|(account, state)| state.flag.map(|flag| FlagRow {
+                    account,
+                    flagged_at: flag.flagged_at,
+                    reason: flag.reason,
+                }))
+                .collect();
+            output::print(&rows, args.format)?;
+        }
+        AccountCommand::ClearFlag { account } => {
+            let mut state = store.load(account)?;
+            if state.flag.take().is_none() {
+                return Err(format!("account {} is not flagged", account).into());
+            }
+            store.put(account, &state)?;
+            println!("已清除账户 {} 的待处理标记，下次运行将恢复签到", account);
+        }
+        AccountCommand::SetCookie { account, no_verify } => {
+            set_cookie(config, config_path, store.as_ref(), account, *no_verify).await?;
+        }
+    }
+    Ok(())
+}
+
+fn find(config: &Config, query: &str) -> Result<(ProviderConfig, Account), Box<dyn std::error::Error>> {
+    let mut matches = Vec::new();
+    for instance in config.provider_instances() {
+        for account in instance.accounts()? {
+            let account = account?;
+            if instance.key(&account.email) == query || account.email == query {
+                matches.push((instance.clone(), account));
+            }
+        }
+    }
+    match matches.len() {
+        0 => Err(format!("account '{}' not found", query).into()),
+        1 => Ok(matches.remove(0)),
+        _ => Err(format!("account '{}' exists in several providers, use <name>/<email>", query).into()),
+    }
+}
+
+// 账户可能定义在主配置、include 文件或 accounts_file 中，按顺序查找第一个包含该账户的文件
+fn account_files(config: &Config, config_path: &str, instance: &ProviderConfig) -> Vec<String> {
+    let mut files = vec![config_path.to_string()];
+    if instance.name.is_none() {
+        let base_dir = Path::new(config_path).parent().unwrap_or_else(|| Path::new(""));
+        files.extend(config.include.iter().map(|include| base_dir.join(include).to_string_lossy().into_owned()));
+    }
+    files.extend(instance.accounts_file.clone());
+    files
+}
+
+async fn set_cookie(config: &Config, config_path: &str, store: &(dyn StateStore + 'static), query: &str, no_verify: bool) -> Result<(), Box<dyn std::error::Error>> {
+    let (instance, account) = find(config, query)?;
+    let key = instance.key(&account.email);
+
+    let cookie = read_cookie(&format!("请输入账户 {} 的新 cookie: ", key))?;
+    let previous = account.clone();
+    let account = Account {
+        cookie: SecretString::new(cookie.clone()),
+        ..account
+    };
+
+    if !no_verify {
+        let service = runner::build_service(config, &instance, &CancellationToken::new()).await?;
+        let status = service.status(&account).await
+            .map_err(|e| format!("cookie verification failed: {} (use --no-verify to save anyway)", e))?;
+        println!("cookie 验证通过，剩余天数: {}", status.left_days.as_deref().unwrap_or("-"));
+    }
+
+    let today = chrono::Local::now().format("%Y-%m-%d").to_string();
+    for file in account_files(config, config_path, &instance) {
+        let content = std::fs::read_to_string(&file)
+            .map_err(|e| format!("failed to read {}: {}", file, e))?;
+        let Some(updated) = config_edit::set_account_field(&file, &content, AccountList::of(&instance), &account.email, "cookie", &cookie.as_str().into())? else {
+            continue;
+        };
+        let mut changes = vec![FieldChange::secret("cookie", Some(&previous.cookie), Some(&account.cookie))];
+        let updated = match config_edit::set_account_field(&file, &updated, AccountList::of(&instance), &account.email, "cookie_updated_at", &today.as_str().into())? {
+            Some(dated) if dated != updated => {
+                changes.push(FieldChange::new("cookie_updated_at", previous.cookie_updated_at.as_deref(), Some(&today)));
+                dated
+            }
+            _ => updated,
+        };
+        config_edit::write_atomic(&file, &updated)?;
+        println!("已更新 {} 中账户 {} 的 cookie", file, key);
+        audit(config, "account set-cookie", &key, &file, changes);
+
+        if store.load(&key)?.flag.is_some() {
+            store.update(&key, |state| state.flag = None)?;
+            println!("已清除账户 {} 的待处理标记，下次运行将恢复签到", key);
+        }
+        return Ok(());
+    }
+    Err(format!("account {} not found in {}", key, account_files(config, config_path, &instance).join(", ")).into())
+}
+
+fn read_cookie(prompt: &str) -> Result<String, Box<dyn std::error::Error>> {
+    if std::io::stdin().is_terminal() {
+        eprint!("{}", prompt);
+    }
+    let mut cookie = String::new();
+    std::io::stdin().read_line(&mut cookie)?;
+    let cookie = cookie.trim().to_string();
+    if cookie.is_empty() {
+        return Err("cookie must not be empty".into());
+    }
+    Ok(cookie)
+}
+
+// 写入后重新加载整个配置；不能加载时（如删除了最后一个账户）恢复原文件，避免留下无法运行的配置
+fn write_checked(config_path: &str, file: &str, original: &str, updated: &str) -> Result<(), Box<dyn std::error::Error>> {
+    config_edit::write_atomic(file, updated)?;
+    if let Err(e) = Config::load_from_file(config_path, None) {
+        config_edit::write_atomic(file, original)?;
+        return Err(format!("{} was left unchanged because the result would not load: {}", file, e).into());
+    }
+    Ok(())
+}
+
+fn audit(config: &Config, command: &str, key: &str, file: &str, changes: Vec<FieldChange>) {
+    if let Err(e) = audit::append(&config.audit_log, &AuditEntry::new(command, key, file, changes)) {
+        eprintln!("记录配置修改失败: {}", e);
+    }
+}
+
+async fn add(config: &Config, config_path: &str, email: &str, cookie: Option<&str>, no_verify: bool) -> Result<(), Box<dyn std::error::Error>> {
+    let instance = config.default_instance();
+    if instance.accounts()?.flatten().any(|account| account.email == email) {
+        return Err(format!("account {} already exists", email).into());
+    }
+    let cookie = match cookie {
+        Some(cookie) => cookie.trim().to_string(),
+        None => read_cookie(&format!("请输入账户 {} 的 cookie: ", email))?,
+    };
+    if let Some(problem) = lint::cookie_problem(&cookie) {
+        return Err(format!("cookie {}", problem).into());
+    }
+    let account = Account {
+        email: email.to_string(),
+        cookie: SecretString::new(cookie.clone()),
+        ..Account::default()
+    };
+
+    if !no_verify {
+        let service = runner::build_service(config, &instance, &CancellationToken::new()).await?;
+        let status = service.status(&account).await
+            .map_err(|e| format!("cookie verification failed: {} (use --no-verify to save anyway)", e))?;
+        println!("cookie 验证通过，剩余天数: {}", status.left_days.as_deref().unwrap_or("-"));
+    }
+
+    let today = chrono::Local::now().format("%Y-%m-%d").to_string();
+    let content = std::fs::read_to_string(config_path)
+        .map_err(|e| format!("failed to read {}: {}", config_path, e))?;
+    let updated = config_edit::add_account(config_path, &content, "accounts", &[
+        ("email", email.into()),
+        ("cookie", cookie.as_str().into()),
+        ("cookie_updated_at", today.as_str().into()),
+    ])?;
+    write_checked(config_path, config_path, &content, &updated)?;
+    println!("已将账户 {} 添加到 {}", email, config_path);
+    audit(config, "account add", email, config_path, vec![
+        FieldChange::new("email", None, Some(email)),
+        FieldChange::secret("cookie", None, Some(&account.cookie)),
+        FieldChange::new("cookie_updated_at", None, Some(&today)),
+    ]);
+    Ok(())
+}
+
+fn remove(config: &Config, config_path: &str, query: &str, archive: bool) -> Result<(), Box<dyn std::error::Error>> {
+    let (instance, account) = find(config, query)?;
+    let key = instance.key(&account.email);
+    if archive && config.archived_accounts.iter().any(|archived| archived.key() == key) {
+        return Err(format!("account {} is already archived, remove it from archived_accounts first", key).into());
+    }
+    let today = chrono::Local::now().format("%Y-%m-%d").to_string();
+    let mut archived: Vec<(&str, serde_json::Value)> = vec![("email", account.email.as_str().into()), ("cookie", account.cookie.expose().into())];
+    archived.extend(instance.name.as_deref().map(|name| ("provider", name.into())));
+    archived.extend(account.cookie_updated_at.as_deref().map(|date| ("cookie_updated_at", date.into())));
+    archived.push(("archived_at", today.as_str().into()));
+
+    for file in account_files(config, config_path, &instance) {
+        let content = std::fs::read_to_string(&file)
+            .map_err(|e| format!("failed to read {}: {}", file, e))?;
+        let Some(updated) = config_edit::remove_account(&file, &content, AccountList::of(&instance), &account.email)? else {
+            continue;
+        };
+        if !archive {
+            write_checked(config_path, &file, &content, &updated)?;
+            println!("已从 {} 删除账户 {}（签到历史和账户状态保留）", file, key);
+            audit(config, "account remove", &key, &file, vec![
+                FieldChange::new("email", Some(&account.email), None),
+                FieldChange::secret("cookie", Some(&account.cookie), None),
+            ]);
+            return Ok(());
+        }
+
+        // 账户在 include 或 accounts_file 中时，先写入主配置的 archived_accounts，再从原文件删除
+        if file == config_path {
+            let updated = config_edit::add_account(config_path, &updated, "archived_accounts", &archived)?;
+            write_checked(config_path, config_path, &content, &updated)?;
+        } else {
+            let main = std::fs::read_to_string(config_path)
+                .map_err(|e| format!("failed to read {}: {}", config_path, e))?;
+            let with_archive = config_edit::add_account(config_path, &main, "archived_accounts", &archived)?;
+            write_checked(config_path, config_path, &main, &with_archive)?;
+            if let Err(e) = write_checked(config_path, &file, &content, &updated) {
+                config_edit::write_atomic(config_path, &main)?;
+                return Err(e);
+            }
+        }
+        println!("已将账户 {} 从 {} 移到 {} 的 archived_accounts（不再签到，签到历史和账户状态保留，web account restore {} 可恢复）",
+            key, file, config_path, account.email);
+        audit(config, "account archive", &key, &file, vec![
+            FieldChange::new("archived_at", None, Some(&today)),
+        ]);
+        return Ok(());
+    }
+    Err(format!("account {} not found in {}", key, account_files(config, config_path, &instance).join(", ")).into())
+}
+
+fn restore(config: &Config, config_path: &str, query: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let matches: Vec<&ArchivedAccount> = config.archived_accounts.iter()
+        .filter(|archived| archived.key() == query || archived.email == query)
+        .collect();
+    let archived = match matches.as_slice() {
+        [] => return Err(format!("archived account '{}' not found", query).into()),
+        [archived] => *archived,
+        _ => return Err(format!("archived account '{}' exists for several providers, use <name>/<email>", query).into()),
+    };
+    if let Some(provider) = &archived.provider {
+        return Err(format!("{} was archived from provider {}, add it back to that provider's accounts manually", archived.email, provider).into());
+    }
+    if config.default_instance().accounts()?.flatten().any(|account| account.email == archived.email) {
+        return Err(format!("account {} already exists", archived.email).into());
+    }
+
+    let content = std::fs::read_to_string(config_path)
+        .map_err(|e| format!("failed to read {}: {}", config_path, e))?;
+    let updated = config_edit::remove_account(config_path, &content, AccountList::Archived(None), &archived.email)?
+        .ok_or_else(|| format!("archived account {} not found in {}", archived.email, config_path))?;
+    let updated = config_edit::remove_empty_list(&updated, "archived_accounts");
+    let mut fields: Vec<(&str, serde_json::Value)> = vec![("email", archived.email.as_str().into()), ("cookie", archived.cookie.expose().into())];
+    fields.extend(archived.cookie_updated_at.as_deref().map(|date| ("cookie_updated_at", date.into())));
+    let updated = config_edit::add_account(config_path, &updated, "accounts", &fields)?;
+    write_checked(config_path, config_path, &content, &updated)?;
+    println!("已恢复账户 {}，下次运行将签到（cookie 可能已失效，可用 web account set-cookie {} 更新）", archived.email, archived.email);
+    audit(config, "account restore", &archived.email, config_path, vec![
+        FieldChange::new("archived_at", Some(&archived.archived_at), None),
+    ]);
+    Ok(())
+}
+
+fn set_disabled(config: &Config, config_path: &str, query: &str, disabled: bool) -> Result<(), Box<dyn std::error::Error>> {
+    let (instance, account) = find(config, query)?;
+    let key = instance.key(&account.email);
+    let action = if disabled { "停用" } else { "启用" };
+    if account.disabled == disabled {
+        println!("账户 {} 已处于{}状态", key, action);
+        return Ok(());
+    }
+    for file in account_files(config, config_path, &instance) {
+        let content = std::fs::read_to_string(&file)
+            .map_err(|e| format!("failed to read {}: {}", file, e))?;
+        let Some(updated) = config_edit::put_account_field(&file, &content, AccountList::of(&instance), &account.email, "disabled", &disabled.into())? else {
+            continue;
+        };
+        write_checked(config_path, &file, &content, &updated)?;
+        println!("已{}账户 {}", action, key);
+        audit(config, if disabled { "account disable" } else { "account enable" }, &key, &file, vec![
+            FieldChange::new("disabled", Some(&account.disabled.to_string()), Some(&disabled.to_string())),
+        ]);
+        return Ok(());
+    }
+    Err(format!("account {} not found in {}", key, account_files(config, config_path, &instance).join(", ")).into())
+}
+
+// 使用与下次运行相同的 cookie（多个 cookie 时按历史表现选择）
+pub async fn show_request(config: &Config, args: &ShowRequestArgs) -> Result<(), Box<dyn std::error::Error>> {
+    let (instance, account) = find(config, &args.account)?;
+    let key = instance.key(&account.email);
+    let state = state_store::open(&config.state_store)?.load(&key)?;
+    let (label, cookie) = cookie_pool::ordered(&account, &state).remove(0);
+    if !account.cookies.is_empty() {
+        eprintln!("使用 cookie {}", label);
+    }
+    let account = Account {
+        cookie: cookie.clone(),
+        ..account.clone()
+    };
+
+    let service = runner::build_service(config, &instance, &CancellationToken::new()).await?;
+    let request = service.checkin_request(&account)?;
+    println!("{} {}", request.method(), request.url());
+    for (name, value) in request.headers() {
+        println!("{}: {}", name, account.cookie.redact(&String::from_utf8_lossy(value.as_bytes())));
+    }
+    println!();
+    if let Some(body) = request.body().and_then(|body| body.as_bytes()) {
+        println!("{}", account.cookie.redact(&String::from_utf8_lossy(body)));
+    }
+    Ok(())
+}
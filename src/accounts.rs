@@ -0,0 +1,145 @@
+use std::{
+    fs::File,
+    io::{BufRead, BufReader, Seek, SeekFrom},
+    path::Path,
+};
+
+use crate::{config::Account, secret::SecretString};
+
+pub type AccountResult = Result<Account, Box<dyn std::error::Error>>;
+
+enum Format {
+    Ndjson,
+    Csv,
+}
+
+pub struct AccountFileReader {
+    reader: BufReader<File>,
+    format: Format,
+    line_no: usize,
+    offset: u64,
+}
+
+// 账户行在文件中的位置，打乱顺序时只保存位置，读取时再定位到该行
+#[derive(Debug, Clone, Copy)]
+pub struct AccountLine {
+    offset: u64,
+    line_no: usize,
+}
+
+impl AccountFileReader {
+    pub fn open(path: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        let format = match Path::new(path).extension().and_then(|e| e.to_str()) {
+            Some("ndjson") | Some("jsonl") => Format::Ndjson,
+            Some("csv") => Format::Csv,
+            _ => return Err(format!("unsupported accounts_file format: {}", path).into()),
+        };
+        let file = File::open(path).map_err(|e| format!("failed to open accounts_file {}: {}", path, e))?;
+        Ok(Self {
+            reader: BufReader::new(file),
+            format,
+            line_no: 0,
+            offset: 0,
+        })
+    }
+
+    // 其余账户行的位置（跳过空行、注释和 CSV 表头）
+    pub fn positions(&mut self) -> Result<Vec<AccountLine>, Box<dyn std::error::Error>> {
+        let mut positions = Vec::new();
+        let mut buf = Vec::new();
+        while let Some(position) = self.next_line(&mut buf)? {
+            positions.push(position);
+        }
+        Ok(positions)
+    }
+
+    // 读取 positions 返回的某一行
+    pub fn read_at(&mut self, position: AccountLine) -> AccountResult {
+        let mut buf = Vec::new();
+        self.reader.seek(SeekFrom::Start(position.offset))?;
+        self.reader.read_until(b'\n', &mut buf)?;
+        self.parse(std::str::from_utf8(&buf)?.trim(), position.line_no)
+    }
+
+    fn next_line(&mut self, buf: &mut Vec<u8>) -> Result<Option<AccountLine>, Box<dyn std::error::Error>> {
+        loop {
+            buf.clear();
+            let read = self.reader.read_until(b'\n', buf)?;
+            if read == 0 {
+                return Ok(None);
+            }
+            let position = AccountLine { offset: self.offset, line_no: self.line_no + 1 };
+            self.offset += read as u64;
+            self.line_no += 1;
+            let line = std::str::from_utf8(buf)
+                .map_err(|e| format!("accounts_file 第 {} 行解析失败: {}", self.line_no, e))?
+                .trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            if matches!(self.format, Format::Csv) && self.line_no == 1 && line.eq_ignore_ascii_case("email,cookie") {
+                continue;
+            }
+            return Ok(Some(position));
+        }
+    }
+
+    fn parse(&self, line: &str, line_no: usize) -> AccountResult {
+        self.parse_line(line).map_err(|e| format!("accounts_file 第 {} 行解析失败: {}", line_no, e).into())
+    }
+
+    fn parse_line(&self, line: &str) -> AccountResult {
+        match self.format {
+            Format::Ndjson => Ok(serde_json::from_str(line)?),
+            Format::Csv => {
+                let (email, cookie) = line.split_once(',').ok_or("expected `email,cookie`")?;
+                Ok(Account {
+                    email: email.trim().to_string(),
+                    cookie: SecretString::new(cookie.trim().trim_matches('"')),
+                    ..Default::default()
+                })
+            }
+        }
+    }
+}
+
+impl Iterator for AccountFileReader {
+    type Item = AccountResult;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut buf = Vec::new();
+        match self.next_line(&mut buf) {
+            Ok(Some(position)) => Some(std::str::from_utf8(&buf).map_err(Into::into)
+                .and_then(|line| self.parse(line.trim(), position.line_no))),
+            Ok(None) => None,
+            Err(e) => Some(Err(e)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn read_at_returns_the_positioned_line() {
+        let dir = std::env::temp_dir().join(format!("web-accounts-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("accounts.csv");
+        std::fs::write(&path, "email,cookie\na@example.com,koa:sess=a\n\n# comment\nb@example.com,\"koa:sess=b\"\nbroken\n").unwrap();
+        let path = path.to_str().unwrap();
+
+        let sequential: Vec<String> = AccountFileReader::open(path).unwrap()
+            .map(|account| account.map(|account| account.email).unwrap_or_else(|e| e.to_string()))
+            .collect();
+        let mut reader = AccountFileReader::open(path).unwrap();
+        let positions = reader.positions().unwrap();
+        let reversed: Vec<String> = positions.into_iter().rev()
+            .map(|position| reader.read_at(position).map(|account| account.email).unwrap_or_else(|e| e.to_string()))
+            .collect();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(sequential, ["a@example.com", "b@example.com", "accounts_file 第 6 行解析失败: expected `email,cookie`"]);
+        assert_eq!(reversed, sequential.into_iter().rev().collect::<Vec<_>>());
+    }
+}
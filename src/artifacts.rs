@@ -0,0 +1,266 @@
+use std::{
+    cell::RefCell,
+    path::{Path, PathBuf},
+    rc::Rc,
+};
+
+use reqwest::{
+    header::{self, HeaderMap},
+    StatusCode,
+};
+use serde_json::{json, Value};
+use tokio::time::Duration;
+
+use crate::{config::ArtifactsConfig, perms, results::RunReport, secret::SecretString};
+
+const LATEST: &str = "latest";
+
+// 一次签到的排查材料目录：创建时确定目录名，运行结束后写入结果、报告、HAR 和响应内容，再更新 latest 并清理旧目录
+pub struct RunArtifacts {
+    root: PathBuf,
+    dir: PathBuf,
+    keep: usize,
+    har: Rc<Har>,
+}
+
+impl RunArtifacts {
+    // 同一秒内开始的运行（如多个配置共用 dir）加上序号
+    pub fn create(config: &ArtifactsConfig) -> std::io::Result<Self> {
+        let root = PathBuf::from(&config.dir);
+        std::fs::create_dir_all(&root)?;
+        let name = chrono::Local::now().format("%Y%m%d-%H%M%S").to_string();
+        let mut dir = root.join(&name);
+        let mut sequence = 1;
+        loop {
+            match std::fs::create_dir(&dir) {
+                Ok(()) => break,
+                Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => {
+                    sequence += 1;
+                    dir = root.join(format!("{}-{}", name, sequence));
+                }
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(Self { root, dir, keep: config.keep, har: Rc::new(Har::default()) })
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    pub fn har(&self) -> Rc<Har> {
+        self.har.clone()
+    }
+
+    pub fn finish(&self, report: &RunReport) -> Result<(), Box<dyn std::error::Error>> {
+        perms::write_private(self.dir.join("results.json"), serde_json::to_string_pretty(report)?)?;
+        perms::write_private(self.dir.join("report.txt"), format!("{}\n{}\n", report.to_text(), report.summary().to_text()))?;
+        let entries = self.har.entries.borrow();
+        let har = json!({
+            "log": {
+                "version": "1.2",
+                "creator": {"name": env!("CARGO_PKG_NAME"), "version": env!("CARGO_PKG_VERSION")},
+                "entries": entries.iter().map(|(_, entry, _)| entry).collect::<Vec<_>>(),
+            }
+        });
+        perms::write_private(self.dir.join("requests.har"), serde_json::to_string_pretty(&har)?)?;
+        let bodies = self.dir.join("bodies");
+        for (index, (account, _, body)) in entries.iter().enumerate() {
+            if let Some(body) = body {
+                std::fs::create_dir_all(&bodies)?;
+                perms::write_private(bodies.join(format!("{:03}-{}.txt", index + 1, file_name(account))), body)?;
+            }
+        }
+        self.link_latest()?;
+        self.prune()
+    }
+
+    // 先建临时链接再改名覆盖，latest 任何时刻都指向一个完整的目录；非 Unix 平台写入目录名
+    fn link_latest(&self) -> std::io::Result<()> {
+        let name = self.dir.file_name().unwrap_or_default();
+        let latest = self.root.join(LATEST);
+        #[cfg(unix)]
+        {
+            let tmp = self.root.join(format!(".{}", LATEST));
+            let _ = std::fs::remove_file(&tmp);
+            std::os::unix::fs::symlink(name, &tmp)?;
+            std::fs::rename(&tmp, &latest)
+        }
+        #[cfg(not(unix))]
+        {
+            std::fs::write(&latest, name.to_string_lossy().as_bytes())
+        }
+    }
+
+    // 只清理按时间命名的目录，目录名按时间排序，保留最近 keep 个
+    fn prune(&self) -> Result<(), Box<dyn std::error::Error>> {
+        let mut runs: Vec<PathBuf> = std::fs::read_dir(&self.root)?
+            .filter_map(Result::ok)
+            .filter(|entry| entry.file_type().is_ok_and(|kind| kind.is_dir()))
+            .filter(|entry| {
+                let name = entry.file_name().to_string_lossy().into_owned();
+                name.get(..15).is_some_and(|stamp| chrono::NaiveDateTime::parse_from_str(stamp, "%Y%m%d-%H%M%S").is_ok())
+            })
+            .map(|entry| entry.path())
+            .collect();
+        runs.sort();
+        let excess = runs.len().saturating_sub(self.keep);
+        for dir in runs.into_iter().take(excess) {
+            std::fs::remove_dir_all(&dir).map_err(|e| format!("failed to remove {}: {}", dir.display(), e))?;
+        }
+        Ok(())
+    }
+}
+
+// 签到请求的记录，运行结束后写成 HAR 1.2；每项为 (账户, HAR entry, 响应内容)
+#[derive(Default)]
+pub struct Har {
+    entries: RefCell<Vec<(String, Value, Option<String>)>>,
+}
+
+pub struct HarEntry<'a> {
+    pub account: &'a str,
+    pub started: chrono::DateTime<chrono::Local>,
+    pub elapsed: Duration,
+    pub url: &'a str,
+    pub headers: &'a HeaderMap,
+    pub body: &'a str,
+    // 账户的 cookie 和 token，写入前从请求头、请求体和响应中替换掉
+    pub secrets: &'a [SecretString],
+    // 收到响应时为状态码、响应头和脱敏后的内容，请求失败时为错误信息
+    pub response: Result<(StatusCode, &'a HeaderMap, String), String>,
+}
+
+impl Har {
+    pub fn record(&self, entry: HarEntry) {
+        let elapsed = entry.elapsed.as_millis() as u64;
+        let redact = |text: &str| entry.secrets.iter().fold(text.to_string(), |text, secret| secret.redact(&text));
+        let (response, body) = match entry.response.map(|(status, headers, body)| (status, headers, redact(&body))) {
+            Ok((status, headers, body)) => (json!({
+                "status": status.as_u16(),
+                "statusText": status.canonical_reason().unwrap_or(""),
+                "httpVersion": "HTTP/1.1",
+                "cookies": [],
+                "headers": har_headers(headers, redact),
+                "content": {
+                    "size": body.len(),
+                    "mimeType": content_type(headers),
+                    "text": body,
+                },
+                "redirectURL": "",
+                "headersSize": -1,
+                "bodySize": body.len(),
+            }), Some(body)),
+            Err(error) => (json!({
+                "status": 0,
+                "statusText": "",
+                "httpVersion": "HTTP/1.1",
+                "cookies": [],
+                "headers": [],
+                "content": {"size": 0, "mimeType": ""},
+                "redirectURL": "",
+                "headersSize": -1,
+                "bodySize": -1,
+                "_error": redact(&error),
+            }), None),
+        };
+        let value = json!({
+            "startedDateTime": entry.started.to_rfc3339(),
+            "time": elapsed,
+            "_account": entry.account,
+            "request": {
+                "method": "POST",
+                "url": entry.url,
+                "httpVersion": "HTTP/1.1",
+                "cookies": [],
+                "headers": har_headers(entry.headers, redact),
+                "queryString": [],
+                "postData": {"mimeType": content_type(entry.headers), "text": redact(entry.body)},
+                "headersSize": -1,
+                "bodySize": entry.body.len(),
+            },
+            "response": response,
+            "cache": {},
+            "timings": {"send": 0, "wait": elapsed, "receive": 0},
+        });
+        self.entries.borrow_mut().push((entry.account.to_string(), value, body));
+    }
+}
+
+// cookie、set-cookie 和认证类请求头含会话信息或凭据，只保留名称；其余请求头的值中出现的账户凭据同样替换掉
+fn har_headers(headers: &HeaderMap, redact: impl Fn(&str) -> String) -> Vec<Value> {
+    headers.iter()
+        .map(|(name, value)| {
+            let value = if sensitive(name) {
+                "[REDACTED]".into()
+            } else {
+                redact(&String::from_utf8_lossy(value.as_bytes()))
+            };
+            json!({"name": name.as_str(), "value": value})
+        })
+        .collect()
+}
+
+fn sensitive(name: &header::HeaderName) -> bool {
+    let name = name.as_str();
+    [header::COOKIE, header::SET_COOKIE, header::AUTHORIZATION, header::PROXY_AUTHORIZATION].iter().any(|sensitive| sensitive == name)
+        || ["token", "secret", "api-key", "apikey", "signature", "session"].iter().any(|part| name.contains(part))
+}
+
+fn content_type(headers: &HeaderMap) -> String {
+    headers.get(header::CONTENT_TYPE)
+        .map(|value| String::from_utf8_lossy(value.as_bytes()).into_owned())
+        .unwrap_or_default()
+}
+
+fn file_name(account: &str) -> String {
+    account.chars()
+        .map(|c| if c.is_ascii_alphanumeric() || matches!(c, '@' | '.' | '-' | '_') { c } else { '_' })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn har_redacts_account_secrets() {
+        let har = Har::default();
+        let mut headers = HeaderMap::new();
+        headers.insert(header::COOKIE, "koa:sess=cookie-value".parse().unwrap());
+        headers.insert("x-auth-token", "plain-token".parse().unwrap());
+        headers.insert(header::REFERER, "https://example.com/?t=secret-token-value".parse().unwrap());
+        let mut response_headers = HeaderMap::new();
+        response_headers.insert(header::SET_COOKIE, "koa:sess=next; path=/".parse().unwrap());
+        let secrets = [SecretString::new("koa:sess=cookie-value"), SecretString::new("secret-token-value")];
+        har.record(HarEntry {
+            account: "a@example.com",
+            started: chrono::Local::now(),
+            elapsed: Duration::from_millis(5),
+            url: "https://example.com/api/user/checkin",
+            headers: &headers,
+            body: r#"{"token":"secret-token-value"}"#,
+            secrets: &secrets,
+            response: Ok((StatusCode::OK, &response_headers, r#"{"echo":"secret-token-value"}"#.to_string())),
+        });
+        har.record(HarEntry {
+            account: "a@example.com",
+            started: chrono::Local::now(),
+            elapsed: Duration::from_millis(5),
+            url: "https://example.com/api/user/checkin",
+            headers: &headers,
+            body: "",
+            secrets: &secrets,
+            response: Err("error sending request with token secret-token-value".to_string()),
+        });
+
+        let entries = har.entries.borrow();
+        let recorded = serde_json::to_string(&entries.iter().map(|(_, entry, _)| entry).collect::<Vec<_>>()).unwrap();
+        for secret in ["cookie-value", "plain-token", "secret-token-value", "koa:sess=next"] {
+            assert!(!recorded.contains(secret), "{} in {}", secret, recorded);
+        }
+        assert_eq!(entries[0].1["request"]["postData"]["text"], r#"{"token":"[REDACTED]"}"#);
+        assert_eq!(entries[0].1["request"]["headers"][2]["value"], "https://example.com/?t=[REDACTED]");
+        assert_eq!(entries[0].2.as_deref(), Some(r#"{"echo":"[REDACTED]"}"#));
+    }
+}
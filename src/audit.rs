@@ -0,0 +1,75 @@
+use serde::Serialize;
+use std::io::Write;
+
+use crate::{cookie_pool, logger, secret::SecretString};
+
+// 配置修改记录（JSON Lines），多人共用的部署中用于追查是谁在何时改了什么；cookie 等敏感值只记录哈希前缀
+#[derive(Debug, Serialize)]
+pub struct AuditEntry {
+    pub time: String,
+    pub user: String,
+    pub command: String,
+    pub account: String,
+    pub file: String,
+    pub changes: Vec<FieldChange>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct FieldChange {
+    pub field: String,
+    pub old: Option<String>,
+    pub new: Option<String>,
+}
+
+impl FieldChange {
+    pub fn new(field: &str, old: Option<&str>, new: Option<&str>) -> Self {
+        Self {
+            field: field.to_string(),
+            old: old.map(str::to_string),
+            new: new.map(str::to_string),
+        }
+    }
+
+    pub fn secret(field: &str, old: Option<&SecretString>, new: Option<&SecretString>) -> Self {
+        Self {
+            field: field.to_string(),
+            old: old.map(fingerprint),
+            new: new.map(fingerprint),
+        }
+    }
+}
+
+impl AuditEntry {
+    pub fn new(command: &str, account: &str, file: &str, changes: Vec<FieldChange>) -> Self {
+        Self {
+            time: chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string(),
+            user: current_user(),
+            command: command.to_string(),
+            account: account.to_string(),
+            file: file.to_string(),
+            changes,
+        }
+    }
+}
+
+pub fn append(path: &str, entry: &AuditEntry) -> Result<(), Box<dyn std::error::Error>> {
+    let line = serde_json::to_string(entry)?;
+    logger::ensure_parent_dir(std::path::Path::new(path))?;
+    let mut file = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)?;
+    writeln!(file, "{}", line)?;
+    Ok(())
+}
+
+fn fingerprint(secret: &SecretString) -> String {
+    format!("sha256:{}", &cookie_pool::hash(secret)[..16])
+}
+
+// sudo 执行时记录原始用户
+fn current_user() -> String {
+    ["SUDO_USER", "USER", "USERNAME"].iter()
+        .find_map(|name| std::env::var(name).ok().filter(|user| !user.is_empty()))
+        .unwrap_or_else(|| "unknown".to_string())
+}
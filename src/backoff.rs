@@ -0,0 +1,97 @@
+use tokio::time::Duration;
+
+use crate::config::BackoffKind;
+
+// 两次尝试之间的等待时间；retry 为即将进行的第几次重试（从 1 开始，续跑时包含之前运行已用掉的次数）
+pub trait BackoffStrategy {
+    fn delay(&self, retry: u32) -> Duration;
+}
+
+// 每次等待相同的 base
+pub struct Constant {
+    pub base: Duration,
+}
+
+impl BackoffStrategy for Constant {
+    fn delay(&self, _retry: u32) -> Duration {
+        self.base
+    }
+}
+
+// base、2×base、4×base……，不超过 max
+pub struct Exponential {
+    pub base: Duration,
+    pub max: Option<Duration>,
+}
+
+impl BackoffStrategy for Exponential {
+    fn delay(&self, retry: u32) -> Duration {
+        let factor = 1u32.checked_shl(retry.saturating_sub(1)).unwrap_or(u32::MAX);
+        cap(self.base.saturating_mul(factor), self.max)
+    }
+}
+
+// base、2×base、3×base、5×base、8×base……，增长比指数退避平缓，不超过 max
+pub struct Fibonacci {
+    pub base: Duration,
+    pub max: Option<Duration>,
+}
+
+impl BackoffStrategy for Fibonacci {
+    fn delay(&self, retry: u32) -> Duration {
+        let (mut current, mut next) = (1u32, 2u32);
+        for _ in 1..retry {
+            (current, next) = (next, current.saturating_add(next));
+        }
+        cap(self.base.saturating_mul(current), self.max)
+    }
+}
+
+pub fn build(kind: BackoffKind, retry_delay: u64, max_retry_delay: Option<u64>) -> Box<dyn BackoffStrategy> {
+    let base = Duration::from_secs(retry_delay);
+    let max = max_retry_delay.map(Duration::from_secs);
+    match kind {
+        BackoffKind::Constant => Box::new(Constant { base }),
+        BackoffKind::Exponential => Box::new(Exponential { base, max }),
+        BackoffKind::Fibonacci => Box::new(Fibonacci { base, max }),
+    }
+}
+
+fn cap(delay: Duration, max: Option<Duration>) -> Duration {
+    max.map_or(delay, |max| delay.min(max))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn delays(kind: BackoffKind, max: Option<u64>) -> Vec<u64> {
+        let strategy = build(kind, 2, max);
+        (1..=6).map(|retry| strategy.delay(retry).as_secs()).collect()
+    }
+
+    #[test]
+    fn constant() {
+        assert_eq!(delays(BackoffKind::Constant, Some(1)), [2, 2, 2, 2, 2, 2]);
+    }
+
+    #[test]
+    fn exponential() {
+        assert_eq!(delays(BackoffKind::Exponential, None), [2, 4, 8, 16, 32, 64]);
+        assert_eq!(delays(BackoffKind::Exponential, Some(10)), [2, 4, 8, 10, 10, 10]);
+    }
+
+    #[test]
+    fn fibonacci() {
+        assert_eq!(delays(BackoffKind::Fibonacci, None), [2, 4, 6, 10, 16, 26]);
+        assert_eq!(delays(BackoffKind::Fibonacci, Some(12)), [2, 4, 6, 10, 12, 12]);
+    }
+
+    #[test]
+    fn large_retries_saturate() {
+        let max = Duration::from_secs(3600);
+        assert_eq!(build(BackoffKind::Exponential, 2, Some(3600)).delay(100), max);
+        assert_eq!(build(BackoffKind::Fibonacci, 2, Some(3600)).delay(100), max);
+        assert_eq!(build(BackoffKind::Exponential, 2, None).delay(0), Duration::from_secs(2));
+    }
+}
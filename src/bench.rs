@@ -0,0 +1,102 @@
+use futures::future::join_all;
+use std::time::{Duration, Instant};
+
+use crate::{
+    cli::BenchArgs,
+    config::{Account, NotifyOn},
+    history::HistoryStore,
+    hook::Hooks,
+    logger::FileLogger,
+    mock::{MockMode, MockServer},
+    runner::Pipeline,
+    secret::SecretString,
+    service::CheckinService,
+};
+
+pub async fn run(args: &BenchArgs) -> Result<(), Box<dyn std::error::Error>> {
+    if !args.mock {
+        return Err("bench 目前仅支持 --mock 模式，避免对真实接口施压".into());
+    }
+
+    let server = MockServer::start(MockMode::Success).await?;
+    let dir = std::env::temp_dir().join(format!("web-bench-{}", std::process::id()));
+    std::fs::create_dir_all(&dir)?;
+
+    let client = reqwest::Client::builder().build()?;
+    let logger = Box::new(FileLogger::new(dir.join("checkin.log")));
+    let pipeline = Pipeline {
+        provider: None,
+        service: CheckinService::new(client, logger, 1, 0).with_base_url(server.base_url()),
+        history: HistoryStore::new(dir.join("history.jsonl")),
+        tz: chrono_tz::Asia::Shanghai,
+        run_state: None,
+        state: None,
+        jitter: None,
+        spread: None,
+        started: tokio::time::Instant::now(),
+        hooks: Hooks::default(),
+        notify_on: NotifyOn::default(),
+        failure_threshold: 1,
+    };
+
+    let accounts = (0..args.accounts).map(|i| Account {
+        email: format!("bench{}@example.com", i),
+        cookie: SecretString::new(format!("koa:sess=bench{}", i)),
+        ..Default::default()
+    });
+
+    let started = Instant::now();
+    let futures = accounts.map(|account| {
+        let pipeline = &pipeline;
+        async move {
+            let account_started = Instant::now();
+            let result = pipeline.run_account(account).await;
+            (account_started.elapsed(), result)
+        }
+    });
+    let outcomes = join_all(futures).await;
+    let elapsed = started.elapsed();
+
+    let _ = std::fs::remove_dir_all(&dir);
+
+    let mut totals = Vec::with_capacity(outcomes.len());
+    let mut requests = Vec::with_capacity(outcomes.len());
+    let mut parses = Vec::with_capacity(outcomes.len());
+    let mut logs = Vec::with_capacity(outcomes.len());
+    let mut failures = 0;
+    for (total, result) in outcomes {
+        totals.push(total);
+        match result.timings {
+            Some(timings) => {
+                requests.push(timings.request);
+                parses.push(timings.parse);
+                logs.push(timings.log);
+            }
+            None => failures += 1,
+        }
+    }
+
+    println!();
+    println!("账户数: {}, 失败: {}, 总耗时: {:.3}s, 吞吐: {:.1} 账户/秒",
+        args.accounts, failures, elapsed.as_secs_f64(),
+        args.accounts as f64 / elapsed.as_secs_f64());
+    println!("{:<10} {:>10} {:>10} {:>10}", "阶段", "p50(ms)", "p95(ms)", "max(ms)");
+    for (name, samples) in [("request", requests), ("parse", parses), ("log", logs), ("total", totals)] {
+        print_stage(name, samples);
+    }
+    Ok(())
+}
+
+fn print_stage(name: &str, mut samples: Vec<Duration>) {
+    if samples.is_empty() {
+        println!("{:<10} {:>10} {:>10} {:>10}", name, "-", "-", "-");
+        return;
+    }
+    samples.sort();
+    let percentile = |p: f64| {
+        let idx = ((samples.len() - 1) as f64 * p).round() as usize;
+        samples[idx].as_secs_f64() * 1000.0
+    };
+    println!("{:<10} {:>10.3} {:>10.3} {:>10.3}",
+        name, percentile(0.50), percentile(0.95), samples[samples.len() - 1].as_secs_f64() * 1000.0);
+}
@@ -0,0 +1,160 @@
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, fmt};
+
+use crate::config::{ErrorCatalogConfig, OutcomePolicy};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Outcome {
+    AlreadyCheckedIn,
+    AccountExpired,
+    Banned,
+    Maintenance,
+    VerificationRequired,
+}
+
+impl Outcome {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Outcome::AlreadyCheckedIn => "already_checked_in",
+            Outcome::AccountExpired => "account_expired",
+            Outcome::Banned => "banned",
+            Outcome::Maintenance => "maintenance",
+            Outcome::VerificationRequired => "verification_required",
+        }
+    }
+
+    pub fn describe(&self, account: &str) -> String {
+        match self {
+            Outcome::AlreadyCheckedIn => format!("账户 {} 今日已签到", account),
+            Outcome::AccountExpired => format!("账户 {} 登录已失效或账户已过期，请更新 cookie", account),
+            Outcome::Banned => format!("账户 {} 可能已被封禁，请登录网站确认", account),
+            Outcome::Maintenance => format!("服务端维护中，账户 {} 本次签到未完成", account),
+            Outcome::VerificationRequired => format!("账户 {} 需要人工验证（验证码/二次验证）", account),
+        }
+    }
+
+    fn default_policy(&self) -> Policy {
+        let (retry, notify) = match self {
+            Outcome::AlreadyCheckedIn => (false, false),
+            Outcome::AccountExpired => (false, true),
+            Outcome::Banned => (false, true),
+            Outcome::Maintenance => (true, false),
+            Outcome::VerificationRequired => (false, true),
+        };
+        Policy { retry, notify }
+    }
+}
+
+impl fmt::Display for Outcome {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Policy {
+    pub retry: bool,
+    pub notify: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Classification {
+    pub outcome: Outcome,
+    pub policy: Policy,
+}
+
+#[derive(Debug, Clone)]
+struct Rule {
+    code: Option<i64>,
+    status: Option<u16>,
+    message: Option<String>,
+    outcome: Outcome,
+}
+
+impl Rule {
+    fn message(outcome: Outcome, markers: &'static [&'static str]) -> impl Iterator<Item = Rule> {
+        markers.iter().map(move |marker| Rule {
+            code: None,
+            status: None,
+            message: Some(marker.to_string()),
+            outcome,
+        })
+    }
+
+    fn matches(&self, status: u16, code: Option<i64>, message: &str) -> bool {
+        self.code.is_none_or(|expected| code == Some(expected))
+            && self.status.is_none_or(|expected| status == expected)
+            && self.message.as_ref().is_none_or(|expected| message.contains(expected.as_str()))
+    }
+}
+
+// 已知的服务端错误码/错误信息到结果类型的映射；用户配置的规则优先于内置规则
+pub struct ErrorCatalog {
+    rules: Vec<Rule>,
+    policies: BTreeMap<Outcome, OutcomePolicy>,
+}
+
+impl Default for ErrorCatalog {
+    fn default() -> Self {
+        Self::new(&ErrorCatalogConfig::default())
+    }
+}
+
+impl ErrorCatalog {
+    pub fn new(config: &ErrorCatalogConfig) -> Self {
+        let mut rules: Vec<Rule> = config.rules.iter()
+            .map(|rule| Rule {
+                code: rule.code,
+                status: rule.status,
+                message: rule.message.as_ref().map(|message| message.to_lowercase()),
+                outcome: rule.outcome,
+            })
+            .collect();
+        rules.extend(Rule::message(Outcome::VerificationRequired,
+            &["captcha", "verification", "two-factor", "2fa", "验证码", "人机验证", "二次验证", "安全验证"]));
+        rules.extend(Rule::message(Outcome::AlreadyCheckedIn, &["try tomorrow", "already checked", "明天再来", "已签到"]));
+        rules.extend(Rule::message(Outcome::Banned, &["banned", "suspended", "封禁", "冻结"]));
+        rules.extend(Rule::message(Outcome::Maintenance, &["maintenance", "维护"]));
+        rules.push(Rule {
+            code: None,
+            status: Some(503),
+            message: None,
+            outcome: Outcome::Maintenance,
+        });
+        rules.push(Rule {
+            code: Some(-2),
+            status: None,
+            message: None,
+            outcome: Outcome::AccountExpired,
+        });
+        rules.extend(Rule::message(Outcome::AccountExpired, &["没有权限", "please login", "expired", "过期"]));
+        Self {
+            rules,
+            policies: config.policies.clone(),
+        }
+    }
+
+    pub fn classify(&self, status: u16, response_json: &serde_json::Value) -> Option<Classification> {
+        let code = response_json["code"].as_i64();
+        let message = response_json["message"].as_str().unwrap_or("").to_lowercase();
+        let outcome = match self.rules.iter().find(|rule| rule.matches(status, code, &message)) {
+            Some(rule) => rule.outcome,
+            None if !response_json["captcha"].is_null() => Outcome::VerificationRequired,
+            None => return None,
+        };
+        Some(Classification {
+            outcome,
+            policy: self.policy(outcome),
+        })
+    }
+
+    pub fn policy(&self, outcome: Outcome) -> Policy {
+        let mut policy = outcome.default_policy();
+        if let Some(configured) = self.policies.get(&outcome) {
+            policy.retry = configured.retry.unwrap_or(policy.retry);
+            policy.notify = configured.notify.unwrap_or(policy.notify);
+        }
+        policy
+    }
+}
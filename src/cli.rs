@@ -0,0 +1,457 @@
+use std::{
+    collections::HashSet,
+    path::{Path, PathBuf},
+};
+
+use clap::{Args, Parser, Subcommand};
+
+use crate::{
+    config::{default_config_path, Account, Config, Profile, DEFAULT_CONFIG_PATH},
+    output::OutputFormat,
+};
+
+// 不带子命令时执行签到，与 web checkin 相同；使用 web checkin 时签到参数写在子命令之后
+#[derive(Debug, Parser)]
+#[command(name = "web", version, about = "GLaDOS 自动签到")]
+pub struct Cli {
+    /// 配置文件路径，配置中的相对路径均相对于该文件所在目录；签到时可多次指定，或指定目录（其中每个 *.yaml/*.yml
+    /// 为一个配置，被其他配置 include 的文件除外），各配置同时运行，使用各自的日志、通知和历史。
+    /// 未指定时依次查找 ./config.yaml、$XDG_CONFIG_HOME/web/config.yaml（默认 ~/.config/web）、/etc/web/config.yaml
+    #[arg(short, long, global = true, env = "WEB_CONFIG")]
+    pub config: Vec<String>,
+    /// 使用配置文件中的指定 profile
+    #[arg(long, global = true)]
+    pub profile: Option<String>,
+    /// 把同组或其他用户可读的配置文件、include、accounts_file 和 signing_key 改为 0600 后退出
+    #[arg(long)]
+    pub fix_perms: bool,
+    #[command(flatten)]
+    pub checkin: CheckinArgs,
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+impl Cli {
+    // web init 写入的路径：未指定 --config 时为当前目录下的 config.yaml，不查找其他位置
+    pub fn init_path(&self) -> Result<String, Box<dyn std::error::Error>> {
+        match self.config.as_slice() {
+            [] => Ok(DEFAULT_CONFIG_PATH.to_string()),
+            [path] if !Path::new(path).is_dir() => Ok(path.clone()),
+            _ => Err("init writes a single --config file".into()),
+        }
+    }
+
+    // 签到以外的命令只针对一个配置文件
+    pub fn config_path(&self) -> Result<String, Box<dyn std::error::Error>> {
+        match self.config.as_slice() {
+            [] => default_config_path(),
+            [path] if !Path::new(path).is_dir() => Ok(path.clone()),
+            _ => Err("only checkin accepts several --config files or a directory".into()),
+        }
+    }
+
+    // 签到时的全部配置文件，目录展开为其中的 *.yaml/*.yml（按文件名排序）
+    pub fn config_paths(&self) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+        if self.config.is_empty() {
+            return Ok(vec![default_config_path()?]);
+        }
+        let mut paths = Vec::new();
+        for path in &self.config {
+            if Path::new(path).is_dir() {
+                paths.extend(config_dir(path)?);
+            } else {
+                paths.push(path.clone());
+            }
+        }
+        paths.dedup();
+        Ok(paths)
+    }
+}
+
+// 目录中的 *.yaml/*.yml 中被其他文件 include 的只提供账户，不作为单独的配置
+fn config_dir(dir: &str) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+    let mut files: Vec<PathBuf> = std::fs::read_dir(dir)
+        .map_err(|e| format!("failed to read {}: {}", dir, e))?
+        .filter_map(Result::ok)
+        .map(|entry| entry.path())
+        .filter(|path| path.is_file() && matches!(path.extension().and_then(|e| e.to_str()), Some("yaml" | "yml")))
+        .collect();
+    files.sort();
+    let mut included = HashSet::new();
+    for file in &files {
+        let Ok(content) = std::fs::read_to_string(file) else {
+            continue;
+        };
+        let Ok(value) = serde_yaml::from_str::<serde_yaml::Value>(&content) else {
+            continue;
+        };
+        if let Some(includes) = value.get("include").and_then(|include| include.as_sequence()) {
+            included.extend(includes.iter().filter_map(|include| include.as_str()).map(|include| Path::new(dir).join(include)));
+        }
+    }
+    let paths: Vec<String> = files.into_iter()
+        .filter(|file| !included.contains(file))
+        .map(|file| file.to_string_lossy().into_owned())
+        .collect();
+    if paths.is_empty() {
+        return Err(format!("no *.yaml config files in {}", dir).into());
+    }
+    Ok(paths)
+}
+
+#[derive(Debug, Args)]
+pub struct CheckinArgs {
+    /// 继续上次被中断的运行（即使不是同一天）
+    #[arg(long)]
+    pub resume: bool,
+    /// 即使当前时间不在 allowed_window 内也执行签到
+    #[arg(long)]
+    pub force: bool,
+    /// 向 notifiers 中的 email 发送一封测试汇总邮件后退出，不执行签到
+    #[arg(long)]
+    pub test_email: bool,
+    /// 常驻运行，按配置中的 schedule（cron 表达式，本地时间）定时签到，不再依赖系统 cron
+    #[arg(long)]
+    pub daemon: bool,
+    /// 与 --daemon 一起使用：接管正在运行的常驻进程（daemon 或 serve），通知其退出后由本进程继续
+    #[arg(long, requires = "daemon")]
+    pub takeover: bool,
+    /// 等待旧进程退出的最长秒数
+    #[arg(long, default_value_t = 3600, requires = "takeover")]
+    pub takeover_timeout: u64,
+    /// 运行过程中把事件（run_started、attempt、result、run_finished）以 JSON Lines 追加写入文件，- 为标准输出
+    #[arg(long, value_name = "PATH")]
+    pub events: Option<String>,
+    /// 在标准错误输出每一次签到尝试（含重试）的结果和耗时
+    #[arg(short, long)]
+    pub verbose: bool,
+    /// 试运行：逐个账户构造签到请求并写日志，用只读的状态接口验证 cookie，但不发送签到请求、不记录结果；有账户检查失败时以非零状态退出
+    #[arg(long, conflicts_with_all = ["daemon", "test_email", "events"])]
+    pub dry_run: bool,
+    /// 每个账户的结果（含 --dry-run 的检查结果）的输出格式；不是 table 时标准输出只有结果，过程提示写到标准错误
+    #[arg(long, value_enum, value_name = "FORMAT", default_value_t)]
+    pub output: OutputFormat,
+    #[command(flatten)]
+    pub overrides: RunOverrides,
+    #[command(flatten)]
+    pub filter: AccountFilter,
+}
+
+// 只为部分账户签到（含 --dry-run 和 --daemon），其余账户不参与本次运行，也不记录结果
+#[derive(Debug, Default, Args)]
+pub struct AccountFilter {
+    /// 只签到匹配的账户：邮箱或 <name>/<email>，支持通配符 * 和 ?，可多次指定
+    #[arg(long, value_name = "GLOB")]
+    pub only: Vec<String>,
+    /// 不签到匹配的账户，写法同 --only，可多次指定
+    #[arg(long, value_name = "GLOB")]
+    pub skip: Vec<String>,
+    /// 只签到带有该标签（账户的 tags）的账户，可多次指定，满足其一即可
+    #[arg(long, value_name = "TAG")]
+    pub tag: Vec<String>,
+}
+
+impl AccountFilter {
+    pub fn is_empty(&self) -> bool {
+        self.only.is_empty() && self.skip.is_empty() && self.tag.is_empty()
+    }
+
+    pub fn matches(&self, key: &str, account: &Account) -> bool {
+        let matched = |pattern: &String| glob_match(pattern, key) || glob_match(pattern, &account.email);
+        (self.only.is_empty() || self.only.iter().any(matched))
+            && !self.skip.iter().any(matched)
+            && (self.tag.is_empty() || self.tag.iter().any(|tag| account.tags.contains(tag)))
+    }
+
+    // 去掉不匹配的账户；没有账户匹配时报错，而不是什么都不做就成功退出
+    pub fn apply(&self, config: &mut Config) -> Result<(), Box<dyn std::error::Error>> {
+        if !self.retain(config)? {
+            return Err("no accounts match --only/--skip/--tag".into());
+        }
+        Ok(())
+    }
+
+    // 去掉不匹配的账户，返回是否还有账户
+    pub fn retain(&self, config: &mut Config) -> Result<bool, Box<dyn std::error::Error>> {
+        if self.is_empty() {
+            return Ok(true);
+        }
+        config.retain_accounts(|key, account| self.matches(key, account))?;
+        Ok(!config.provider_instances().is_empty())
+    }
+}
+
+// * 匹配任意长度的字符（含空），? 匹配单个字符
+fn glob_match(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+    let (mut p, mut t) = (0, 0);
+    let mut backtrack = None;
+    while t < text.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p] == text[t]) {
+            p += 1;
+            t += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            backtrack = Some((p, t));
+            p += 1;
+        } else if let Some((star, matched)) = backtrack {
+            // 让上一个 * 多匹配一个字符
+            p = star + 1;
+            t = matched + 1;
+            backtrack = Some((star, matched + 1));
+        } else {
+            return false;
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+// 仅对本次运行生效，优先于配置文件和 --profile
+#[derive(Debug, Args)]
+pub struct RunOverrides {
+    /// 覆盖 max_retries
+    #[arg(long, value_name = "N")]
+    pub retries: Option<u32>,
+    /// 覆盖 retry_delay（秒）
+    #[arg(long, value_name = "SECS")]
+    pub retry_delay: Option<u64>,
+    /// 覆盖 concurrency
+    #[arg(long, value_name = "N")]
+    pub concurrency: Option<usize>,
+    /// 覆盖 account_deadline（秒）
+    #[arg(long, value_name = "SECS")]
+    pub account_deadline: Option<u64>,
+    /// 覆盖 run_deadline（秒）
+    #[arg(long, value_name = "SECS")]
+    pub run_deadline: Option<u64>,
+}
+
+impl RunOverrides {
+    pub fn profile(&self) -> Profile {
+        Profile {
+            max_retries: self.retries,
+            retry_delay: self.retry_delay,
+            concurrency: self.concurrency,
+            account_deadline: self.account_deadline,
+            run_deadline: self.run_deadline,
+            ..Profile::default()
+        }
+    }
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// 执行签到（默认）
+    Checkin(CheckinArgs),
+    /// 对完整签到流程进行压测
+    Bench(BenchArgs),
+    /// 查看各账户状态（默认读取本地历史，不执行签到）；有未结束的运行时在标准错误输出其进度
+    Status(StatusArgs),
+    /// 查看签到历史记录（--remote 时查询服务端的积分流水）
+    History(HistoryArgs),
+    /// 列出配置中的所有账户
+    ListAccounts(OutputArgs),
+    /// 按账户汇总历史签到统计
+    Stats(OutputArgs),
+    /// 将今天的结果与之前的运行比较，列出新增失败、余额下降等变化
+    Diff(DiffArgs),
+    /// 根据历史记录核对积分流水，找出无对应变化的余额跳变和漏签的日期
+    Reconcile(ReconcileArgs),
+    /// 启动 HTTP 触发服务（POST /trigger 执行签到，GET /status 查看最近结果）
+    Serve(ServeArgs),
+    /// 管理账户：添加、删除、停用/启用、更新 cookie 和人工处理标记；修改直接写回配置文件并保留注释
+    #[command(visible_alias = "accounts")]
+    Account(AccountArgs),
+    /// 打印将要发送的签到请求（方法、URL、请求头、请求体），不实际发送；cookie 已脱敏
+    ShowRequest(ShowRequestArgs),
+    /// 通知渠道相关操作
+    Notify(NotifyArgs),
+    /// 检查配置（不访问网络）：不合法的值、未知的键、重复的账户、格式错误的 cookie、无法写入的日志目录报错并以非零状态退出，并对重复的 cookie、过高的重试次数、未配置通知、其他用户可读的配置文件等给出警告
+    #[command(visible_alias = "validate")]
+    LintConfig,
+    /// 诊断运行环境：配置、文件权限、日志文件和磁盘空间、代理、服务端域名解析和连通性、各账户 cookie 是否有效（只读查询，不签到）、状态存储、通知渠道是否可达，输出检查清单；有失败项时以非零状态退出
+    Doctor(DoctorArgs),
+    /// 交互式生成配置文件：依次询问账户（验证 cookie）、重试设置和通知渠道，写入 --config 指定的路径（默认 ./config.yaml）
+    Init(InitArgs),
+}
+
+#[derive(Debug, Args)]
+pub struct InitArgs {
+    /// 覆盖已存在的配置文件
+    #[arg(long)]
+    pub force: bool,
+    /// 不向服务端验证 cookie（离线时使用）
+    #[arg(long)]
+    pub no_verify: bool,
+}
+
+#[derive(Debug, Args)]
+pub struct StatusArgs {
+    /// 向服务端查询剩余天数、套餐、邮箱验证状态和积分（不签到）
+    #[arg(long)]
+    pub remote: bool,
+    #[command(flatten)]
+    pub output: OutputArgs,
+}
+
+#[derive(Debug, Args)]
+pub struct DoctorArgs {
+    /// 向每个通知渠道实际发送一条测试消息（与 web notify test 相同），报告各渠道的耗时和错误，代替只检查连通性
+    #[arg(long)]
+    pub send_test: bool,
+    #[command(flatten)]
+    pub output: OutputArgs,
+}
+
+#[derive(Debug, Args)]
+pub struct OutputArgs {
+    /// 输出格式
+    #[arg(long, value_enum, default_value_t)]
+    pub format: OutputFormat,
+}
+
+#[derive(Debug, Args)]
+pub struct HistoryArgs {
+    /// 只显示指定账户（邮箱或 <name>/<email>）
+    #[arg(long)]
+    pub account: Option<String>,
+    /// 最多显示最近的记录条数（--remote 时为每个账户的条数）
+    #[arg(long, default_value_t = 20)]
+    pub limit: usize,
+    /// 只显示最近几天（含今天）的记录
+    #[arg(long)]
+    pub days: Option<u32>,
+    /// 向服务端查询各账户的积分流水（签到等积分变化，时间按签到重置时区显示），不签到
+    #[arg(long)]
+    pub remote: bool,
+    #[command(flatten)]
+    pub output: OutputArgs,
+}
+
+#[derive(Debug, Args)]
+pub struct ShowRequestArgs {
+    /// 账户（邮箱或 <name>/<email>）
+    #[arg(long)]
+    pub account: String,
+}
+
+#[derive(Debug, Args)]
+pub struct ReconcileArgs {
+    /// 只核对指定账户（邮箱或 <name>/<email>）
+    #[arg(long)]
+    pub account: Option<String>,
+    /// 同时列出核对一致的记录
+    #[arg(long)]
+    pub all: bool,
+    #[command(flatten)]
+    pub output: OutputArgs,
+}
+
+#[derive(Debug, Args)]
+pub struct DiffArgs {
+    /// 比较对象：yesterday、previous（今天之前最近一次运行）或 YYYY-MM-DD
+    #[arg(long, default_value = "yesterday")]
+    pub against: String,
+    /// 只显示退化（新增失败、余额下降）
+    #[arg(long)]
+    pub regressions: bool,
+    #[command(flatten)]
+    pub output: OutputArgs,
+}
+
+#[derive(Debug, Args)]
+pub struct BenchArgs {
+    /// 模拟账户数量
+    #[arg(long, default_value_t = 500)]
+    pub accounts: usize,
+    /// 使用进程内模拟服务而非真实接口
+    #[arg(long)]
+    pub mock: bool,
+}
+
+#[derive(Debug, Args)]
+pub struct ServeArgs {
+    /// 监听地址，覆盖配置中的 trigger.listen
+    #[arg(long)]
+    pub listen: Option<String>,
+    /// 接管正在运行的 serve 进程：通知其完成进行中的运行后退出，再由本进程继续提供服务
+    #[arg(long)]
+    pub takeover: bool,
+    /// 等待旧进程退出的最长秒数
+    #[arg(long, default_value_t = 3600)]
+    pub takeover_timeout: u64,
+}
+
+#[derive(Debug, Args)]
+pub struct AccountArgs {
+    #[command(subcommand)]
+    pub command: AccountCommand,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum AccountCommand {
+    /// 列出配置中的所有账户（同 list-accounts）
+    List(OutputArgs),
+    /// 向主配置文件的 accounts 列表添加账户；未指定 --cookie 时从标准输入读取，通过状态查询验证后写入
+    Add {
+        /// 账户邮箱
+        #[arg(long)]
+        email: String,
+        /// 账户 cookie（会留在 shell 历史中，建议省略后从标准输入输入）
+        #[arg(long)]
+        cookie: Option<String>,
+        /// 不联网验证，直接保存
+        #[arg(long)]
+        no_verify: bool,
+    },
+    /// 从配置文件中删除账户
+    Remove {
+        /// 账户邮箱；非默认服务实例的账户写作 <name>/<email>
+        account: String,
+        /// 移到主配置的 archived_accounts 中而不是直接删除：不再签到，stats 等仍显示其历史，可用 restore 恢复
+        #[arg(long)]
+        archive: bool,
+    },
+    /// 把 archived_accounts 中的账户恢复到主配置的 accounts 列表
+    Restore {
+        /// 账户邮箱
+        account: String,
+    },
+    /// 停用账户：保留在配置中，但不再签到
+    Disable {
+        /// 账户邮箱；非默认服务实例的账户写作 <name>/<email>
+        account: String,
+    },
+    /// 重新启用停用的账户
+    Enable {
+        /// 账户邮箱；非默认服务实例的账户写作 <name>/<email>
+        account: String,
+    },
+    /// 列出等待人工验证的账户
+    Flags(OutputArgs),
+    /// 完成验证后清除账户的待处理标记，恢复自动签到
+    ClearFlag {
+        /// 账户邮箱；非默认服务实例的账户写作 <name>/<email>
+        account: String,
+    },
+    /// 更新账户的 cookie：从标准输入读取（终端中会提示输入），通过状态查询验证后写回配置文件
+    SetCookie {
+        /// 账户邮箱；非默认服务实例的账户写作 <name>/<email>
+        account: String,
+        /// 不联网验证，直接保存
+        #[arg(long)]
+        no_verify: bool,
+    },
+}
+
+#[derive(Debug, Args)]
+pub struct NotifyArgs {
+    #[command(subcommand)]
+    pub command: NotifyCommand,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum NotifyCommand {
+    /// 向每个通知渠道发送一条测试消息（email 为测试汇总邮件）并列出结果，不执行签到；有渠道失败时以非零状态退出
+    Test(OutputArgs),
+}
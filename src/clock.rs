@@ -0,0 +1,19 @@
+use chrono::{DateTime, NaiveDate, NaiveTime, Utc};
+use chrono_tz::Tz;
+
+pub fn provider_now(tz: Tz) -> DateTime<Tz> {
+    Utc::now().with_timezone(&tz)
+}
+
+pub fn provider_today(tz: Tz) -> NaiveDate {
+    provider_now(tz).date_naive()
+}
+
+// start > end 表示跨越午夜的窗口，例如 22:00-02:00
+pub fn within(time: NaiveTime, start: NaiveTime, end: NaiveTime) -> bool {
+    if start <= end {
+        start <= time && time < end
+    } else {
+        time >= start || time < end
+    }
+}
@@ -1,42 +1,1381 @@
-use serde::{Deserialize, Serialize};
-use std::fs;
+use crate::{
+    accounts::{AccountFileReader, AccountLine, AccountResult},
+    backoff::{self, BackoffStrategy},
+    catalog::Outcome,
+    clock,
+    cookie_pool,
+    cron,
+    logger::FileLogger,
+    rate_limit::RateLimiter,
+    redact::BodyFilter,
+    secret::SecretString,
+    template,
+};
+use chrono::NaiveTime;
+use chrono_tz::Tz;
+use rand::{seq::SliceRandom, Rng};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    fs,
+    path::Path,
+    sync::Arc,
+};
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+pub const DEFAULT_CONFIG_PATH: &str = "config.yaml";
+
+// 使用 accounts_file 且未设置 concurrency 时同时处理的账户数，避免把整个文件读入内存并同时发出全部请求
+const DEFAULT_FILE_CONCURRENCY: usize = 16;
+
+// 未指定 --config 且没有设置 WEB_CONFIG 时依次查找：当前目录、$XDG_CONFIG_HOME/web（默认 ~/.config/web）、/etc/web
+pub fn default_config_path() -> Result<String, Box<dyn std::error::Error>> {
+    let mut candidates = vec![DEFAULT_CONFIG_PATH.to_string()];
+    let config_home = std::env::var("XDG_CONFIG_HOME").ok().filter(|dir| !dir.is_empty())
+        .or_else(|| std::env::var("HOME").ok().filter(|home| !home.is_empty()).map(|home| format!("{}/.config", home)));
+    if let Some(config_home) = config_home {
+        candidates.push(format!("{}/web/{}", config_home, DEFAULT_CONFIG_PATH));
+    }
+    candidates.push(format!("/etc/web/{}", DEFAULT_CONFIG_PATH));
+    candidates.iter()
+        .find(|path| Path::new(path.as_str()).is_file())
+        .cloned()
+        .ok_or_else(|| format!("no config file found (searched {}), pass --config or set WEB_CONFIG", candidates.join(", ")).into())
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
 pub struct Account {
     pub email: String,
-    pub cookie: String,
+    pub cookie: SecretString,
+    // 备用 cookie；按历史表现自动优先使用最近成功且未失效的一个
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub cookies: Vec<SecretString>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cookie_updated_at: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub body_template: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub token: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub on_success: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub on_failure: Option<String>,
+    // web --daemon 下该账户自己的计划，覆盖全局 schedule
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub schedule: Option<String>,
+    // 解释该账户 schedule 的时区（IANA 名称），默认为本地时间
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub schedule_timezone: Option<String>,
+    // 停用的账户保留在配置中，但不签到、不参与计划和 cookie 重复检查
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub disabled: bool,
+    // 用于 web --tag 选择账户
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+}
+
+// web account remove --archive 移出的账户：不再签到，历史和账户状态保留，web account restore 可恢复
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ArchivedAccount {
+    pub email: String,
+    pub cookie: SecretString,
+    // 原来所在的服务实例（providers 中的 name），默认实例为空
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub provider: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cookie_updated_at: Option<String>,
+    pub archived_at: String,
+}
+
+impl ArchivedAccount {
+    pub fn key(&self) -> String {
+        account_key(self.provider.as_deref(), &self.email)
+    }
+}
+
+impl Account {
+    pub fn schedule_tz(&self) -> Result<Option<Tz>, Box<dyn std::error::Error>> {
+        self.schedule_timezone.as_deref()
+            .map(|timezone| timezone.parse::<Tz>()
+                .map_err(|e| format!("invalid schedule_timezone '{}' for {}: {}", timezone, self.email, e).into()))
+            .transpose()
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
 pub struct Config {
+    #[serde(default)]
+    pub provider: ProviderKind,
+    #[serde(default)]
+    pub base_url: Option<String>,
+    #[serde(default)]
+    pub providers: Vec<ProviderConfig>,
+    #[serde(default)]
+    pub include: Vec<String>,
+    #[serde(default)]
     pub accounts: Vec<Account>,
+    #[serde(default)]
+    pub accounts_file: Option<String>,
+    #[serde(default)]
+    pub shared_cookie: SharedCookie,
+    #[serde(default)]
+    pub archived_accounts: Vec<ArchivedAccount>,
+    #[serde(default)]
+    pub concurrency: Option<usize>,
     pub max_retries: u32,
     pub retry_delay: u64,
+    #[serde(default)]
+    pub backoff: BackoffKind,
+    #[serde(default)]
+    pub max_retry_delay: Option<u64>,
+    #[serde(default)]
+    pub account_deadline: Option<u64>,
+    #[serde(default)]
+    pub run_deadline: Option<u64>,
+    #[serde(default = "default_true")]
+    pub cancel_sleep_on_shutdown: bool,
     pub log_file: String,
+    #[serde(default)]
+    pub log_max_line: Option<usize>,
+    #[serde(default)]
+    pub debug_archive_dir: Option<String>,
+    #[serde(default)]
+    pub artifacts: Option<ArtifactsConfig>,
+    #[serde(default = "default_history_file")]
+    pub history_file: String,
+    #[serde(default = "default_audit_log")]
+    pub audit_log: String,
+    #[serde(default = "default_state_file")]
+    pub state_file: String,
+    #[serde(default = "default_timezone")]
+    pub timezone: String,
+    #[serde(default)]
+    pub allowed_window: Option<TimeWindow>,
+    #[serde(default)]
+    pub maintenance_windows: Vec<TimeWindow>,
+    #[serde(default)]
+    pub proxy: Option<String>,
+    #[serde(default)]
+    pub randomize_fingerprint: bool,
+    #[serde(default)]
+    pub shuffle_accounts: bool,
+    #[serde(default)]
+    pub shuffle_seed: Option<u64>,
+    #[serde(default)]
+    pub rate_limit_per_hour: Option<u32>,
+    #[serde(default = "default_rate_limit_file")]
+    pub rate_limit_file: String,
+    #[serde(default)]
+    pub results_file: Option<String>,
+    #[serde(default)]
+    pub signing_key: Option<String>,
+    #[serde(default)]
+    pub sinks: Vec<SinkConfig>,
+    #[serde(default = "default_sink_queue_dir")]
+    pub sink_queue_dir: String,
+    #[serde(default)]
+    pub notifiers: Vec<NotifierConfig>,
+    #[serde(default)]
+    pub notify_on: NotifyOn,
+    #[serde(default = "default_failure_threshold")]
+    pub failure_threshold: u32,
+    #[serde(default)]
+    pub notify_queue: Option<NotifyQueueConfig>,
+    #[serde(default)]
+    pub on_success: Option<String>,
+    #[serde(default)]
+    pub on_failure: Option<String>,
+    #[serde(default = "default_hook_timeout")]
+    pub hook_timeout: u64,
+    #[serde(default)]
+    pub cookie_reminder: Option<CookieReminderConfig>,
+    #[serde(default)]
+    pub network_wait: Option<NetworkWaitConfig>,
+    #[serde(default)]
+    pub preflight: Option<PreflightConfig>,
+    #[serde(default)]
+    pub schedule: Option<String>,
+    #[serde(default)]
+    pub jitter_minutes: u64,
+    #[serde(default)]
+    pub spread_minutes: u64,
+    #[serde(default)]
+    pub account_jitter: Option<AccountJitterConfig>,
+    #[serde(default)]
+    pub state_store: StateStoreConfig,
+    #[serde(default)]
+    pub messages: MessageTemplates,
+    #[serde(default)]
+    pub body_capture: BodyCaptureConfig,
+    #[serde(default = "default_capture_headers")]
+    pub capture_headers: Vec<String>,
+    #[serde(default)]
+    pub error_catalog: ErrorCatalogConfig,
+    #[serde(default)]
+    pub trigger: TriggerConfig,
+    #[serde(default)]
+    pub profiles: BTreeMap<String, Profile>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ProviderKind {
+    #[default]
+    Glados,
+    Demo,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ProviderConfig {
+    #[serde(default)]
+    pub name: Option<String>,
+    #[serde(default, rename = "type")]
+    pub kind: ProviderKind,
+    #[serde(default)]
+    pub base_url: Option<String>,
+    #[serde(default)]
+    pub rate_limit_per_hour: Option<u32>,
+    #[serde(default)]
+    pub accounts: Vec<Account>,
+    #[serde(default)]
+    pub accounts_file: Option<String>,
+    #[serde(default)]
+    pub maintenance_windows: Vec<TimeWindow>,
+}
+
+impl ProviderConfig {
+    pub fn accounts(&self) -> Result<Box<dyn Iterator<Item = AccountResult> + '_>, Box<dyn std::error::Error>> {
+        let inline = self.accounts.iter().cloned().map(Ok);
+        match &self.accounts_file {
+            Some(path) => Ok(Box::new(inline.chain(AccountFileReader::open(path)?))),
+            None => Ok(Box::new(inline)),
+        }
+    }
+
+    // 随机顺序的全部账户；accounts_file 只记录每行的位置，打乱后逐行读取，不把整个文件读入内存
+    pub fn shuffled_accounts(&self, rng: &mut impl Rng) -> Result<Box<dyn Iterator<Item = AccountResult> + '_>, Box<dyn std::error::Error>> {
+        enum Entry {
+            Inline(usize),
+            Line(AccountLine),
+        }
+        let mut file = self.accounts_file.as_deref().map(AccountFileReader::open).transpose()?;
+        let mut entries: Vec<Entry> = (0..self.accounts.len()).map(Entry::Inline).collect();
+        if let Some(file) = &mut file {
+            entries.extend(file.positions()?.into_iter().map(Entry::Line));
+        }
+        entries.shuffle(rng);
+        Ok(Box::new(entries.into_iter().map(move |entry| match entry {
+            Entry::Inline(index) => Ok(self.accounts[index].clone()),
+            Entry::Line(line) => file.as_mut().ok_or("accounts_file is not open")?.read_at(line),
+        })))
+    }
+
+    pub fn key(&self, email: &str) -> String {
+        account_key(self.name.as_deref(), email)
+    }
+
+    pub fn label(&self) -> &str {
+        self.name.as_deref().unwrap_or("default")
+    }
+
+    pub fn maintenance_remaining(&self, now: NaiveTime) -> Result<Option<chrono::Duration>, Box<dyn std::error::Error>> {
+        let mut longest = None;
+        for window in &self.maintenance_windows {
+            if let Some(remaining) = window.remaining(now)? {
+                longest = longest.max(Some(remaining));
+            }
+        }
+        Ok(longest)
+    }
+}
+
+// 默认部署的账户以邮箱为键，其他部署加上名称前缀，同一邮箱可以同时存在于多个部署
+pub fn account_key(provider: Option<&str>, email: &str) -> String {
+    match provider {
+        Some(name) => format!("{}/{}", name, email),
+        None => email.to_string(),
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CookieReminderConfig {
+    #[serde(default = "default_cookie_lifetime_days")]
+    pub lifetime_days: u32,
+    #[serde(default = "default_remind_before_days")]
+    pub remind_before_days: u32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AccountJitterConfig {
+    pub max_offset: u64,
+    #[serde(default = "default_jitter_drift")]
+    pub drift: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct NetworkWaitConfig {
+    #[serde(default = "default_network_max_wait")]
+    pub max_wait: u64,
+    #[serde(default = "default_network_initial_delay")]
+    pub initial_delay: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PreflightConfig {
+    #[serde(default = "default_preflight_timeout")]
+    pub timeout: u64,
+}
+
+// 发送失败的通知保存在 dir 中，之后每次向该渠道发送前先按顺序补发；超过 max_age 小时的直接丢弃
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct NotifyQueueConfig {
+    #[serde(default = "default_notify_queue_dir")]
+    pub dir: String,
+    #[serde(default = "default_notify_queue_max_age")]
+    pub max_age: u64,
+}
+
+// 每次签到在 dir 下创建一个以开始时间命名的目录，保存结果、报告、请求记录（HAR）和响应内容；只保留最近 keep 次
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ArtifactsConfig {
+    #[serde(default = "default_artifacts_dir")]
+    pub dir: String,
+    #[serde(default = "default_artifacts_keep")]
+    pub keep: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TimeWindow {
+    pub start: String,
+    pub end: String,
+}
+
+impl TimeWindow {
+    pub fn bounds(&self) -> Result<(NaiveTime, NaiveTime), Box<dyn std::error::Error>> {
+        let parse = |value: &str| NaiveTime::parse_from_str(value, "%H:%M")
+            .map_err(|e| format!("invalid time '{}' in window {}-{}: {}", value, self.start, self.end, e));
+        Ok((parse(&self.start)?, parse(&self.end)?))
+    }
+
+    // 当前处于窗口内时返回距窗口结束的时长
+    pub fn remaining(&self, now: NaiveTime) -> Result<Option<chrono::Duration>, Box<dyn std::error::Error>> {
+        let (start, end) = self.bounds()?;
+        if !clock::within(now, start, end) {
+            return Ok(None);
+        }
+        let remaining = end - now;
+        Ok(Some(if remaining < chrono::Duration::zero() { remaining + chrono::Duration::days(1) } else { remaining }))
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StateBackend {
+    #[default]
+    File,
+    Sqlite,
+    Redis,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct StateStoreConfig {
+    #[serde(default)]
+    pub backend: StateBackend,
+    #[serde(default)]
+    pub path: Option<String>,
+    #[serde(default)]
+    pub url: Option<String>,
+    #[serde(default = "default_state_store_key")]
+    pub key: String,
+}
+
+impl StateStoreConfig {
+    pub fn path(&self) -> &str {
+        match (&self.path, self.backend) {
+            (Some(path), _) => path,
+            (None, StateBackend::Sqlite) => "logs/state.db",
+            (None, _) => "logs/state.json",
+        }
+    }
+}
+
+impl Default for StateStoreConfig {
+    fn default() -> Self {
+        Self {
+            backend: StateBackend::default(),
+            path: None,
+            url: None,
+            key: default_state_store_key(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ErrorCatalogConfig {
+    #[serde(default)]
+    pub rules: Vec<CatalogRule>,
+    #[serde(default)]
+    pub policies: BTreeMap<Outcome, OutcomePolicy>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CatalogRule {
+    #[serde(default)]
+    pub code: Option<i64>,
+    #[serde(default)]
+    pub status: Option<u16>,
+    #[serde(default)]
+    pub message: Option<String>,
+    pub outcome: Outcome,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct OutcomePolicy {
+    #[serde(default)]
+    pub retry: Option<bool>,
+    #[serde(default)]
+    pub notify: Option<bool>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ReportFormat {
+    #[default]
+    Json,
+    Html,
+}
+
+// 运行结果的输出目的地，按配置顺序依次写入
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase", deny_unknown_fields)]
+pub enum SinkConfig {
+    File {
+        path: String,
+        #[serde(default)]
+        format: ReportFormat,
+        #[serde(default)]
+        signing_key: Option<String>,
+    },
+    Webhook {
+        url: String,
+        #[serde(default)]
+        headers: BTreeMap<String, String>,
+        // 发送失败的结果保存在 sink_queue_dir 中，之后的运行按顺序补发
+        #[serde(default = "default_true")]
+        queue: bool,
+    },
+    S3 {
+        bucket: String,
+        #[serde(default = "default_s3_region")]
+        region: String,
+        #[serde(default)]
+        endpoint: Option<String>,
+        #[serde(default)]
+        prefix: String,
+        access_key: String,
+        secret_key: SecretString,
+    },
+    Mqtt {
+        host: String,
+        #[serde(default = "default_mqtt_port")]
+        port: u16,
+        topic: String,
+        #[serde(default = "default_mqtt_client_id")]
+        client_id: String,
+        #[serde(default)]
+        username: Option<String>,
+        #[serde(default)]
+        password: Option<SecretString>,
+        #[serde(default)]
+        retain: bool,
+    },
+    Sqlite {
+        path: String,
+    },
+    #[serde(rename = "google_sheets")]
+    GoogleSheets {
+        credentials_file: String,
+        spreadsheet_id: String,
+        #[serde(default = "default_sheets_range")]
+        range: String,
+        #[serde(default)]
+        endpoint: Option<String>,
+    },
+}
+
+// 重试等待的增长方式，以 retry_delay 为基数：constant 每次相同，exponential 每次翻倍，fibonacci 按斐波那契数列增长
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BackoffKind {
+    #[default]
+    Constant,
+    Exponential,
+    Fibonacci,
+}
+
+// 多个账户使用相同 cookie 时的处理：warn 只记录警告，refuse 拒绝为这些账户签到并记为失败
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SharedCookie {
+    #[default]
+    Warn,
+    Refuse,
+}
+
+// 逐账户通知的发送范围；汇总类渠道（email）不受影响，每次运行都会发送
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum NotifyOn {
+    #[default]
+    All,
+    Failure,
+    Summary,
+}
+
+// 每个账户签到完成（成功或失败）后推送通知的渠道；required 为 false 时该渠道发送失败只记录日志，不会使本次运行失败
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase", deny_unknown_fields)]
+pub enum NotifierConfig {
+    Webhook {
+        url: String,
+        #[serde(default)]
+        headers: BTreeMap<String, String>,
+        #[serde(default)]
+        body_template: Option<String>,
+        #[serde(default = "default_webhook_content_type")]
+        content_type: String,
+        #[serde(default = "default_true")]
+        required: bool,
+        #[serde(default)]
+        max_per_hour: Option<u32>,
+    },
+    Telegram {
+        bot_token: SecretString,
+        chat_id: String,
+        #[serde(default)]
+        endpoint: Option<String>,
+        #[serde(default = "default_true")]
+        required: bool,
+        #[serde(default)]
+        max_per_hour: Option<u32>,
+    },
+    // 微信推送服务，token 分别为 ServerChan 的 SendKey、PushPlus 的 token、Bark 的 device key
+    ServerChan {
+        token: SecretString,
+        #[serde(default)]
+        endpoint: Option<String>,
+        #[serde(default = "default_true")]
+        required: bool,
+        #[serde(default)]
+        max_per_hour: Option<u32>,
+    },
+    PushPlus {
+        token: SecretString,
+        #[serde(default)]
+        endpoint: Option<String>,
+        #[serde(default = "default_true")]
+        required: bool,
+        #[serde(default)]
+        max_per_hour: Option<u32>,
+    },
+    Bark {
+        token: SecretString,
+        #[serde(default)]
+        endpoint: Option<String>,
+        #[serde(default = "default_true")]
+        required: bool,
+        #[serde(default)]
+        max_per_hour: Option<u32>,
+    },
+    // 群机器人 webhook；钉钉和飞书开启“加签”时需要配置 secret
+    DingTalk {
+        url: String,
+        #[serde(default)]
+        secret: Option<SecretString>,
+        #[serde(default = "default_true")]
+        required: bool,
+        #[serde(default)]
+        max_per_hour: Option<u32>,
+    },
+    WeCom {
+        url: String,
+        #[serde(default = "default_true")]
+        required: bool,
+        #[serde(default)]
+        max_per_hour: Option<u32>,
+    },
+    Feishu {
+        url: String,
+        #[serde(default)]
+        secret: Option<SecretString>,
+        #[serde(default = "default_true")]
+        required: bool,
+        #[serde(default)]
+        max_per_hour: Option<u32>,
+    },
+    // 所有账户处理完后发送一封汇总邮件，不逐个账户发送
+    Email {
+        host: String,
+        #[serde(default)]
+        port: Option<u16>,
+        #[serde(default)]
+        tls: SmtpTls,
+        #[serde(default)]
+        username: Option<String>,
+        #[serde(default)]
+        password: Option<SecretString>,
+        from: String,
+        to: Vec<String>,
+        #[serde(default = "default_true")]
+        required: bool,
+        #[serde(default)]
+        max_per_hour: Option<u32>,
+    },
+}
+
+impl NotifierConfig {
+    pub fn required(&self) -> bool {
+        match self {
+            NotifierConfig::Webhook { required, .. }
+            | NotifierConfig::Telegram { required, .. }
+            | NotifierConfig::ServerChan { required, .. }
+            | NotifierConfig::PushPlus { required, .. }
+            | NotifierConfig::Bark { required, .. }
+            | NotifierConfig::DingTalk { required, .. }
+            | NotifierConfig::WeCom { required, .. }
+            | NotifierConfig::Feishu { required, .. }
+            | NotifierConfig::Email { required, .. } => *required,
+        }
+    }
+
+    pub fn max_per_hour(&self) -> Option<u32> {
+        match self {
+            NotifierConfig::Webhook { max_per_hour, .. }
+            | NotifierConfig::Telegram { max_per_hour, .. }
+            | NotifierConfig::ServerChan { max_per_hour, .. }
+            | NotifierConfig::PushPlus { max_per_hour, .. }
+            | NotifierConfig::Bark { max_per_hour, .. }
+            | NotifierConfig::DingTalk { max_per_hour, .. }
+            | NotifierConfig::WeCom { max_per_hour, .. }
+            | NotifierConfig::Feishu { max_per_hour, .. }
+            | NotifierConfig::Email { max_per_hour, .. } => *max_per_hour,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SmtpTls {
+    #[default]
+    Starttls,
+    Tls,
+    None,
+}
+
+impl SmtpTls {
+    pub fn default_port(&self) -> u16 {
+        match self {
+            SmtpTls::Starttls => 587,
+            SmtpTls::Tls => 465,
+            SmtpTls::None => 25,
+        }
+    }
+}
+
+// 签到结果的日志行和通知文本，占位符见 template::MESSAGE_PLACEHOLDERS；日志行前自动加上时间
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct MessageTemplates {
+    #[serde(default = "default_success_log")]
+    pub success_log: String,
+    #[serde(default = "default_failure_log")]
+    pub failure_log: String,
+    #[serde(default = "default_success_notification")]
+    pub success_notification: String,
+    #[serde(default = "default_failure_notification")]
+    pub failure_notification: String,
+}
+
+impl Default for MessageTemplates {
+    fn default() -> Self {
+        Self {
+            success_log: default_success_log(),
+            failure_log: default_failure_log(),
+            success_notification: default_success_notification(),
+            failure_notification: default_failure_notification(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct BodyCaptureConfig {
+    #[serde(default = "default_body_max_bytes")]
+    pub max_bytes: usize,
+    #[serde(default)]
+    pub redact_patterns: Vec<String>,
+}
+
+impl Default for BodyCaptureConfig {
+    fn default() -> Self {
+        Self {
+            max_bytes: default_body_max_bytes(),
+            redact_patterns: Vec::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TriggerConfig {
+    #[serde(default = "default_trigger_listen")]
+    pub listen: String,
+    #[serde(default)]
+    pub secret: Option<String>,
+    #[serde(default = "default_signature_tolerance")]
+    pub signature_tolerance: i64,
+    #[serde(default = "default_trigger_lock_file")]
+    pub lock_file: String,
+    // 在该 Unix 域套接字上提供接口（权限 0600）；设置后除非指定 serve --listen，不再监听 TCP
+    #[serde(default)]
+    pub socket: Option<String>,
+}
+
+impl Default for TriggerConfig {
+    fn default() -> Self {
+        Self {
+            listen: default_trigger_listen(),
+            secret: None,
+            signature_tolerance: default_signature_tolerance(),
+            lock_file: default_trigger_lock_file(),
+            socket: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Profile {
+    #[serde(default)]
+    pub proxy: Option<String>,
+    #[serde(default)]
+    pub concurrency: Option<usize>,
+    #[serde(default)]
+    pub max_retries: Option<u32>,
+    #[serde(default)]
+    pub retry_delay: Option<u64>,
+    #[serde(default)]
+    pub account_deadline: Option<u64>,
+    #[serde(default)]
+    pub run_deadline: Option<u64>,
+    #[serde(default)]
+    pub log_file: Option<String>,
+    // 设置时整体替换 notifiers，如家里的机器只发 Telegram、服务器上再加一个 webhook
+    #[serde(default)]
+    pub notifiers: Option<Vec<NotifierConfig>>,
+    #[serde(default)]
+    pub notify_on: Option<NotifyOn>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct IncludeFile {
+    #[serde(default)]
+    accounts: Vec<Account>,
+}
+
+fn default_success_log() -> String {
+    "Account: {{account}}, Message: {{message}}, Change: {{change}}, Balance: {{balance}}".to_string()
+}
+
+fn default_failure_log() -> String {
+    "账户 {{account}} 处理失败: {{message}}".to_string()
+}
+
+fn default_success_notification() -> String {
+    "账户 {{account}} 签到成功: {{message}}（变化 {{change}}，余额 {{balance}}）".to_string()
+}
+
+fn default_failure_notification() -> String {
+    "账户 {{account}} 处理失败: {{message}}".to_string()
+}
+
+fn default_preflight_timeout() -> u64 {
+    10
+}
+
+fn default_failure_threshold() -> u32 {
+    1
+}
+
+fn default_notify_queue_dir() -> String {
+    "logs/notify_queue".to_string()
+}
+
+fn default_notify_queue_max_age() -> u64 {
+    48
+}
+
+fn default_artifacts_dir() -> String {
+    "logs/runs".to_string()
+}
+
+fn default_artifacts_keep() -> usize {
+    20
+}
+
+fn default_hook_timeout() -> u64 {
+    60
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn default_history_file() -> String {
+    "logs/history.jsonl".to_string()
+}
+
+fn default_audit_log() -> String {
+    "logs/audit.jsonl".to_string()
+}
+
+fn default_state_store_key() -> String {
+    "web:state".to_string()
+}
+
+fn default_state_file() -> String {
+    "logs/run_state.json".to_string()
+}
+
+fn default_cookie_lifetime_days() -> u32 {
+    30
+}
+
+fn default_remind_before_days() -> u32 {
+    3
+}
+
+fn default_webhook_content_type() -> String {
+    "application/json".to_string()
+}
+
+fn default_jitter_drift() -> u64 {
+    60
+}
+
+fn default_network_max_wait() -> u64 {
+    600
+}
+
+fn default_network_initial_delay() -> u64 {
+    5
+}
+
+fn default_body_max_bytes() -> usize {
+    2048
+}
+
+fn default_capture_headers() -> Vec<String> {
+    ["cf-ray", "server", "retry-after", "x-ratelimit-*", "ratelimit-*"].map(str::to_string).to_vec()
+}
+
+fn default_trigger_listen() -> String {
+    "127.0.0.1:8080".to_string()
+}
+
+fn default_trigger_lock_file() -> String {
+    "logs/serve.lock".to_string()
+}
+
+fn default_signature_tolerance() -> i64 {
+    300
+}
+
+fn default_s3_region() -> String {
+    "us-east-1".to_string()
+}
+
+fn default_sheets_range() -> String {
+    "Sheet1!A1".to_string()
+}
+
+fn default_mqtt_port() -> u16 {
+    1883
+}
+
+fn default_mqtt_client_id() -> String {
+    "web".to_string()
+}
+
+fn default_sink_queue_dir() -> String {
+    "logs/sink_queue".to_string()
+}
+
+fn default_rate_limit_file() -> String {
+    "logs/rate_limit.json".to_string()
+}
+
+fn default_timezone() -> String {
+    "Asia/Shanghai".to_string()
 }
 
 impl Config {
-    pub fn load_from_file(path: &str) -> Result<Self, Box<dyn std::error::Error>> {
-        let content = fs::read_to_string(path)?;
-        let config: Config = if path.ends_with(".yaml") || path.ends_with(".yml") {
-            serde_yaml::from_str(&content)?
-        } else {
-            serde_json::from_str(&content)?
-        };
+    pub fn load_from_file(path: &str, profile: Option<&str>) -> Result<Self, Box<dyn std::error::Error>> {
+        Self::load_with_overrides(path, profile, &Profile::default())
+    }
+
+    // overrides 来自命令行，在 profile 之后应用，只影响本次运行
+    pub fn load_with_overrides(path: &str, profile: Option<&str>, overrides: &Profile) -> Result<Self, Box<dyn std::error::Error>> {
+        let mut config: Config = parse_file(path)?;
+        config.merge_includes(path)?;
+        if let Some(name) = profile {
+            let profile = config.profiles.get(name).cloned().ok_or_else(|| {
+                let known: Vec<&str> = config.profiles.keys().map(String::as_str).collect();
+                format!("unknown profile '{}' (available: {})", name, known.join(", "))
+            })?;
+            config.apply_profile(profile);
+        }
+        config.apply_profile(overrides.clone());
+        config.resolve_paths(path);
         config.validate()?;
         Ok(config)
     }
 
+    fn apply_profile(&mut self, profile: Profile) {
+        if profile.proxy.is_some() {
+            self.proxy = profile.proxy;
+        }
+        if profile.concurrency.is_some() {
+            self.concurrency = profile.concurrency;
+        }
+        if let Some(max_retries) = profile.max_retries {
+            self.max_retries = max_retries;
+        }
+        if let Some(retry_delay) = profile.retry_delay {
+            self.retry_delay = retry_delay;
+        }
+        if profile.account_deadline.is_some() {
+            self.account_deadline = profile.account_deadline;
+        }
+        if profile.run_deadline.is_some() {
+            self.run_deadline = profile.run_deadline;
+        }
+        if let Some(log_file) = profile.log_file {
+            self.log_file = log_file;
+        }
+        if let Some(notifiers) = profile.notifiers {
+            self.notifiers = notifiers;
+        }
+        if let Some(notify_on) = profile.notify_on {
+            self.notify_on = notify_on;
+        }
+    }
+
+    // 相对路径一律相对于配置文件所在目录解析，避免从 cron 等其他工作目录运行时文件散落各处；绝对路径保持不变
+    fn resolve_paths(&mut self, path: &str) {
+        let base_dir = Path::new(path).parent().unwrap_or_else(|| Path::new(""));
+        if base_dir.as_os_str().is_empty() {
+            return;
+        }
+        let resolve = |value: &mut String| {
+            if !value.is_empty() && Path::new(value.as_str()).is_relative() {
+                *value = base_dir.join(value.as_str()).to_string_lossy().into_owned();
+            }
+        };
+        resolve(&mut self.log_file);
+        resolve(&mut self.history_file);
+        resolve(&mut self.audit_log);
+        resolve(&mut self.state_file);
+        resolve(&mut self.trigger.lock_file);
+        resolve(&mut self.rate_limit_file);
+        resolve(&mut self.sink_queue_dir);
+        if let Some(queue) = &mut self.notify_queue {
+            resolve(&mut queue.dir);
+        }
+        if let Some(artifacts) = &mut self.artifacts {
+            resolve(&mut artifacts.dir);
+        }
+        if self.state_store.backend != StateBackend::Redis {
+            let path = self.state_store.path().to_string();
+            resolve(self.state_store.path.insert(path));
+        }
+        for provider in &mut self.providers {
+            if let Some(value) = &mut provider.accounts_file {
+                resolve(value);
+            }
+        }
+        for value in [&mut self.accounts_file, &mut self.results_file, &mut self.signing_key, &mut self.debug_archive_dir, &mut self.trigger.socket].into_iter().flatten() {
+            resolve(value);
+        }
+        for sink in &mut self.sinks {
+            match sink {
+                SinkConfig::File { path, signing_key, .. } => {
+                    resolve(path);
+                    if let Some(key) = signing_key {
+                        resolve(key);
+                    }
+                }
+                SinkConfig::Sqlite { path } | SinkConfig::GoogleSheets { credentials_file: path, .. } => resolve(path),
+                _ => {}
+            }
+        }
+    }
+
+    // 被包含的文件只能提供 accounts，按 include 中的顺序追加在主配置账户之后，邮箱重复视为错误
+    fn merge_includes(&mut self, path: &str) -> Result<(), Box<dyn std::error::Error>> {
+        let base_dir = Path::new(path).parent().unwrap_or_else(|| Path::new(""));
+        let mut sources: HashMap<String, String> = HashMap::new();
+        for account in &self.accounts {
+            if let Some(previous) = sources.insert(account.email.clone(), path.to_string()) {
+                return Err(format!("duplicate account {} in {}", account.email, previous).into());
+            }
+        }
+        for include in &self.include {
+            let include_path = base_dir.join(include);
+            let include_path = include_path.to_string_lossy();
+            let included: IncludeFile = parse_file(&include_path)
+                .map_err(|e| format!("failed to load include {}", e))?;
+            for account in included.accounts {
+                if let Some(previous) = sources.insert(account.email.clone(), include_path.to_string()) {
+                    return Err(format!("duplicate account {} in {} (already defined in {})",
+                        account.email, include_path, previous).into());
+                }
+                self.accounts.push(account);
+            }
+        }
+        Ok(())
+    }
+
     fn validate(&self) -> Result<(), Box<dyn std::error::Error>> {
-        if self.accounts.is_empty() {
+        if self.accounts.is_empty() && self.accounts_file.is_none()
+            && self.providers.iter().all(|p| p.accounts.is_empty() && p.accounts_file.is_none())
+        {
             return Err("No accounts configured".into());
         }
+        if cfg!(not(unix)) && self.trigger.socket.is_some() {
+            return Err("trigger.socket requires a Unix system, use trigger.listen instead".into());
+        }
+        let mut provider_names = HashSet::new();
+        for provider in &self.providers {
+            let name = provider.name.as_deref().unwrap_or_default();
+            if name.is_empty() || name == "default" || name.contains('/') {
+                return Err("each entry in providers needs a unique name other than 'default' without '/'".into());
+            }
+            if !provider_names.insert(name) {
+                return Err(format!("duplicate provider name '{}'", name).into());
+            }
+            if provider.rate_limit_per_hour == Some(0) {
+                return Err(format!("rate_limit_per_hour for provider '{}' must be greater than 0", name).into());
+            }
+        }
+        if self.concurrency == Some(0) {
+            return Err("concurrency must be greater than 0".into());
+        }
         if self.max_retries == 0 {
             return Err("max_retries must be greater than 0".into());
         }
+        if self.max_retry_delay.is_some_and(|max| max < self.retry_delay) {
+            return Err("max_retry_delay must not be less than retry_delay".into());
+        }
         if self.log_file.is_empty() {
             return Err("log_file path must not be empty".into());
         }
+        if self.history_file.is_empty() {
+            return Err("history_file path must not be empty".into());
+        }
+        if self.state_file.is_empty() {
+            return Err("state_file path must not be empty".into());
+        }
+        match self.state_store.backend {
+            StateBackend::Redis if self.state_store.url.is_none() => {
+                return Err("state_store.url is required for the redis backend".into());
+            }
+            StateBackend::File | StateBackend::Sqlite if self.state_store.path().is_empty() => {
+                return Err("state_store.path must not be empty".into());
+            }
+            _ => {}
+        }
+        for account in self.accounts.iter().chain(self.providers.iter().flat_map(|p| &p.accounts)) {
+            if let Some(date) = &account.cookie_updated_at {
+                chrono::NaiveDate::parse_from_str(date, "%Y-%m-%d")
+                    .map_err(|e| format!("invalid cookie_updated_at '{}' for {}: {}", date, account.email, e))?;
+            }
+            if let Some(body_template) = &account.body_template {
+                template::validate(body_template, template::PLACEHOLDERS)
+                    .map_err(|e| format!("invalid body_template for {}: {}", account.email, e))?;
+            }
+            if let Some(schedule) = &account.schedule {
+                cron::Schedule::parse(schedule).map_err(|e| format!("invalid schedule '{}' for {}: {}", schedule, account.email, e))?;
+            }
+            if account.schedule_timezone.is_some() && account.schedule.is_none() && self.schedule.is_none() {
+                return Err(format!("schedule_timezone for {} requires schedule to be set", account.email).into());
+            }
+            account.schedule_tz()?;
+        }
+        let provider_windows = self.providers.iter().flat_map(|provider| &provider.maintenance_windows);
+        for window in self.allowed_window.iter().chain(&self.maintenance_windows).chain(provider_windows) {
+            window.bounds()?;
+        }
+        for rule in &self.error_catalog.rules {
+            if rule.code.is_none() && rule.status.is_none() && rule.message.as_deref().is_none_or(str::is_empty) {
+                return Err(format!("error_catalog rule for {} must set code, status or message", rule.outcome).into());
+            }
+        }
+        if self.rate_limit_per_hour == Some(0) {
+            return Err("rate_limit_per_hour must be greater than 0".into());
+        }
+        if self.shuffle_seed.is_some() && !self.shuffle_accounts {
+            return Err("shuffle_seed requires shuffle_accounts to be enabled".into());
+        }
+        if self.signing_key.is_some() && self.results_file.is_none() {
+            return Err("signing_key requires results_file to be set".into());
+        }
+        for sink in &self.sinks {
+            match sink {
+                SinkConfig::Webhook { url, .. } => {
+                    reqwest::Url::parse(url).map_err(|e| format!("invalid webhook sink url '{}': {}", url, e))?;
+                }
+                SinkConfig::S3 { endpoint: Some(endpoint), .. } | SinkConfig::GoogleSheets { endpoint: Some(endpoint), .. } => {
+                    reqwest::Url::parse(endpoint).map_err(|e| format!("invalid sink endpoint '{}': {}", endpoint, e))?;
+                }
+                SinkConfig::Mqtt { topic, .. } if topic.is_empty() || topic.contains(['+', '#']) => {
+                    return Err(format!("invalid mqtt sink topic '{}'", topic).into());
+                }
+                // MQTT 3.1.1 中密码标志要求同时设置用户名
+                SinkConfig::Mqtt { username: None, password: Some(_), .. } => {
+                    return Err("mqtt sink password requires username to be set".into());
+                }
+                _ => {}
+            }
+        }
+        if self.log_max_line == Some(0) {
+            return Err("log_max_line must be greater than 0".into());
+        }
+        if self.debug_archive_dir.is_some() && self.log_max_line.is_none() {
+            return Err("debug_archive_dir requires log_max_line to be set".into());
+        }
+        if self.account_jitter.as_ref().is_some_and(|jitter| jitter.max_offset == 0) {
+            return Err("account_jitter.max_offset must be greater than 0".into());
+        }
+        if self.network_wait.as_ref().is_some_and(|wait| wait.initial_delay == 0) {
+            return Err("network_wait.initial_delay must be greater than 0".into());
+        }
+        for notifier in &self.notifiers {
+            match notifier {
+                NotifierConfig::Webhook { url, body_template, .. } => {
+                    reqwest::Url::parse(url).map_err(|e| format!("invalid webhook notifier url '{}': {}", url, e))?;
+                    if let Some(body_template) = body_template {
+                        template::validate(body_template, template::NOTIFICATION_PLACEHOLDERS)
+                            .map_err(|e| format!("invalid webhook notifier body_template: {}", e))?;
+                    }
+                }
+                NotifierConfig::Telegram { bot_token, chat_id, endpoint, .. } => {
+                    if bot_token.expose().is_empty() || chat_id.is_empty() {
+                        return Err("telegram notifier requires bot_token and chat_id".into());
+                    }
+                    if let Some(endpoint) = endpoint {
+                        reqwest::Url::parse(endpoint).map_err(|e| format!("invalid telegram notifier endpoint '{}': {}", endpoint, e))?;
+                    }
+                }
+                NotifierConfig::ServerChan { token, endpoint, .. } | NotifierConfig::PushPlus { token, endpoint, .. } | NotifierConfig::Bark { token, endpoint, .. } => {
+                    if token.expose().is_empty() {
+                        return Err("push notifier requires token".into());
+                    }
+                    if let Some(endpoint) = endpoint {
+                        reqwest::Url::parse(endpoint).map_err(|e| format!("invalid push notifier endpoint '{}': {}", endpoint, e))?;
+                    }
+                }
+                NotifierConfig::DingTalk { url, .. } | NotifierConfig::WeCom { url, .. } | NotifierConfig::Feishu { url, .. } => {
+                    reqwest::Url::parse(url).map_err(|e| format!("invalid bot notifier url: {}", e.to_string().replace(url.as_str(), "")))?;
+                }
+                NotifierConfig::Email { host, to, username, password, .. } => {
+                    if host.is_empty() || to.is_empty() {
+                        return Err("email notifier requires host and at least one address in to".into());
+                    }
+                    if password.is_some() && username.is_none() {
+                        return Err("email notifier password requires username".into());
+                    }
+                }
+            }
+        }
+        for (name, message) in [
+            ("success_log", &self.messages.success_log),
+            ("failure_log", &self.messages.failure_log),
+            ("success_notification", &self.messages.success_notification),
+            ("failure_notification", &self.messages.failure_notification),
+        ] {
+            template::validate(message, template::MESSAGE_PLACEHOLDERS)
+                .map_err(|e| format!("invalid messages.{}: {}", name, e))?;
+        }
+        if let Some(schedule) = &self.schedule {
+            cron::Schedule::parse(schedule).map_err(|e| format!("invalid schedule '{}': {}", schedule, e))?;
+        }
+        if self.notifiers.iter().any(|notifier| notifier.max_per_hour() == Some(0)) {
+            return Err("notifiers[].max_per_hour must be greater than 0".into());
+        }
+        if self.notify_queue.as_ref().is_some_and(|queue| queue.max_age == 0) {
+            return Err("notify_queue.max_age must be greater than 0".into());
+        }
+        if self.artifacts.as_ref().is_some_and(|artifacts| artifacts.keep == 0) {
+            return Err("artifacts.keep must be greater than 0".into());
+        }
+        if self.preflight.as_ref().is_some_and(|preflight| preflight.timeout == 0) {
+            return Err("preflight.timeout must be greater than 0".into());
+        }
+        if self.failure_threshold == 0 {
+            return Err("failure_threshold must be greater than 0".into());
+        }
+        if self.hook_timeout == 0 {
+            return Err("hook_timeout must be greater than 0".into());
+        }
+        if let Some(proxy) = &self.proxy {
+            reqwest::Proxy::all(proxy).map_err(|e| format!("invalid proxy '{}': {}", proxy, e))?;
+        }
+        self.body_filter()?;
+        self.reset_timezone()?;
         Ok(())
     }
-}
\ No newline at end of file
+
+    // 顶层的 provider/base_url/accounts 构成默认部署，providers 中的每一项是额外的部署
+    pub fn provider_instances(&self) -> Vec<ProviderConfig> {
+        let mut instances = Vec::with_capacity(self.providers.len() + 1);
+        if !self.accounts.is_empty() || self.accounts_file.is_some() {
+            instances.push(self.default_instance());
+        }
+        instances.extend(self.providers.iter().cloned());
+        instances
+    }
+
+    // 默认部署，即使其中没有账户（例如向其添加第一个账户时）
+    pub fn default_instance(&self) -> ProviderConfig {
+        ProviderConfig {
+            name: None,
+            kind: self.provider,
+            base_url: self.base_url.clone(),
+            rate_limit_per_hour: self.rate_limit_per_hour,
+            accounts: self.accounts.clone(),
+            accounts_file: self.accounts_file.clone(),
+            maintenance_windows: self.maintenance_windows.clone(),
+        }
+    }
+
+    // 只保留 keep(账户键, 账户) 为 true 的账户，其余的不参与本次运行；accounts_file 中的账户读入后一并过滤
+    pub fn retain_accounts(&mut self, keep: impl Fn(&str, &Account) -> bool) -> Result<(), Box<dyn std::error::Error>> {
+        let mut kept = Vec::new();
+        for instance in self.provider_instances() {
+            let mut accounts = Vec::new();
+            for account in instance.accounts()? {
+                let account = account?;
+                if keep(&instance.key(&account.email), &account) {
+                    accounts.push(account);
+                }
+            }
+            kept.push(accounts);
+        }
+        let mut kept = kept.into_iter();
+        if !self.accounts.is_empty() || self.accounts_file.is_some() {
+            self.accounts = kept.next().unwrap_or_default();
+            self.accounts_file = None;
+        }
+        for (provider, accounts) in self.providers.iter_mut().zip(kept) {
+            provider.accounts = accounts;
+            provider.accounts_file = None;
+        }
+        self.providers.retain(|provider| !provider.accounts.is_empty());
+        Ok(())
+    }
+
+    // 使用相同 cookie（含备用 cookie）的账户，每组按出现顺序列出账户键。同一 cookie 属于同一个 GLaDOS 账户，
+    // 多个账户共用时会重复签到同一个账户，其他账户从未签到，结果却都显示成功
+    pub fn shared_cookies(&self) -> Result<Vec<Vec<String>>, Box<dyn std::error::Error>> {
+        let mut owners = cookie_pool::Owners::default();
+        for instance in self.provider_instances() {
+            for account in instance.accounts()? {
+                let account = account?;
+                if !account.disabled {
+                    owners.add(&instance.key(&account.email), &account);
+                }
+            }
+        }
+        Ok(owners.shared())
+    }
+
+    // results_file/signing_key 等价于排在最前面的 file 输出
+    pub fn result_sinks(&self) -> Vec<SinkConfig> {
+        let legacy = self.results_file.iter().map(|path| SinkConfig::File {
+            path: path.clone(),
+            format: ReportFormat::Json,
+            signing_key: self.signing_key.clone(),
+        });
+        legacy.chain(self.sinks.iter().cloned()).collect()
+    }
+
+    pub fn rate_limiter(&self, instance: &ProviderConfig) -> Option<Arc<RateLimiter>> {
+        let per_hour = instance.rate_limit_per_hour?;
+        let file = match &instance.name {
+            Some(name) => {
+                let path = Path::new(&self.rate_limit_file);
+                let stem = path.file_stem().and_then(|s| s.to_str()).unwrap_or("rate_limit");
+                path.with_file_name(format!("{}-{}.json", stem, name)).to_string_lossy().into_owned()
+            }
+            None => self.rate_limit_file.clone(),
+        };
+        Some(Arc::new(RateLimiter::open(per_hour, file)))
+    }
+
+    // 通知渠道的限流状态保存在 rate_limit_file 旁，如 logs/rate_limit-notify-telegram-<哈希>.json
+    pub fn notify_rate_limit_file(&self, channel: &str) -> String {
+        let path = Path::new(&self.rate_limit_file);
+        let stem = path.file_stem().and_then(|s| s.to_str()).unwrap_or("rate_limit");
+        path.with_file_name(format!("{}-notify-{}.json", stem, channel)).to_string_lossy().into_owned()
+    }
+
+    // 同时处理的账户数：未设置 concurrency 时只有内联账户不限制，使用 accounts_file 时为 DEFAULT_FILE_CONCURRENCY
+    pub fn concurrency_limit(&self) -> usize {
+        let streamed = self.accounts_file.is_some() || self.providers.iter().any(|provider| provider.accounts_file.is_some());
+        self.concurrency.unwrap_or(if streamed { DEFAULT_FILE_CONCURRENCY } else { usize::MAX })
+    }
+
+    pub fn file_logger(&self) -> FileLogger {
+        FileLogger::new(&self.log_file).with_max_line(self.log_max_line, self.debug_archive_dir.as_deref())
+    }
+
+    pub fn backoff_strategy(&self) -> Box<dyn BackoffStrategy> {
+        backoff::build(self.backoff, self.retry_delay, self.max_retry_delay)
+    }
+
+    pub fn body_filter(&self) -> Result<BodyFilter, Box<dyn std::error::Error>> {
+        BodyFilter::new(self.body_capture.max_bytes, &self.body_capture.redact_patterns)
+    }
+
+    pub fn reset_timezone(&self) -> Result<Tz, Box<dyn std::error::Error>> {
+        self.timezone
+            .parse::<Tz>()
+            .map_err(|e| format!("invalid timezone '{}': {}", self.timezone, e).into())
+    }
+}
+
+// 解析错误中带上文件名和位置（serde_yaml/serde_json 的错误信息包含行列号）
+fn parse_file<T: DeserializeOwned>(path: &str) -> Result<T, Box<dyn std::error::Error>> {
+    let content = fs::read_to_string(path).map_err(|e| format!("failed to read {}: {}", path, e))?;
+    if path.ends_with(".yaml") || path.ends_with(".yml") {
+        serde_yaml::from_str(&content).map_err(|e| format!("{}: {}", path, e).into())
+    } else {
+        serde_json::from_str(&content).map_err(|e| format!("{}: {}", path, e).into())
+    }
+}
@@ -0,0 +1,575 @@
+use regex::Regex;
+use serde_json::Value;
+use std::{ops::Range, path::Path};
+
+use crate::{config::ProviderConfig, perms};
+
+// 账户条目所在的 YAML 列表：同一邮箱可以同时出现在多个部署和 archived_accounts 中，只在目标列表内查找。
+// ndjson/csv 文件只属于一个部署，不受影响
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AccountList<'a> {
+    // 顶层 accounts（默认部署，包括 include 文件）
+    Default,
+    // providers 中 name 为该值的部署的 accounts
+    Provider(&'a str),
+    // 顶层 archived_accounts 中 provider 为该值（None 为默认部署）的条目
+    Archived(Option<&'a str>),
+}
+
+impl<'a> AccountList<'a> {
+    pub fn of(instance: &'a ProviderConfig) -> Self {
+        match &instance.name {
+            Some(name) => AccountList::Provider(name),
+            None => AccountList::Default,
+        }
+    }
+
+    // 列表在文件中的行范围（含键所在行，流式写法的条目可能与键在同一行）；文件中没有该列表时为 None
+    fn range(&self, lines: &[String]) -> Option<Range<usize>> {
+        match self {
+            AccountList::Default => top_level_list(lines, "accounts"),
+            AccountList::Archived(_) => top_level_list(lines, "archived_accounts"),
+            AccountList::Provider(name) => provider_accounts(lines, name),
+        }
+    }
+}
+
+// 直接修改配置文件文本中某个账户的字段，保留注释和其余格式；serde_yaml 往返会丢失注释，因此不重新序列化整个文件
+// 字段不存在时不改动（cookie 除外）；YAML 文件中只在 list 内查找，返回 None 表示没有该账户
+pub fn set_account_field(path: &str, content: &str, list: AccountList, email: &str, field: &str, value: &Value) -> Result<Option<String>, Box<dyn std::error::Error>> {
+    edit_field(path, content, list, email, field, value, false)
+}
+
+// 同 set_account_field，但字段不存在时添加到该账户下
+pub fn put_account_field(path: &str, content: &str, list: AccountList, email: &str, field: &str, value: &Value) -> Result<Option<String>, Box<dyn std::error::Error>> {
+    edit_field(path, content, list, email, field, value, true)
+}
+
+fn edit_field(path: &str, content: &str, list: AccountList, email: &str, field: &str, value: &Value, insert: bool) -> Result<Option<String>, Box<dyn std::error::Error>> {
+    match Path::new(path).extension().and_then(|e| e.to_str()) {
+        Some("ndjson") | Some("jsonl") => set_ndjson(content, email, field, value, insert),
+        Some("csv") if field == "cookie" => Ok(set_csv(content, email, value.as_str().unwrap_or_default())),
+        Some("csv") if insert => Err(format!("{} only stores email and cookie, cannot set {}", path, field).into()),
+        Some("csv") => Ok(Some(content.to_string())),
+        _ => set_yaml(content, list, email, field, value, insert),
+    }
+}
+
+// 在主配置的顶层列表 list（accounts 或 archived_accounts）末尾追加一个账户（fields 按顺序写出），列表不存在时在文件末尾新建
+pub fn add_account(path: &str, content: &str, list: &str, fields: &[(&str, Value)]) -> Result<String, Box<dyn std::error::Error>> {
+    if !matches!(Path::new(path).extension().and_then(|e| e.to_str()), Some("yaml") | Some("yml")) {
+        return Err(format!("{} is not a YAML file, add the account manually", path).into());
+    }
+    let mut lines: Vec<String> = content.split_inclusive('\n').map(str::to_string).collect();
+    if lines.last().is_some_and(|last| !last.ends_with('\n')) {
+        lines.last_mut().unwrap().push('\n');
+    }
+    let key = Regex::new(&format!(r"^{}:\s*(.*?)\s*$", regex::escape(list)))?;
+    let Some(key_line) = lines.iter().position(|line| key.is_match(line.trim_end_matches(['\r', '\n']))) else {
+        if lines.last().is_some_and(|last| !last.trim().is_empty()) {
+            lines.push("\n".to_string());
+        }
+        lines.push(format!("{}:\n", list));
+        lines.extend(account_lines(2, fields)?);
+        return Ok(lines.concat());
+    };
+
+    let rest = key.captures(lines[key_line].trim_end_matches(['\r', '\n'])).ok_or("unreachable")?[1].to_string();
+    if rest.starts_with("[]") {
+        lines[key_line] = format!("{}:{}\n", list, trailing_comment(rest.trim_start_matches("[]")));
+        let entry = account_lines(2, fields)?;
+        lines.splice(key_line + 1..key_line + 1, entry);
+        return Ok(lines.concat());
+    }
+    if !rest.is_empty() && !rest.starts_with('#') {
+        return Err(format!("{} is written in flow style, edit the file manually", list).into());
+    }
+
+    // 列表到下一个顶层键为止；列表项内缩进的注释属于该列表，顶层注释属于后面的配置项
+    let end = list_end(&lines, key_line, 0);
+    let items: Vec<usize> = (key_line + 1..end).filter(|&i| is_content(&lines[i]) && lines[i].trim_start().starts_with('-')).collect();
+    let dash_indent = items.first().map_or(2, |&i| indent(&lines[i]));
+    let insert_at = (key_line + 1..end).rev()
+        .find(|&i| is_content(&lines[i]) || (!lines[i].trim().is_empty() && indent(&lines[i]) > 0))
+        .map_or(key_line + 1, |i| i + 1);
+    let entry = account_lines(dash_indent, fields)?;
+    lines.splice(insert_at..insert_at, entry);
+    Ok(lines.concat())
+}
+
+// 删除账户的整个条目；YAML 文件中只在 list 内查找，返回 None 表示没有该账户
+pub fn remove_account(path: &str, content: &str, list: AccountList, email: &str) -> Result<Option<String>, Box<dyn std::error::Error>> {
+    match Path::new(path).extension().and_then(|e| e.to_str()) {
+        Some("ndjson") | Some("jsonl") => remove_lines(content, |line| {
+            let record: Value = serde_json::from_str(line)?;
+            Ok(record["email"].as_str() == Some(email))
+        }),
+        Some("csv") => remove_lines(content, |line| Ok(line.split_once(',').is_some_and(|(line_email, _)| line_email.trim() == email))),
+        _ => {
+            let mut lines: Vec<String> = content.split_inclusive('\n').map(str::to_string).collect();
+            let Some(entry) = locate_yaml(&lines, list, email)? else {
+                return Ok(None);
+            };
+            let range = match entry.block {
+                Some(block) => block.start..block.end,
+                None => entry.line..entry.line + 1,
+            };
+            lines.drain(range);
+            Ok(Some(lines.concat()))
+        }
+    }
+}
+
+// 删除没有列表项的顶层列表键（及其前面的空行），用于 restore 移走最后一个已归档账户之后
+pub fn remove_empty_list(content: &str, list: &str) -> String {
+    let mut lines: Vec<String> = content.split_inclusive('\n').map(str::to_string).collect();
+    let Some(key_line) = lines.iter().position(|line| line.trim_end() == format!("{}:", list)) else {
+        return content.to_string();
+    };
+    let end = list_end(&lines, key_line, 0);
+    if (key_line + 1..end).any(|i| is_content(&lines[i])) {
+        return content.to_string();
+    }
+    let start = if key_line > 0 && lines[key_line - 1].trim().is_empty() { key_line - 1 } else { key_line };
+    lines.drain(start..key_line + 1);
+    lines.concat()
+}
+
+// 先写临时文件再重命名，避免写到一半时其他进程读到不完整的配置；文件包含 cookie，写入后权限为 0600
+pub fn write_atomic(path: &str, content: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let tmp_path = format!("{}.tmp", path);
+    perms::write_private(&tmp_path, content)?;
+    std::fs::rename(&tmp_path, path)?;
+    Ok(())
+}
+
+fn indent(line: &str) -> usize {
+    line.len() - line.trim_start().len()
+}
+
+fn is_content(line: &str) -> bool {
+    let trimmed = line.trim();
+    !trimmed.is_empty() && !trimmed.starts_with('#')
+}
+
+fn account_lines(dash_indent: usize, fields: &[(&str, Value)]) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+    fields.iter().enumerate()
+        .map(|(i, (field, value))| {
+            let prefix = if i == 0 { format!("{}- ", " ".repeat(dash_indent)) } else { " ".repeat(dash_indent + 2) };
+            Ok(format!("{}{}: {}\n", prefix, field, serde_json::to_string(value)?))
+        })
+        .collect()
+}
+
+struct Entry {
+    line: usize,
+    // 块写法时为整个列表项的行范围 [start, end) 及其中键的缩进；单行写法 - {email: ...} 时为 None
+    block: Option<Block>,
+}
+
+struct Block {
+    start: usize,
+    end: usize,
+    key_indent: usize,
+}
+
+// 键所在行之后、下一个缩进不大于 key_indent 的键之前的行属于该键；与键同缩进的列表项（- ...）也属于该键
+fn list_end(lines: &[String], key_line: usize, key_indent: usize) -> usize {
+    (key_line + 1..lines.len())
+        .find(|&i| is_content(&lines[i]) && (indent(&lines[i]) < key_indent || (indent(&lines[i]) == key_indent && !lines[i].trim_start().starts_with('-'))))
+        .unwrap_or(lines.len())
+}
+
+fn top_level_list(lines: &[String], key: &str) -> Option<Range<usize>> {
+    let prefix = format!("{}:", key);
+    let key_line = lines.iter().position(|line| line.starts_with(&prefix))?;
+    Some(key_line..list_end(lines, key_line, 0))
+}
+
+// providers 中 name 为该值的列表项下的 accounts；name 和 accounts 都须是该列表项的直接键
+fn provider_accounts(lines: &[String], name: &str) -> Option<Range<usize>> {
+    let providers = top_level_list(lines, "providers")?;
+    let key = Regex::new(r"^(\s*(?:-\s+)?)([A-Za-z_]+):(.*)$").ok()?;
+    let item_indent = providers.clone().skip(1)
+        .find(|&i| is_content(&lines[i]))
+        .filter(|&i| lines[i].trim_start().starts_with('-'))
+        .map(|i| indent(&lines[i]))?;
+    let starts: Vec<usize> = providers.clone().skip(1)
+        .filter(|&i| is_content(&lines[i]) && indent(&lines[i]) == item_indent && lines[i].trim_start().starts_with('-'))
+        .collect();
+    for (n, &start) in starts.iter().enumerate() {
+        let end = starts.get(n + 1).copied().unwrap_or(providers.end);
+        let Some(first) = key.captures(lines[start].trim_end_matches(['\r', '\n'])) else {
+            continue;
+        };
+        let key_indent = first[1].len();
+        let mut found_name = false;
+        let mut accounts = None;
+        for (i, line) in lines.iter().enumerate().take(end).skip(start) {
+            let Some(caps) = key.captures(line.trim_end_matches(['\r', '\n'])) else {
+                continue;
+            };
+            if caps[1].len() != key_indent || (i > start && caps[1].contains('-')) {
+                continue;
+            }
+            match &caps[2] {
+                "name" => found_name = unquote(&caps[3]) == name,
+                "accounts" => accounts = Some(i),
+                _ => {}
+            }
+        }
+        if let (true, Some(accounts)) = (found_name, accounts) {
+            return Some(accounts..list_end(lines, accounts, key_indent).min(end));
+        }
+    }
+    None
+}
+
+// 标量值去掉引号和行内注释
+fn unquote(value: &str) -> String {
+    let value = value.trim();
+    let value = match value.chars().next() {
+        Some('"' | '\'') => return serde_yaml::from_str::<String>(value.split(" #").next().unwrap_or(value)).unwrap_or_default(),
+        _ => value.split(" #").next().unwrap_or(value),
+    };
+    value.trim().to_string()
+}
+
+fn locate_yaml(lines: &[String], list: AccountList, email: &str) -> Result<Option<Entry>, Box<dyn std::error::Error>> {
+    let quoted_email = regex::escape(email);
+    let block = Regex::new(&format!(r#"^(\s*)(-\s+)?email:\s*(?:"{0}"|'{0}'|{0})\s*(?:#.*)?$"#, quoted_email))?;
+    let flow = Regex::new(&format!(r#"email:\s*(?:"{0}"|'{0}'|{0})\s*[,}}]"#, quoted_email))?;
+
+    let Some(range) = list.range(lines) else {
+        return Ok(None);
+    };
+    let mut found: Vec<Entry> = range
+        .filter(|&i| block.is_match(lines[i].trim_end_matches(['\r', '\n'])) || (lines[i].contains('{') && flow.is_match(&lines[i])))
+        .map(|i| entry_at(lines, &block, i))
+        .collect();
+    // 已归档的条目以 provider 字段区分部署
+    if let AccountList::Archived(provider) = list {
+        found.retain(|entry| entry_field(lines, entry, "provider").as_deref() == provider);
+    }
+    match found.len() {
+        0 => Ok(None),
+        1 => Ok(found.pop()),
+        n => Err(format!("account {} appears {} times, edit the file manually", email, n).into()),
+    }
+}
+
+// 条目中某个直接键的值（去掉引号）
+fn entry_field(lines: &[String], entry: &Entry, field: &str) -> Option<String> {
+    let Some(block) = &entry.block else {
+        let flow = Regex::new(&format!(r#"[{{,]\s*{}:\s*("(?:[^"\\]|\\.)*"|'[^']*'|[^,}}]*)"#, regex::escape(field))).ok()?;
+        return flow.captures(&lines[entry.line]).map(|caps| unquote(&caps[1]));
+    };
+    let key = Regex::new(&format!(r"^(\s*(?:-\s+)?){}:(.*)$", regex::escape(field))).ok()?;
+    (block.start..block.end).find_map(|i| {
+        let caps = key.captures(lines[i].trim_end_matches(['\r', '\n']))?;
+        (caps[1].len() == block.key_indent).then(|| unquote(&caps[2]))
+    })
+}
+
+fn entry_at(lines: &[String], block: &Regex, line_no: usize) -> Entry {
+    let Some(caps) = block.captures(lines[line_no].trim_end_matches(['\r', '\n'])) else {
+        return Entry { line: line_no, block: None };
+    };
+
+    // 块写法：同一列表项内与 email 相同缩进的键
+    let key_indent = caps[1].len() + caps.get(2).map_or(0, |dash| dash.as_str().len());
+    let mut start = line_no;
+    if caps.get(2).is_none() {
+        while start > 0 {
+            start -= 1;
+            let previous = &lines[start];
+            if is_content(previous) && indent(previous) + 2 <= key_indent && previous.trim_start().starts_with('-') {
+                break;
+            }
+        }
+    }
+    let mut end = (line_no + 1..lines.len())
+        .find(|&i| is_content(&lines[i]) && (indent(&lines[i]) < key_indent || (indent(&lines[i]) == key_indent && lines[i].trim_start().starts_with('-'))))
+        .unwrap_or(lines.len());
+    // 列表项之后的空行和缩进更少的注释属于后面的内容
+    while end > line_no + 1 && !is_content(&lines[end - 1]) && (lines[end - 1].trim().is_empty() || indent(&lines[end - 1]) < key_indent) {
+        end -= 1;
+    }
+    Entry { line: line_no, block: Some(Block { start, end, key_indent }) }
+}
+
+fn set_yaml(content: &str, list: AccountList, email: &str, field: &str, value: &Value, insert: bool) -> Result<Option<String>, Box<dyn std::error::Error>> {
+    let mut lines: Vec<String> = content.split_inclusive('\n').map(str::to_string).collect();
+    let quoted_value = serde_json::to_string(value)?;
+    let Some(entry) = locate_yaml(&lines, list, email)? else {
+        return Ok(None);
+    };
+
+    let line = lines[entry.line].clone();
+    let Some(block) = entry.block else {
+        // 单行写法 - {email: ..., cookie: ...}
+        let field_re = Regex::new(&format!(r#"({}:\s*)(?:"(?:[^"\\]|\\.)*"|'[^']*'|[^,}}]*)"#, regex::escape(field)))?;
+        if field_re.is_match(&line) {
+            lines[entry.line] = field_re.replace(&line, |caps: &regex::Captures| format!("{}{}", &caps[1], quoted_value)).into_owned();
+        } else if insert {
+            let close = line.rfind('}').ok_or_else(|| format!("cannot parse the entry of {}, edit the file manually", email))?;
+            lines[entry.line] = format!("{}, {}: {}{}", line[..close].trim_end(), field, quoted_value, &line[close..]);
+        }
+        return Ok(Some(lines.concat()));
+    };
+
+    let field_re = Regex::new(&format!(r#"^(\s*(?:-\s+)?){}:(.*?)(\r?\n)?$"#, regex::escape(field)))?;
+    for i in block.start..block.end {
+        let Some(caps) = field_re.captures(&lines[i]) else {
+            continue;
+        };
+        if caps[1].len() != block.key_indent {
+            continue;
+        }
+        let current = caps[2].trim();
+        if current.starts_with('|') || current.starts_with('>') {
+            return Err(format!("{} of {} uses a multi-line value, edit the file manually", field, email).into());
+        }
+        let newline = caps.get(3).map_or("", |newline| newline.as_str());
+        lines[i] = format!("{}{}: {}{}{}", &caps[1], field, quoted_value, trailing_comment(current), newline);
+        return Ok(Some(lines.concat()));
+    }
+    if field == "cookie" {
+        return Err(format!("account {} has no cookie field", email).into());
+    }
+    if insert {
+        if !lines[entry.line].ends_with('\n') {
+            lines[entry.line].push('\n');
+        }
+        lines.insert(entry.line + 1, format!("{}{}: {}\n", " ".repeat(block.key_indent), field, quoted_value));
+    }
+    Ok(Some(lines.concat()))
+}
+
+// 值后面的行内注释（" # ..."），改写时原样保留
+fn trailing_comment(value: &str) -> String {
+    let value_end = match value.chars().next() {
+        Some(quote @ ('"' | '\'')) => {
+            let mut escaped = false;
+            value.char_indices().skip(1)
+                .find(|&(_, c)| {
+                    let closes = c == quote && !escaped;
+                    escaped = quote == '"' && c == '\\' && !escaped;
+                    closes
+                })
+                .map_or(value.len(), |(end, _)| end + 1)
+        }
+        _ => value.find(" #").unwrap_or(value.len()),
+    };
+    let rest = value[value_end..].trim();
+    if rest.starts_with('#') { format!(" {}", rest) } else { String::new() }
+}
+
+fn set_ndjson(content: &str, email: &str, field: &str, value: &Value, insert: bool) -> Result<Option<String>, Box<dyn std::error::Error>> {
+    let mut found = false;
+    let mut output = String::with_capacity(content.len());
+    for line in content.split_inclusive('\n') {
+        let trimmed = line.trim();
+        if !trimmed.is_empty() && !trimmed.starts_with('#') {
+            let mut record: Value = serde_json::from_str(trimmed)?;
+            if record["email"].as_str() == Some(email) {
+                found = true;
+                if insert || field == "cookie" || record.get(field).is_some() {
+                    record[field] = value.clone();
+                }
+                output.push_str(&serde_json::to_string(&record)?);
+                output.push_str(if line.ends_with('\n') { "\n" } else { "" });
+                continue;
+            }
+        }
+        output.push_str(line);
+    }
+    Ok(found.then_some(output))
+}
+
+fn set_csv(content: &str, email: &str, value: &str) -> Option<String> {
+    let mut found = false;
+    let output = content.split_inclusive('\n')
+        .map(|line| match line.split_once(',') {
+            Some((line_email, _)) if line_email.trim() == email => {
+                found = true;
+                let newline = &line[line.trim_end_matches(['\r', '\n']).len()..];
+                format!("{},{}{}", email, value, newline)
+            }
+            _ => line.to_string(),
+        })
+        .collect();
+    found.then_some(output)
+}
+
+fn remove_lines(content: &str, matches: impl Fn(&str) -> Result<bool, Box<dyn std::error::Error>>) -> Result<Option<String>, Box<dyn std::error::Error>> {
+    let mut found = false;
+    let mut output = String::with_capacity(content.len());
+    for line in content.split_inclusive('\n') {
+        let trimmed = line.trim();
+        if !trimmed.is_empty() && !trimmed.starts_with('#') && matches(trimmed)? {
+            found = true;
+            continue;
+        }
+        output.push_str(line);
+    }
+    Ok(found.then_some(output))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONFIG: &str = "\
+# 主配置
+provider: glados
+accounts:
+  - email: a@example.com
+    cookie: \"default-a\" # 默认部署
+  - {email: b@example.com, cookie: \"default-b\"}
+
+providers:
+  - name: mirror
+    provider: glados
+    accounts:
+      - email: a@example.com
+        cookie: \"mirror-a\"
+      - {email: b@example.com, cookie: \"mirror-b\"}
+  - provider: glados
+    name: 'backup'
+    accounts:
+    - email: a@example.com
+      cookie: \"backup-a\"
+
+archived_accounts:
+  - email: a@example.com
+    cookie: \"archived-default\"
+    archived_at: \"2024-01-01\"
+  - email: a@example.com
+    provider: mirror
+    cookie: \"archived-mirror\"
+    archived_at: \"2024-01-02\"
+
+log_file: run.log
+";
+
+    fn parsed(content: &str) -> serde_yaml::Value {
+        serde_yaml::from_str(content).unwrap()
+    }
+
+    fn cookie(content: &str, path: &[&str], index: usize) -> String {
+        let mut value = parsed(content);
+        for key in path {
+            value = value[*key].clone();
+        }
+        value[index]["cookie"].as_str().unwrap().to_string()
+    }
+
+    #[test]
+    fn put_only_edits_the_target_deployment() {
+        let updated = put_account_field("config.yaml", CONFIG, AccountList::Provider("mirror"), "a@example.com", "cookie", &"new".into()).unwrap().unwrap();
+        let value = parsed(&updated);
+        assert_eq!(value["providers"][0]["accounts"][0]["cookie"], "new");
+        assert_eq!(cookie(&updated, &["accounts"], 0), "default-a");
+        assert_eq!(value["providers"][1]["accounts"][0]["cookie"], "backup-a");
+        assert_eq!(cookie(&updated, &["archived_accounts"], 0), "archived-default");
+
+        let updated = put_account_field("config.yaml", CONFIG, AccountList::Default, "a@example.com", "cookie", &"new".into()).unwrap().unwrap();
+        assert_eq!(cookie(&updated, &["accounts"], 0), "new");
+        assert!(updated.contains("# 默认部署"), "trailing comment kept: {}", updated);
+        assert_eq!(parsed(&updated)["providers"][0]["accounts"][0]["cookie"], "mirror-a");
+    }
+
+    #[test]
+    fn set_only_edits_the_target_deployment() {
+        let updated = set_account_field("config.yaml", CONFIG, AccountList::Provider("backup"), "a@example.com", "cookie", &"new".into()).unwrap().unwrap();
+        let value = parsed(&updated);
+        assert_eq!(value["providers"][1]["accounts"][0]["cookie"], "new");
+        assert_eq!(value["providers"][0]["accounts"][0]["cookie"], "mirror-a");
+        assert_eq!(cookie(&updated, &["accounts"], 0), "default-a");
+
+        // 不存在的字段不添加
+        let unchanged = set_account_field("config.yaml", CONFIG, AccountList::Default, "a@example.com", "cookie_updated_at", &"2024-01-01".into()).unwrap().unwrap();
+        assert_eq!(unchanged, CONFIG);
+    }
+
+    #[test]
+    fn put_matches_quoted_names_and_indentless_lists() {
+        let updated = put_account_field("config.yaml", CONFIG, AccountList::Provider("backup"), "a@example.com", "disabled", &true.into()).unwrap().unwrap();
+        let value = parsed(&updated);
+        assert_eq!(value["providers"][1]["accounts"][0]["disabled"], true);
+        assert!(value["providers"][0]["accounts"][0]["disabled"].is_null());
+        assert!(value["accounts"][0]["disabled"].is_null());
+    }
+
+    #[test]
+    fn put_edits_flow_entries_in_scope() {
+        let updated = put_account_field("config.yaml", CONFIG, AccountList::Provider("mirror"), "b@example.com", "disabled", &true.into()).unwrap().unwrap();
+        let value = parsed(&updated);
+        assert_eq!(value["providers"][0]["accounts"][1]["disabled"], true);
+        assert!(value["accounts"][1]["disabled"].is_null());
+    }
+
+    #[test]
+    fn missing_accounts_are_not_found() {
+        assert!(put_account_field("config.yaml", CONFIG, AccountList::Provider("backup"), "b@example.com", "disabled", &true.into()).unwrap().is_none());
+        assert!(put_account_field("config.yaml", CONFIG, AccountList::Provider("unknown"), "a@example.com", "disabled", &true.into()).unwrap().is_none());
+        assert!(remove_account("config.yaml", CONFIG, AccountList::Archived(Some("backup")), "a@example.com").unwrap().is_none());
+    }
+
+    #[test]
+    fn remove_only_removes_the_target_entry() {
+        let updated = remove_account("config.yaml", CONFIG, AccountList::Provider("mirror"), "a@example.com").unwrap().unwrap();
+        let value = parsed(&updated);
+        assert_eq!(value["providers"][0]["accounts"].as_sequence().unwrap().len(), 1);
+        assert_eq!(value["providers"][0]["accounts"][0]["cookie"], "mirror-b");
+        assert_eq!(value["accounts"].as_sequence().unwrap().len(), 2);
+        assert_eq!(value["providers"][1]["accounts"].as_sequence().unwrap().len(), 1);
+        assert_eq!(value["log_file"], "run.log");
+
+        let updated = remove_account("config.yaml", CONFIG, AccountList::Default, "b@example.com").unwrap().unwrap();
+        let value = parsed(&updated);
+        assert_eq!(value["accounts"].as_sequence().unwrap().len(), 1);
+        assert_eq!(value["providers"][0]["accounts"][1]["cookie"], "mirror-b");
+    }
+
+    #[test]
+    fn remove_archived_entry_by_provider() {
+        let updated = remove_account("config.yaml", CONFIG, AccountList::Archived(None), "a@example.com").unwrap().unwrap();
+        assert_eq!(cookie(&updated, &["archived_accounts"], 0), "archived-mirror");
+        assert_eq!(parsed(&updated)["archived_accounts"].as_sequence().unwrap().len(), 1);
+
+        let updated = remove_account("config.yaml", CONFIG, AccountList::Archived(Some("mirror")), "a@example.com").unwrap().unwrap();
+        assert_eq!(cookie(&updated, &["archived_accounts"], 0), "archived-default");
+        assert_eq!(parsed(&updated)["archived_accounts"].as_sequence().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn duplicates_within_scope_are_rejected() {
+        let content = "accounts:\n  - email: a@example.com\n    cookie: x\n  - email: a@example.com\n    cookie: y\n";
+        let error = put_account_field("config.yaml", content, AccountList::Default, "a@example.com", "cookie", &"z".into()).unwrap_err();
+        assert!(error.to_string().contains("appears 2 times"), "{}", error);
+    }
+
+    #[test]
+    fn add_and_remove_empty_list() {
+        let content = "accounts:\n  - email: a@example.com\n    cookie: x\n\nlog_file: run.log\n";
+        let updated = add_account("config.yaml", content, "archived_accounts", &[("email", "b@example.com".into()), ("cookie", "y".into())]).unwrap();
+        assert_eq!(cookie(&updated, &["archived_accounts"], 0), "y");
+        let updated = remove_account("config.yaml", &updated, AccountList::Archived(None), "b@example.com").unwrap().unwrap();
+        assert_eq!(remove_empty_list(&updated, "archived_accounts"), content);
+    }
+
+    #[test]
+    fn ndjson_and_csv_ignore_scope() {
+        let ndjson = "{\"email\":\"a@example.com\",\"cookie\":\"x\"}\n{\"email\":\"b@example.com\",\"cookie\":\"y\"}\n";
+        let updated = put_account_field("accounts.ndjson", ndjson, AccountList::Provider("mirror"), "b@example.com", "disabled", &true.into()).unwrap().unwrap();
+        assert_eq!(updated.lines().nth(1).map(|line| serde_json::from_str::<Value>(line).unwrap()["disabled"].clone()), Some(Value::Bool(true)));
+        let updated = remove_account("accounts.ndjson", ndjson, AccountList::Default, "a@example.com").unwrap().unwrap();
+        assert_eq!(updated.lines().count(), 1);
+
+        let csv = "email,cookie\na@example.com,x\nb@example.com,y\n";
+        let updated = remove_account("accounts.csv", csv, AccountList::Default, "a@example.com").unwrap().unwrap();
+        assert_eq!(updated, "email,cookie\nb@example.com,y\n");
+    }
+}
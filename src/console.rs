@@ -0,0 +1,42 @@
+use std::sync::OnceLock;
+
+use crate::output::OutputFormat;
+
+// Windows 控制台默认使用系统代码页（中文系统为 GBK/936），直接输出 UTF-8 中文会乱码，启动时切换为 UTF-8
+#[cfg(windows)]
+pub fn init() {
+    const CP_UTF8: u32 = 65001;
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn SetConsoleOutputCP(code_page_id: u32) -> i32;
+        fn SetConsoleCP(code_page_id: u32) -> i32;
+    }
+    unsafe {
+        if SetConsoleOutputCP(CP_UTF8) == 0 {
+            eprintln!("warning: failed to switch console to UTF-8, output may be garbled");
+        }
+        SetConsoleCP(CP_UTF8);
+    }
+}
+
+#[cfg(not(windows))]
+pub fn init() {}
+
+static RESULT_FORMAT: OnceLock<OutputFormat> = OnceLock::new();
+
+// web --output：签到结果按该格式写到标准输出；不是表格时标准输出只留给结果，签到过程中的提示改写到标准错误
+pub fn set_result_format(format: OutputFormat) {
+    let _ = RESULT_FORMAT.set(format);
+}
+
+pub fn result_format() -> OutputFormat {
+    RESULT_FORMAT.get().copied().unwrap_or_default()
+}
+
+// 签到过程中的提示（跳过、等待、汇总等），--output 为表格时写到标准输出，否则写到标准错误
+pub fn progress(line: &str) {
+    match result_format() {
+        OutputFormat::Table => println!("{}", line),
+        _ => eprintln!("{}", line),
+    }
+}
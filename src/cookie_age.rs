@@ -0,0 +1,58 @@
+use chrono::NaiveDate;
+use std::sync::Arc;
+
+use crate::{
+    config::Account,
+    cookie_pool,
+    state_store::{CookieRecord, StateStore},
+};
+
+pub struct CookieTracker {
+    store: Arc<dyn StateStore>,
+    lifetime_days: i64,
+    remind_before_days: i64,
+}
+
+impl CookieTracker {
+    pub fn new(store: Arc<dyn StateStore>, lifetime_days: u32, remind_before_days: u32) -> Self {
+        Self {
+            store,
+            lifetime_days: lifetime_days as i64,
+            remind_before_days: remind_before_days as i64,
+        }
+    }
+
+    // cookie 更新日期优先取账户配置的 cookie_updated_at，否则取本工具首次见到该 cookie 的日期；每个账户每天最多提醒一次
+    pub fn check(&self, key: &str, account: &Account, today: NaiveDate) -> Result<Option<String>, Box<dyn std::error::Error>> {
+        let hash = cookie_pool::hash(&account.cookie);
+        self.store.update(key, |state| {
+            if state.cookie.as_ref().is_some_and(|record| record.hash != hash) {
+                state.cookie = None;
+            }
+            let record = state.cookie.get_or_insert_with(|| CookieRecord {
+                hash,
+                first_seen: today.format("%Y-%m-%d").to_string(),
+                last_reminded: None,
+            });
+            self.remind(record, account, today)
+        })
+    }
+
+    fn remind(&self, record: &mut CookieRecord, account: &Account, today: NaiveDate) -> Option<String> {
+        let today_str = today.format("%Y-%m-%d").to_string();
+        let updated = account.cookie_updated_at.as_deref().unwrap_or(&record.first_seen);
+        let updated = NaiveDate::parse_from_str(updated, "%Y-%m-%d").ok()?;
+        let age = (today - updated).num_days();
+        let remaining = self.lifetime_days - age;
+        if remaining > self.remind_before_days || record.last_reminded.as_deref() == Some(today_str.as_str()) {
+            return None;
+        }
+        record.last_reminded = Some(today_str);
+
+        Some(if remaining > 0 {
+            format!("账户 {} 的 cookie 预计约 {} 天后过期（已使用 {} 天），请及时更新", account.email, remaining, age)
+        } else {
+            format!("账户 {} 的 cookie 已使用 {} 天，可能已经过期，请尽快更新", account.email, age)
+        })
+    }
+}
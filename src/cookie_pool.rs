@@ -0,0 +1,111 @@
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+
+use crate::{
+    config::Account,
+    secret::SecretString,
+    state_store::AccountState,
+};
+
+pub fn hash(cookie: &SecretString) -> String {
+    hex::encode(Sha256::digest(cookie.expose().as_bytes()))
+}
+
+// 逐个加入账户，按 cookie（含备用 cookie）的摘要归组账户键；只保存摘要，不保存 cookie 本身
+#[derive(Default)]
+pub struct Owners {
+    index: HashMap<[u8; 32], usize>,
+    groups: Vec<Vec<String>>,
+}
+
+impl Owners {
+    pub fn add(&mut self, key: &str, account: &Account) {
+        for cookie in std::iter::once(&account.cookie).chain(&account.cookies) {
+            let next = self.groups.len();
+            let group = *self.index.entry(Sha256::digest(cookie.expose().as_bytes()).into()).or_insert(next);
+            if group == next {
+                self.groups.push(Vec::new());
+            }
+            if !self.groups[group].iter().any(|owner| owner == key) {
+                self.groups[group].push(key.to_string());
+            }
+        }
+    }
+
+    // 多个账户共用的 cookie，每组按出现顺序列出账户键
+    pub fn shared(self) -> Vec<Vec<String>> {
+        self.groups.into_iter().filter(|owners| owners.len() > 1).collect()
+    }
+}
+
+// 配置中的全部 cookie，标签按配置顺序编号：#1 为 cookie，#2 起为 cookies 中的备用项
+pub fn all(account: &Account) -> Vec<(String, &SecretString)> {
+    let mut seen = Vec::new();
+    std::iter::once(&account.cookie)
+        .chain(&account.cookies)
+        .enumerate()
+        .filter(|(_, cookie)| {
+            let new = !seen.contains(cookie);
+            seen.push(*cookie);
+            new
+        })
+        .map(|(index, cookie)| (format!("#{}", index + 1), cookie))
+        .collect()
+}
+
+// 连续失效次数少的优先，其次是最近成功过的，其余保持配置顺序
+pub fn ordered<'a>(account: &'a Account, state: &AccountState) -> Vec<(String, &'a SecretString)> {
+    let mut cookies = all(account);
+    cookies.sort_by_cached_key(|(_, cookie)| {
+        let health = state.cookie_health.get(&hash(cookie));
+        let streak = health.map_or(0, |health| health.failure_streak);
+        let last_success = health.and_then(|health| health.last_success.clone());
+        (streak, std::cmp::Reverse(last_success))
+    });
+    cookies
+}
+
+pub fn dead(account: &Account, state: &AccountState) -> Vec<String> {
+    all(account).into_iter()
+        .filter(|(_, cookie)| state.cookie_health.get(&hash(cookie)).is_some_and(|health| health.failure_streak > 0))
+        .map(|(label, _)| label)
+        .collect()
+}
+
+// 只保留当前仍在配置中的 cookie 的记录，替换掉的旧 cookie 不再出现在状态中
+pub fn record(state: &mut AccountState, account: &Account, cookie: &SecretString, success: bool) {
+    let current: Vec<String> = all(account).into_iter().map(|(_, cookie)| hash(cookie)).collect();
+    state.cookie_health.retain(|hash, _| current.contains(hash));
+    let now = chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string();
+    let health = state.cookie_health.entry(hash(cookie)).or_default();
+    if success {
+        health.last_success = Some(now);
+        health.failure_streak = 0;
+    } else {
+        health.last_failure = Some(now);
+        health.failure_streak += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn account(cookie: &str, cookies: &[&str]) -> Account {
+        Account {
+            cookie: SecretString::new(cookie),
+            cookies: cookies.iter().map(|cookie| SecretString::new(*cookie)).collect(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn owners_group_accounts_by_cookie() {
+        let mut owners = Owners::default();
+        owners.add("a", &account("koa:sess=1", &["koa:sess=1"]));
+        owners.add("b", &account("koa:sess=2", &["koa:sess=1"]));
+        owners.add("c", &account("koa:sess=3", &[]));
+        owners.add("main/a", &account("koa:sess=2", &[]));
+        assert_eq!(owners.shared(), [vec!["a", "b"], vec!["b", "main/a"]]);
+    }
+}
@@ -0,0 +1,185 @@
+use chrono::{Datelike, Duration, NaiveDate, NaiveDateTime, NaiveTime, Timelike};
+
+// 最多向后查找的年数；2 月 29 日这类表达式最多四年触发一次
+const MAX_SEARCH_YEARS: i32 = 5;
+
+// 标准 5 字段 cron 表达式：分 时 日 月 周，支持 * 、列表 a,b、范围 a-b、步长 */n 和 a-b/n，
+// 周 0 和 7 均为周日；日和周都不是 * 时满足其一即触发（与 cron 相同）
+#[derive(Debug, Clone)]
+pub struct Schedule {
+    minutes: u64,
+    hours: u64,
+    days: u64,
+    months: u64,
+    weekdays: u64,
+    any_day: bool,
+    any_weekday: bool,
+}
+
+impl Schedule {
+    pub fn parse(expression: &str) -> Result<Self, String> {
+        let expanded = match expression.trim() {
+            "@hourly" => "0 * * * *",
+            "@daily" | "@midnight" => "0 0 * * *",
+            "@weekly" => "0 0 * * 0",
+            "@monthly" => "0 0 1 * *",
+            other => other,
+        };
+        let fields: Vec<&str> = expanded.split_whitespace().collect();
+        let [minute, hour, day, month, weekday] = fields.as_slice() else {
+            return Err(format!("expected 5 fields (minute hour day month weekday), got {}", fields.len()));
+        };
+        let mut weekdays = field(weekday, 0, 7, "weekday")?;
+        // 7 也表示周日
+        if weekdays & (1 << 7) != 0 {
+            weekdays |= 1;
+        }
+        let schedule = Self {
+            minutes: field(minute, 0, 59, "minute")?,
+            hours: field(hour, 0, 23, "hour")?,
+            days: field(day, 1, 31, "day")?,
+            months: field(month, 1, 12, "month")?,
+            weekdays,
+            any_day: *day == "*",
+            any_weekday: *weekday == "*",
+        };
+        if schedule.next_after(NaiveDate::from_ymd_opt(2000, 1, 1).unwrap_or_default().and_time(NaiveTime::MIN)).is_none() {
+            return Err(format!("'{}' never matches a date", expression));
+        }
+        Ok(schedule)
+    }
+
+    // after 之后（不含）的第一个触发时刻，精确到分钟
+    pub fn next_after(&self, after: NaiveDateTime) -> Option<NaiveDateTime> {
+        let mut time = after.with_second(0)?.with_nanosecond(0)? + Duration::minutes(1);
+        let limit = after + Duration::days(366 * i64::from(MAX_SEARCH_YEARS));
+        while time <= limit {
+            if !bit(self.months, time.month()) {
+                let (year, month) = if time.month() == 12 { (time.year() + 1, 1) } else { (time.year(), time.month() + 1) };
+                time = NaiveDate::from_ymd_opt(year, month, 1)?.and_time(NaiveTime::MIN);
+            } else if !self.matches_day(time.date()) {
+                time = time.date().succ_opt()?.and_time(NaiveTime::MIN);
+            } else if !bit(self.hours, time.hour()) {
+                time = time.with_minute(0)? + Duration::hours(1);
+            } else if !bit(self.minutes, time.minute()) {
+                time += Duration::minutes(1);
+            } else {
+                return Some(time);
+            }
+        }
+        None
+    }
+
+    // date 当天、不晚于 until 的最后一个触发时刻
+    pub fn last_on(&self, date: NaiveDate, until: NaiveDateTime) -> Option<NaiveDateTime> {
+        let mut time = date.and_time(NaiveTime::MIN) - Duration::minutes(1);
+        let mut last = None;
+        while let Some(next) = self.next_after(time).filter(|next| next.date() == date && *next <= until) {
+            last = Some(next);
+            time = next;
+        }
+        last
+    }
+
+    fn matches_day(&self, date: NaiveDate) -> bool {
+        let day = bit(self.days, date.day());
+        let weekday = bit(self.weekdays, date.weekday().num_days_from_sunday());
+        match (self.any_day, self.any_weekday) {
+            (true, true) => true,
+            (false, true) => day,
+            (true, false) => weekday,
+            (false, false) => day || weekday,
+        }
+    }
+}
+
+fn bit(mask: u64, value: u32) -> bool {
+    mask & (1 << value) != 0
+}
+
+fn field(spec: &str, min: u32, max: u32, name: &str) -> Result<u64, String> {
+    let mut mask = 0u64;
+    for part in spec.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => (range, step.parse::<u32>().ok().filter(|&step| step > 0)
+                .ok_or_else(|| format!("invalid step in {} field '{}'", name, spec))?),
+            None => (part, 1),
+        };
+        let (start, end) = if range == "*" {
+            (min, max)
+        } else if let Some((start, end)) = range.split_once('-') {
+            (number(start, min, max, name)?, number(end, min, max, name)?)
+        } else {
+            let value = number(range, min, max, name)?;
+            // a/n 表示从 a 开始到最大值每 n 个
+            (value, if part.contains('/') { max } else { value })
+        };
+        if start > end {
+            return Err(format!("invalid range '{}' in {} field", range, name));
+        }
+        for value in (start..=end).step_by(step as usize) {
+            mask |= 1 << value;
+        }
+    }
+    Ok(mask)
+}
+
+fn number(value: &str, min: u32, max: u32, name: &str) -> Result<u32, String> {
+    value.parse::<u32>().ok().filter(|value| (min..=max).contains(value))
+        .ok_or_else(|| format!("{} value '{}' must be between {} and {}", name, value, min, max))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(text: &str) -> NaiveDateTime {
+        NaiveDateTime::parse_from_str(text, "%Y-%m-%d %H:%M").unwrap()
+    }
+
+    fn next(expression: &str, after: &str) -> String {
+        Schedule::parse(expression).unwrap().next_after(at(after)).unwrap().format("%Y-%m-%d %H:%M").to_string()
+    }
+
+    #[test]
+    fn next_after_is_exclusive() {
+        assert_eq!(next("30 8 * * *", "2024-10-15 08:29"), "2024-10-15 08:30");
+        assert_eq!(next("30 8 * * *", "2024-10-15 08:30"), "2024-10-16 08:30");
+        assert_eq!(next("@hourly", "2024-12-31 23:00"), "2025-01-01 00:00");
+    }
+
+    #[test]
+    fn lists_ranges_and_steps() {
+        assert_eq!(next("*/20 * * * *", "2024-10-15 08:41"), "2024-10-15 09:00");
+        assert_eq!(next("0 9-17/4 * * *", "2024-10-15 13:00"), "2024-10-15 17:00");
+        assert_eq!(next("5/30 * * * *", "2024-10-15 08:06"), "2024-10-15 08:35");
+        assert_eq!(next("0 8,20 * * *", "2024-10-15 09:00"), "2024-10-15 20:00");
+    }
+
+    #[test]
+    fn weekdays_and_days() {
+        // 2024-10-15 是周二；0 和 7 都是周日
+        assert_eq!(next("0 0 * * 0", "2024-10-15 00:00"), "2024-10-20 00:00");
+        assert_eq!(next("0 0 * * 7", "2024-10-15 00:00"), "2024-10-20 00:00");
+        assert_eq!(next("0 0 1 * *", "2024-10-15 00:00"), "2024-11-01 00:00");
+        // 日和周都指定时满足其一即可
+        assert_eq!(next("0 0 1 * 5", "2024-10-15 00:00"), "2024-10-18 00:00");
+        assert_eq!(next("0 0 29 2 *", "2024-03-01 00:00"), "2028-02-29 00:00");
+    }
+
+    #[test]
+    fn last_on_date() {
+        let schedule = Schedule::parse("0 */6 * * *").unwrap();
+        let date = NaiveDate::from_ymd_opt(2024, 10, 15).unwrap();
+        assert_eq!(schedule.last_on(date, at("2024-10-15 13:00")), Some(at("2024-10-15 12:00")));
+        assert_eq!(schedule.last_on(date, at("2024-10-15 00:00")), Some(at("2024-10-15 00:00")));
+        assert_eq!(Schedule::parse("0 8 * * *").unwrap().last_on(date, at("2024-10-15 07:59")), None);
+    }
+
+    #[test]
+    fn rejects_invalid_expressions() {
+        for expression in ["", "* * * *", "60 * * * *", "* 24 * * *", "* * 0 * *", "* * * 13 *", "* * * * 8", "*/0 * * * *", "10-5 * * * *", "a * * * *", "0 0 31 2 *"] {
+            assert!(Schedule::parse(expression).is_err(), "{}", expression);
+        }
+    }
+}
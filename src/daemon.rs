@@ -0,0 +1,282 @@
+use chrono::{DateTime, Local, NaiveDateTime, TimeZone};
+use chrono_tz::Tz;
+use rand::Rng;
+use std::collections::BTreeSet;
+use tokio::time::Duration;
+use tokio_util::sync::CancellationToken;
+
+use crate::{
+    cli::{CheckinArgs, Cli},
+    config::Config,
+    console,
+    cron::Schedule,
+    daemon_lock::DaemonLock,
+    logger::Logger,
+    runner, shutdown, state_store, systemd,
+};
+
+// 睡眠期间分段检查墙上时间，系统休眠或调整时钟后不会错过或推迟太久
+const MAX_SLEEP: Duration = Duration::from_secs(60);
+
+// 计划相同（cron 表达式和时区均相同）的账户在同一时刻一起运行
+struct Slot {
+    expression: String,
+    timezone: Option<Tz>,
+    schedule: Schedule,
+    accounts: BTreeSet<String>,
+}
+
+impl Slot {
+    fn is(&self, (expression, timezone): &(String, Option<Tz>)) -> bool {
+        self.expression == *expression && self.timezone == *timezone
+    }
+
+    fn describe(&self) -> String {
+        match self.timezone {
+            Some(tz) => format!("{} ({})", self.expression, tz),
+            None => self.expression.clone(),
+        }
+    }
+
+    // 该计划所在时区的墙上时间
+    fn wall(&self, now: DateTime<Local>) -> NaiveDateTime {
+        match self.timezone {
+            Some(tz) => now.with_timezone(&tz).naive_local(),
+            None => now.naive_local(),
+        }
+    }
+
+    fn localize(&self, time: NaiveDateTime) -> Option<DateTime<Local>> {
+        match self.timezone {
+            Some(tz) => tz.from_local_datetime(&time).earliest().map(|time| time.with_timezone(&Local)),
+            None => Local.from_local_datetime(&time).earliest(),
+        }
+    }
+
+    fn next_after(&self, now: DateTime<Local>) -> Option<DateTime<Local>> {
+        self.localize(self.schedule.next_after(self.wall(now))?)
+    }
+}
+
+// web --daemon：按配置中的 schedule（本地时间）定时签到，不依赖系统 cron；账户可以用自己的
+// schedule/schedule_timezone 覆盖全局计划。每次运行前重新加载配置，与 serve 共用锁文件，同一时间只有一个常驻进程
+pub async fn run(cli: &Cli, args: &CheckinArgs) -> Result<(), Box<dyn std::error::Error>> {
+    let config = load(cli, args)?;
+    let _lock = DaemonLock::acquire(&config.trigger.lock_file, args.takeover, Duration::from_secs(args.takeover_timeout)).await?;
+    let shutdown = shutdown::install();
+    let mut slots = plan(&config)?;
+    let mut jitter_minutes = config.jitter_minutes;
+    let mut logger = config.file_logger();
+    log(&logger, &format!("常驻模式已启动，计划: {}", describe(&slots)));
+    systemd::notify("READY=1");
+    systemd::spawn_watchdog(shutdown.clone());
+
+    // 机器休眠或重启错过了今天的计划时间时立即补签，今天已成功的账户由运行本身跳过
+    match missed_slots(&config, &slots) {
+        Ok(missed) if !missed.is_empty() => {
+            let mut accounts = BTreeSet::new();
+            for slot in missed {
+                log(&logger, &format!("已错过今天 {} 的签到（{} 个账户今天尚未成功），立即补签", slot.at.format("%H:%M"), slot.pending));
+                accounts.extend(slots[slot.index].accounts.iter().cloned());
+            }
+            let selection = (accounts.len() < slots.iter().map(|slot| slot.accounts.len()).sum()).then_some(accounts);
+            run_once(config, selection, &shutdown, &logger).await;
+        }
+        Ok(_) => {}
+        Err(e) => log(&logger, &format!("检查是否错过签到失败: {}", e)),
+    }
+
+    while !shutdown.is_cancelled() {
+        let now = Local::now();
+        let Some(scheduled) = slots.iter().filter_map(|slot| slot.next_after(now)).min() else {
+            return Err("schedule has no upcoming run".into());
+        };
+        // 同一时刻到期的计划可能有多个；记下它们而不是账户，重新加载配置后按新配置中的账户运行
+        let due: Vec<(String, Option<Tz>)> = slots.iter()
+            .filter(|slot| slot.next_after(now) == Some(scheduled))
+            .map(|slot| (slot.expression.clone(), slot.timezone))
+            .collect();
+        // 在计划时间之后的 jitter_minutes 分钟内随机选一个时刻，避免每天在同一秒请求
+        let delay = rand::thread_rng().gen_range(0..=jitter_minutes * 60);
+        let next = scheduled + chrono::Duration::seconds(delay as i64);
+        let scope = if due.len() < slots.len() {
+            let accounts: usize = slots.iter().filter(|slot| due.iter().any(|key| slot.is(key))).map(|slot| slot.accounts.len()).sum();
+            format!("，{} 个账户", accounts)
+        } else {
+            String::new()
+        };
+        if delay == 0 {
+            log(&logger, &format!("下次签到时间: {}{}", next.format("%Y-%m-%d %H:%M"), scope));
+        } else {
+            log(&logger, &format!("下次签到时间: {}（计划 {}，随机延迟 {} 秒{}）",
+                next.format("%Y-%m-%d %H:%M:%S"), scheduled.format("%H:%M"), delay, scope));
+        }
+        systemd::notify(&format!("STATUS=下次签到时间: {}", next.format("%Y-%m-%d %H:%M:%S")));
+        if !sleep_until(next, &shutdown).await {
+            break;
+        }
+
+        // 配置在两次运行之间可能被修改；加载失败时沿用上一次的计划，跳过本次
+        let config = match load(cli, args) {
+            Ok(config) => config,
+            Err(e) => {
+                log(&logger, &format!("加载配置失败，跳过本次签到: {}", e));
+                continue;
+            }
+        };
+        logger = config.file_logger();
+        jitter_minutes = config.jitter_minutes;
+        match plan(&config) {
+            Ok(updated) => slots = updated,
+            Err(e) => log(&logger, &format!("schedule 无效，沿用之前的计划: {}", e)),
+        }
+        // 所有计划都到期（包括只有全局 schedule 的情况）时运行全部账户
+        let selection = (!slots.iter().all(|slot| due.iter().any(|key| slot.is(key)))).then(|| {
+            slots.iter().filter(|slot| due.iter().any(|key| slot.is(key)))
+                .flat_map(|slot| slot.accounts.iter().cloned())
+                .collect()
+        });
+        run_once(config, selection, &shutdown, &logger).await;
+    }
+    systemd::notify("STOPPING=1");
+    log(&logger, "常驻模式已停止");
+    Ok(())
+}
+
+// accounts 为 None 时运行全部账户，否则只运行其中的账户（账户键）；服务端处于维护时段时推迟到维护结束后运行
+async fn run_once(mut config: Config, accounts: Option<BTreeSet<String>>, shutdown: &CancellationToken, logger: &dyn Logger) {
+    if let Some(accounts) = accounts {
+        if let Err(e) = config.retain_accounts(|key, _| accounts.contains(key)) {
+            log(logger, &format!("跳过本次签到: {}", e));
+            return;
+        }
+    }
+    if let Err(e) = runner::ensure_allowed_window(&config, false) {
+        log(logger, &format!("跳过本次签到: {}", e));
+        return;
+    }
+    match runner::maintenance_remaining(&config) {
+        Ok(Some(delay)) => {
+            log(logger, &format!("服务端维护中，签到推迟 {} 秒后执行", delay.as_secs()));
+            tokio::select! {
+                _ = tokio::time::sleep(delay) => {}
+                _ = shutdown.cancelled() => {
+                    log(logger, "常驻模式停止，取消推迟的签到");
+                    return;
+                }
+            }
+        }
+        Ok(None) => {}
+        Err(e) => log(logger, &format!("检查维护时段失败: {}", e)),
+    }
+    if let Err(e) = runner::execute(&config, false, shutdown).await {
+        log(logger, &format!("本次签到失败: {}", e));
+    }
+}
+
+struct Missed {
+    index: usize,
+    at: DateTime<Local>,
+    pending: usize,
+}
+
+// 每个计划中今天最近一次计划时间已过，且有账户的最近成功时间（账户状态中的 last_success）不是计划当天时，
+// 返回该计划的序号、计划时间（本地时间）和这些账户的数量
+fn missed_slots(config: &Config, slots: &[Slot]) -> Result<Vec<Missed>, Box<dyn std::error::Error>> {
+    let store = state_store::open(&config.state_store)?;
+    let now = Local::now();
+    let mut missed = Vec::new();
+    for (index, slot) in slots.iter().enumerate() {
+        let wall = slot.wall(now);
+        let Some(last) = slot.schedule.last_on(wall.date(), wall).and_then(|last| slot.localize(last)) else {
+            continue;
+        };
+        let day = last.format("%Y-%m-%d").to_string();
+        let mut count = 0;
+        for key in &slot.accounts {
+            if !store.load(key)?.last_success.is_some_and(|last_success| last_success.starts_with(&day)) {
+                count += 1;
+            }
+        }
+        if count > 0 {
+            missed.push(Missed { index, at: last, pending: count });
+        }
+    }
+    Ok(missed)
+}
+
+fn load(cli: &Cli, args: &CheckinArgs) -> Result<Config, Box<dyn std::error::Error>> {
+    let mut config = Config::load_with_overrides(&cli.config_path()?, cli.profile.as_deref(), &args.overrides.profile())?;
+    args.filter.apply(&mut config)?;
+    Ok(config)
+}
+
+// 按账户的 schedule（默认为全局 schedule）和 schedule_timezone 分组，跳过停用的账户；没有账户时按全局 schedule 运行
+fn plan(config: &Config) -> Result<Vec<Slot>, Box<dyn std::error::Error>> {
+    let mut slots: Vec<Slot> = Vec::new();
+    for instance in config.provider_instances() {
+        for account in instance.accounts()? {
+            let account = account?;
+            if account.disabled {
+                continue;
+            }
+            let expression = account.schedule.as_deref().or(config.schedule.as_deref()).ok_or_else(|| {
+                format!("--daemon requires schedule to be set in the config or on account {}", account.email)
+            })?;
+            add(&mut slots, expression, account.schedule_tz()?, Some(instance.key(&account.email)))?;
+        }
+    }
+    if slots.is_empty() {
+        let expression = config.schedule.as_deref().ok_or("--daemon requires schedule to be set in the config")?;
+        add(&mut slots, expression, None, None)?;
+    }
+    Ok(slots)
+}
+
+fn add(slots: &mut Vec<Slot>, expression: &str, timezone: Option<Tz>, account: Option<String>) -> Result<(), Box<dyn std::error::Error>> {
+    let key = (expression.to_string(), timezone);
+    let index = match slots.iter().position(|slot| slot.is(&key)) {
+        Some(index) => index,
+        None => {
+            let schedule = Schedule::parse(expression).map_err(|e| format!("invalid schedule '{}': {}", expression, e))?;
+            slots.push(Slot { expression: key.0, timezone, schedule, accounts: BTreeSet::new() });
+            slots.len() - 1
+        }
+    };
+    slots[index].accounts.extend(account);
+    Ok(())
+}
+
+fn describe(slots: &[Slot]) -> String {
+    match slots {
+        [slot] => slot.describe(),
+        _ => slots.iter()
+            .map(|slot| format!("{}（{} 个账户）", slot.describe(), slot.accounts.len()))
+            .collect::<Vec<_>>()
+            .join("; "),
+    }
+}
+
+// 到达 next 返回 true，收到退出信号返回 false
+async fn sleep_until(next: DateTime<Local>, shutdown: &CancellationToken) -> bool {
+    loop {
+        let Ok(remaining) = (next - Local::now()).to_std() else {
+            return true;
+        };
+        if remaining.is_zero() {
+            return true;
+        }
+        tokio::select! {
+            _ = tokio::time::sleep(remaining.min(MAX_SLEEP)) => {}
+            _ = shutdown.cancelled() => return false,
+        }
+    }
+}
+
+fn log(logger: &dyn Logger, message: &str) {
+    let log_content = format!("[{}] {}", Local::now().format("%Y-%m-%d %H:%M:%S"), message);
+    console::progress(&log_content);
+    if let Err(e) = logger.log(&log_content) {
+        eprintln!("记录日志失败: {}", e);
+    }
+}
@@ -0,0 +1,110 @@
+use std::{
+    io::Write,
+    path::{Path, PathBuf},
+};
+use tokio::time::{sleep, Duration, Instant};
+
+use crate::logger;
+
+const POLL_INTERVAL: Duration = Duration::from_millis(200);
+
+// 同一时间只允许一个 serve 进程持有锁文件（内容为 pid）；进程退出时删除
+pub struct DaemonLock {
+    file_path: PathBuf,
+}
+
+impl DaemonLock {
+    // takeover 时向旧进程发送 SIGTERM，旧进程完成进行中的运行后释放锁，新进程再接手
+    pub async fn acquire(file_path: impl Into<PathBuf>, takeover: bool, timeout: Duration) -> Result<Self, Box<dyn std::error::Error>> {
+        let file_path = file_path.into();
+        logger::ensure_parent_dir(&file_path)?;
+        let started = Instant::now();
+        let mut signaled = None;
+        loop {
+            match std::fs::OpenOptions::new().write(true).create_new(true).open(&file_path) {
+                Ok(mut file) => {
+                    write!(file, "{}", std::process::id())?;
+                    return Ok(Self { file_path });
+                }
+                Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => {}
+                Err(e) => return Err(e.into()),
+            }
+
+            let Some(pid) = read_pid(&file_path) else {
+                // 对方刚创建文件还没写入 pid
+                sleep(POLL_INTERVAL).await;
+                continue;
+            };
+            if !process::is_alive(pid) {
+                println!("[{}] 清理残留的锁文件（进程 {} 已退出）",
+                    chrono::Local::now().format("%Y-%m-%d %H:%M:%S"), pid);
+                remove_if_owned(&file_path, pid);
+                continue;
+            }
+            if !takeover {
+                return Err(format!("another daemon (pid {}) holds {}, use --takeover to replace it", pid, file_path.display()).into());
+            }
+            if signaled != Some(pid) {
+                process::terminate(pid)?;
+                println!("[{}] 已通知旧进程 {} 完成进行中的运行后退出，等待交接",
+                    chrono::Local::now().format("%Y-%m-%d %H:%M:%S"), pid);
+                signaled = Some(pid);
+            }
+            if started.elapsed() > timeout {
+                return Err(format!("daemon pid {} did not release {} within {}s", pid, file_path.display(), timeout.as_secs()).into());
+            }
+            sleep(POLL_INTERVAL).await;
+        }
+    }
+}
+
+impl Drop for DaemonLock {
+    fn drop(&mut self) {
+        remove_if_owned(&self.file_path, std::process::id());
+    }
+}
+
+fn read_pid(path: &Path) -> Option<u32> {
+    std::fs::read_to_string(path).ok()?.trim().parse().ok()
+}
+
+fn remove_if_owned(path: &Path, pid: u32) {
+    if read_pid(path) == Some(pid) {
+        if let Err(e) = std::fs::remove_file(path) {
+            eprintln!("删除锁文件失败: {}", e);
+        }
+    }
+}
+
+#[cfg(unix)]
+mod process {
+    const SIGTERM: i32 = 15;
+    const EPERM: i32 = 1;
+
+    extern "C" {
+        fn kill(pid: i32, sig: i32) -> i32;
+    }
+
+    pub fn is_alive(pid: u32) -> bool {
+        let result = unsafe { kill(pid as i32, 0) };
+        result == 0 || std::io::Error::last_os_error().raw_os_error() == Some(EPERM)
+    }
+
+    pub fn terminate(pid: u32) -> std::io::Result<()> {
+        if unsafe { kill(pid as i32, SIGTERM) } != 0 {
+            return Err(std::io::Error::last_os_error());
+        }
+        Ok(())
+    }
+}
+
+#[cfg(not(unix))]
+mod process {
+    pub fn is_alive(_pid: u32) -> bool {
+        true
+    }
+
+    pub fn terminate(_pid: u32) -> std::io::Result<()> {
+        Err(std::io::Error::new(std::io::ErrorKind::Unsupported, "--takeover is only supported on unix"))
+    }
+}
@@ -0,0 +1,156 @@
+use chrono::NaiveDate;
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::{
+    cli::DiffArgs,
+    clock,
+    config::Config,
+    history::{HistoryEntry, HistoryStore},
+    output::{self, TableRow},
+    results::Status,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Change {
+    NewlyFailing,
+    BalanceDrop,
+    Recovered,
+    New,
+    Missing,
+}
+
+impl Change {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Change::NewlyFailing => "newly_failing",
+            Change::BalanceDrop => "balance_drop",
+            Change::Recovered => "recovered",
+            Change::New => "new",
+            Change::Missing => "missing",
+        }
+    }
+
+    pub fn is_regression(&self) -> bool {
+        matches!(self, Change::NewlyFailing | Change::BalanceDrop)
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct DiffRow {
+    pub account: String,
+    pub change: Change,
+    pub previous_status: Option<Status>,
+    pub current_status: Option<Status>,
+    pub previous_balance: Option<String>,
+    pub current_balance: Option<String>,
+}
+
+impl TableRow for DiffRow {
+    const HEADERS: &'static [&'static str] = &["account", "change", "previous", "current", "previous_balance", "current_balance"];
+
+    fn cells(&self) -> Vec<String> {
+        vec![
+            self.account.clone(),
+            self.change.as_str().to_string(),
+            output::cell(self.previous_status.as_ref().map(Status::as_str)),
+            output::cell(self.current_status.as_ref().map(Status::as_str)),
+            output::cell(self.previous_balance.as_deref()),
+            output::cell(self.current_balance.as_deref()),
+        ]
+    }
+}
+
+pub fn run(config: &Config, args: &DiffArgs) -> Result<(), Box<dyn std::error::Error>> {
+    let history = HistoryStore::new(&config.history_file);
+    let today = clock::provider_today(config.reset_timezone()?);
+    let against = match args.against.as_str() {
+        "yesterday" => today.pred_opt(),
+        "previous" => previous_date(&history, today)?,
+        date => Some(NaiveDate::parse_from_str(date, "%Y-%m-%d")
+            .map_err(|e| format!("invalid --against '{}': {}", date, e))?),
+    };
+    let Some(against) = against else {
+        return Err("no previous run to compare against".into());
+    };
+    let mut rows = compare(&history, against, today)?;
+    if args.regressions {
+        rows.retain(|row| row.change.is_regression());
+    }
+    output::print(&rows, args.output.format)
+}
+
+pub fn previous_date(history: &HistoryStore, today: NaiveDate) -> Result<Option<NaiveDate>, Box<dyn std::error::Error>> {
+    let today = today.format("%Y-%m-%d").to_string();
+    let mut previous: Option<String> = None;
+    history.for_each(|entry| {
+        if entry.date < today && previous.as_ref().is_none_or(|date| entry.date > *date) {
+            previous = Some(entry.date);
+        }
+    })?;
+    Ok(previous.and_then(|date| NaiveDate::parse_from_str(&date, "%Y-%m-%d").ok()))
+}
+
+// 比较两天各账户最后一条记录，只返回有变化的账户
+pub fn compare(history: &HistoryStore, previous: NaiveDate, current: NaiveDate) -> Result<Vec<DiffRow>, Box<dyn std::error::Error>> {
+    let previous_date = previous.format("%Y-%m-%d").to_string();
+    let current_date = current.format("%Y-%m-%d").to_string();
+    let mut before: BTreeMap<String, HistoryEntry> = BTreeMap::new();
+    let mut after: BTreeMap<String, HistoryEntry> = BTreeMap::new();
+    history.for_each(|entry| {
+        if entry.date == previous_date {
+            before.insert(entry.key(), entry);
+        } else if entry.date == current_date {
+            after.insert(entry.key(), entry);
+        }
+    })?;
+
+    let accounts: BTreeSet<&String> = before.keys().chain(after.keys()).collect();
+    let mut rows = Vec::new();
+    for account in accounts {
+        let (previous, current) = (before.get(account), after.get(account));
+        let change = match (previous, current) {
+            (None, Some(_)) => Change::New,
+            (Some(_), None) => Change::Missing,
+            (Some(previous), Some(current)) => match (previous.status, current.status) {
+                (Status::Success, Status::Failed) => Change::NewlyFailing,
+                (Status::Failed, Status::Success) => Change::Recovered,
+                _ if balance(previous).zip(balance(current)).is_some_and(|(before, after)| after < before) => Change::BalanceDrop,
+                _ => continue,
+            },
+            (None, None) => continue,
+        };
+        rows.push(DiffRow {
+            account: account.clone(),
+            change,
+            previous_status: previous.map(|entry| entry.status),
+            current_status: current.map(|entry| entry.status),
+            previous_balance: previous.and_then(|entry| entry.balance.clone()),
+            current_balance: current.and_then(|entry| entry.balance.clone()),
+        });
+    }
+    Ok(rows)
+}
+
+// 用作失败提醒的正文；没有退化时返回 None
+pub fn summary(rows: &[DiffRow]) -> Option<String> {
+    let lines: Vec<String> = rows.iter()
+        .filter_map(|row| match row.change {
+            Change::NewlyFailing => Some(format!("账户 {} 新增失败（上次成功）", row.account)),
+            Change::BalanceDrop => Some(format!("账户 {} 余额下降 {} -> {}",
+                row.account,
+                output::cell(row.previous_balance.as_deref()),
+                output::cell(row.current_balance.as_deref()))),
+            _ => None,
+        })
+        .collect();
+    if lines.is_empty() {
+        return None;
+    }
+    Some(format!("与上次运行相比有 {} 项退化:\n{}", lines.len(), lines.join("\n")))
+}
+
+fn balance(entry: &HistoryEntry) -> Option<i64> {
+    entry.balance.as_deref()?.parse().ok()
+}
@@ -0,0 +1,258 @@
+use serde::Serialize;
+use std::{path::Path, time::Instant};
+use tokio::{net::TcpStream, time::Duration};
+use tokio_util::sync::CancellationToken;
+
+use crate::{
+    cli::{Cli, DoctorArgs},
+    config::{Config, ProviderConfig, ProviderKind, StateBackend},
+    network,
+    notifier::{self, Endpoint},
+    output::{self, TableRow},
+    perms, runner,
+    service::DEFAULT_BASE_URL,
+    state_store,
+};
+
+const PROBE_TIMEOUT: Duration = Duration::from_secs(10);
+// 日志目录所在磁盘的可用空间低于该值时判为失败
+const MIN_FREE_BYTES: u64 = 50 * 1024 * 1024;
+
+#[derive(Serialize)]
+struct CheckRow {
+    check: String,
+    ok: bool,
+    detail: String,
+}
+
+impl TableRow for CheckRow {
+    const HEADERS: &'static [&'static str] = &["check", "result", "detail"];
+
+    fn cells(&self) -> Vec<String> {
+        vec![
+            self.check.clone(),
+            if self.ok { "ok" } else { "failed" }.to_string(),
+            self.detail.clone(),
+        ]
+    }
+}
+
+impl CheckRow {
+    fn new(check: impl Into<String>, result: Result<String, String>) -> Self {
+        let (ok, detail) = match result {
+            Ok(detail) => (true, detail),
+            Err(detail) => (false, detail),
+        };
+        Self { check: check.into(), ok, detail }
+    }
+}
+
+// web doctor：依次检查配置、文件权限、日志文件和磁盘、代理、服务端的域名解析和连通性、各账户的 cookie、
+// 状态存储、通知渠道，不签到；--send-test 时向各通知渠道发送测试消息，否则只检查连通性。
+// 配置无法加载时只输出这一项。有检查失败时以非零状态退出
+pub async fn run(cli: &Cli, args: &DoctorArgs) -> Result<(), Box<dyn std::error::Error>> {
+    let config_path = cli.config_path()?;
+    let mut rows = Vec::new();
+    match Config::load_from_file(&config_path, cli.profile.as_deref()) {
+        Ok(config) => {
+            rows.push(CheckRow::new("config", check_config(&config)));
+            rows.push(CheckRow::new("permissions", check_permissions(&config, &config_path)));
+            rows.push(CheckRow::new("log_disk", check_log_disk(&config.log_file)));
+            rows.push(CheckRow::new("proxy", check_proxy(config.proxy.as_deref()).await));
+            for instance in config.provider_instances() {
+                if instance.kind == ProviderKind::Glados {
+                    let base_url = instance.base_url.as_deref().unwrap_or(DEFAULT_BASE_URL);
+                    rows.push(CheckRow::new(format!("dns:{}", instance.label()), check_dns(base_url, config.proxy.as_deref()).await));
+                }
+                let result = match instance.kind {
+                    ProviderKind::Demo => Ok("演示服务，不访问网络".to_string()),
+                    ProviderKind::Glados => check_http(&config, instance.base_url.as_deref().unwrap_or(DEFAULT_BASE_URL)).await,
+                };
+                rows.push(CheckRow::new(format!("provider:{}", instance.label()), result));
+                rows.extend(check_cookies(&config, &instance).await);
+            }
+            rows.push(CheckRow::new("state_store", check_state_store(&config)));
+            if args.send_test {
+                rows.extend(send_tests(&config).await);
+            } else {
+                match notifier::endpoints(&config) {
+                    Ok(endpoints) => {
+                        for channel in endpoints {
+                            let result = match &channel.endpoint {
+                                Endpoint::Http(url) => check_http(&config, url).await,
+                                Endpoint::Tcp { host, port } => check_tcp(&format!("{}:{}", host, port)).await,
+                            };
+                            let result = result.map_err(|e| if channel.required { e } else { format!("{}（非必需渠道）", e) });
+                            rows.push(CheckRow::new(format!("notifier:{}", channel.channel), result));
+                        }
+                    }
+                    Err(e) => rows.push(CheckRow::new("notifiers", Err(e.to_string()))),
+                }
+            }
+        }
+        Err(e) => rows.push(CheckRow::new("config", Err(e.to_string()))),
+    }
+
+    output::print(&rows, args.output.format)?;
+    let failed = rows.iter().filter(|row| !row.ok).count();
+    if failed > 0 {
+        return Err(format!("{} of {} checks failed", failed, rows.len()).into());
+    }
+    Ok(())
+}
+
+fn check_config(config: &Config) -> Result<String, String> {
+    let mut accounts = 0;
+    for instance in config.provider_instances() {
+        for account in instance.accounts().map_err(|e| e.to_string())? {
+            account.map_err(|e| e.to_string())?;
+            accounts += 1;
+        }
+    }
+    Ok(format!("{} 个账户，{} 个通知渠道", accounts, config.notifiers.len()))
+}
+
+fn check_permissions(config: &Config, config_path: &str) -> Result<String, String> {
+    let exposed: Vec<String> = perms::credential_files(config, config_path).into_iter()
+        .filter_map(|path| perms::exposed(&path).map(|mode| format!("{} ({:o})", path, mode)))
+        .collect();
+    if exposed.is_empty() {
+        Ok("包含凭据的文件仅所有者可读".to_string())
+    } else {
+        Err(format!("同组或其他用户可读: {}，运行 web --fix-perms 修正", exposed.join(", ")))
+    }
+}
+
+// 日志目录可写（日志文件已存在时文件本身也可追加写入），且所在磁盘剩余空间足够
+fn check_log_disk(log_file: &str) -> Result<String, String> {
+    let dir = perms::writable_dir(log_file)?;
+    if Path::new(log_file).exists() {
+        std::fs::OpenOptions::new().append(true).open(log_file).map_err(|e| format!("{} 不可写: {}", log_file, e))?;
+    }
+    match free_bytes(&dir) {
+        Some(free) if free < MIN_FREE_BYTES => Err(format!("{} 所在磁盘仅剩 {}", dir.display(), human_bytes(free))),
+        Some(free) => Ok(format!("{} 可写，剩余 {}", dir.display(), human_bytes(free))),
+        None => Ok(format!("{} 可写（无法获取剩余空间）", dir.display())),
+    }
+}
+
+// 没有 statvfs 绑定，通过 df 获取；POSIX 输出格式的第 4 列为以 KB 计的可用空间
+#[cfg(unix)]
+fn free_bytes(dir: &Path) -> Option<u64> {
+    let output = std::process::Command::new("df").arg("-Pk").arg(dir).output().ok()?;
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let available: u64 = stdout.lines().nth(1)?.split_whitespace().nth(3)?.parse().ok()?;
+    Some(available * 1024)
+}
+
+#[cfg(not(unix))]
+fn free_bytes(_dir: &Path) -> Option<u64> {
+    None
+}
+
+fn human_bytes(bytes: u64) -> String {
+    const MB: u64 = 1024 * 1024;
+    if bytes >= 1024 * MB {
+        format!("{:.1} GB", bytes as f64 / (1024 * MB) as f64)
+    } else {
+        format!("{} MB", bytes / MB)
+    }
+}
+
+// 直接连接代理端口；服务端和通知渠道的检查经过代理，代理不可用时它们也会失败
+async fn check_proxy(proxy: Option<&str>) -> Result<String, String> {
+    let Some(proxy) = proxy else {
+        return Ok("未配置".to_string());
+    };
+    let target = network::probe_target(proxy).ok_or_else(|| format!("无法解析代理地址 {}", proxy))?;
+    check_tcp(&target).await
+}
+
+// 在本机解析服务端域名；经 HTTP 代理或 socks5h 访问时由代理解析，本机解析失败不影响签到
+async fn check_dns(url: &str, proxy: Option<&str>) -> Result<String, String> {
+    let target = network::probe_target(url).ok_or_else(|| format!("无法解析地址 {}", url))?;
+    let host = target.rsplit_once(':').map_or(target.as_str(), |(host, _)| host).to_string();
+    let started = Instant::now();
+    let result = match tokio::time::timeout(PROBE_TIMEOUT, tokio::net::lookup_host(&target)).await {
+        Ok(Ok(addrs)) => {
+            let addrs: Vec<String> = addrs.map(|addr| addr.ip().to_string()).collect();
+            Ok(format!("{} → {}，{} ms", host, addrs.join(", "), started.elapsed().as_millis()))
+        }
+        Ok(Err(e)) => Err(format!("无法解析 {}: {}", host, e)),
+        Err(_) => Err(format!("解析 {} 超时（{} 秒）", host, PROBE_TIMEOUT.as_secs())),
+    };
+    match (result, proxy) {
+        (Err(e), Some(proxy)) if !proxy.starts_with("socks5://") => Ok(format!("{}，经代理访问时由代理解析", e)),
+        (result, _) => result,
+    }
+}
+
+// 每个启用的账户请求一次状态接口（只读，不签到），cookie 失效时服务端返回错误
+async fn check_cookies(config: &Config, instance: &ProviderConfig) -> Vec<CheckRow> {
+    let accounts = match instance.accounts().and_then(|accounts| accounts.collect::<Result<Vec<_>, _>>()) {
+        Ok(accounts) => accounts,
+        Err(e) => return vec![CheckRow::new(format!("cookie:{}", instance.label()), Err(e.to_string()))],
+    };
+    let accounts: Vec<_> = accounts.into_iter().filter(|account| !account.disabled).collect();
+    if accounts.is_empty() {
+        return Vec::new();
+    }
+    let service = match runner::build_service(config, instance, &CancellationToken::new()).await {
+        Ok(service) => service,
+        Err(e) => return vec![CheckRow::new(format!("cookie:{}", instance.label()), Err(e.to_string()))],
+    };
+    let mut rows = Vec::new();
+    for account in &accounts {
+        let result = match service.verify_cookie(account).await {
+            Ok(left_days) => Ok(format!("有效，剩余 {} 天", left_days.as_deref().unwrap_or("-"))),
+            Err(e) => Err(e.to_string()),
+        };
+        rows.push(CheckRow::new(format!("cookie:{}", instance.key(&account.email)), result));
+    }
+    rows
+}
+
+// HEAD 请求（经过代理），收到任何 HTTP 响应即为可达
+async fn check_http(config: &Config, url: &str) -> Result<String, String> {
+    let client = runner::http_client(config).map_err(|e| e.to_string())?;
+    let started = Instant::now();
+    let response = client.head(url).timeout(PROBE_TIMEOUT).send().await
+        .map_err(|e| format!("{} 不可达: {}", url, e.without_url()))?;
+    Ok(format!("{} HTTP {}，{} ms", url, response.status().as_u16(), started.elapsed().as_millis()))
+}
+
+async fn check_tcp(target: &str) -> Result<String, String> {
+    let started = Instant::now();
+    match tokio::time::timeout(PROBE_TIMEOUT, TcpStream::connect(target)).await {
+        Ok(Ok(_)) => Ok(format!("{} 可连接，{} ms", target, started.elapsed().as_millis())),
+        Ok(Err(e)) => Err(format!("无法连接 {}: {}", target, e)),
+        Err(_) => Err(format!("连接 {} 超时（{} 秒）", target, PROBE_TIMEOUT.as_secs())),
+    }
+}
+
+async fn send_tests(config: &Config) -> Vec<CheckRow> {
+    match notifier::send_tests(config).await {
+        Ok(results) => results.into_iter()
+            .map(|result| {
+                let detail = match result.error {
+                    None => Ok(format!("测试消息已发送，{} ms", result.latency_ms)),
+                    Some(e) if result.required => Err(format!("{}（{} ms）", e, result.latency_ms)),
+                    Some(e) => Err(format!("{}（{} ms，非必需渠道）", e, result.latency_ms)),
+                };
+                CheckRow::new(format!("notifier:{}", result.channel), detail)
+            })
+            .collect(),
+        Err(e) => vec![CheckRow::new("notifiers", Err(e.to_string()))],
+    }
+}
+
+fn check_state_store(config: &Config) -> Result<String, String> {
+    let store = state_store::open(&config.state_store).map_err(|e| e.to_string())?;
+    let states = store.all().map_err(|e| e.to_string())?;
+    // redis 地址中可能有密码，不输出
+    let location = match config.state_store.backend {
+        StateBackend::Redis => "redis",
+        _ => config.state_store.path(),
+    };
+    Ok(format!("{}，{} 个账户的状态可正常读取", location, states.len()))
+}
@@ -0,0 +1,256 @@
+// 测试替身：把通知和运行结果记录在内存中而不实际发送，用于确定性地测试完整流程
+// （签到 → 汇总 → 通知 → 输出）：
+//
+//     let notifier = MemoryNotifier::new("test");
+//     let sink = MemorySink::new("test");
+//     let report = runner::execute_with(&config, false, &shutdown, EventSender::default(),
+//         Rc::new(vec![notifier.channel(true)]), vec![sink.boxed()]).await?;
+//     assert_eq!(notifier.notifications().len(), report.count(Status::Success) + report.count(Status::Failed));
+//     assert_eq!(sink.reports()[0].accounts.len(), report.accounts.len());
+//
+// 克隆出的句柄共享同一份记录，交给运行后仍可读取。仅在测试或启用 doubles feature
+// （cargo test --features doubles）时编译
+use futures::future::LocalBoxFuture;
+use std::{cell::RefCell, rc::Rc};
+
+use crate::{
+    notifier::{Channel, Endpoint, Notification, Notifier},
+    results::RunReport,
+    sink::ResultSink,
+};
+
+#[derive(Clone, Default)]
+pub struct MemoryNotifier {
+    name: String,
+    failing: bool,
+    notifications: Rc<RefCell<Vec<Notification>>>,
+    summaries: Rc<RefCell<Vec<RunReport>>>,
+}
+
+impl MemoryNotifier {
+    pub fn new(name: impl Into<String>) -> Self {
+        Self { name: name.into(), ..Default::default() }
+    }
+
+    // 每次发送都返回错误（仍会记录），用于测试投递统计、required 渠道和 notify_queue
+    pub fn failing(mut self) -> Self {
+        self.failing = true;
+        self
+    }
+
+    pub fn channel(&self, required: bool) -> Channel {
+        Channel::from_notifier(Box::new(self.clone()), required)
+    }
+
+    pub fn notifications(&self) -> Vec<Notification> {
+        self.notifications.borrow().clone()
+    }
+
+    pub fn summaries(&self) -> Vec<RunReport> {
+        self.summaries.borrow().clone()
+    }
+
+    fn result(&self) -> Result<(), Box<dyn std::error::Error>> {
+        if self.failing {
+            return Err(format!("{} is configured to fail", self.name()).into());
+        }
+        Ok(())
+    }
+}
+
+impl Notifier for MemoryNotifier {
+    fn name(&self) -> String {
+        format!("memory:{}", self.name)
+    }
+
+    fn endpoint(&self) -> Endpoint {
+        Endpoint::Http(format!("memory://{}", self.name))
+    }
+
+    fn notify<'a>(&'a self, notification: &'a Notification) -> LocalBoxFuture<'a, Result<(), Box<dyn std::error::Error>>> {
+        self.notifications.borrow_mut().push(notification.clone());
+        Box::pin(async move { self.result() })
+    }
+
+    fn summarize<'a>(&'a self, report: &'a RunReport) -> LocalBoxFuture<'a, Result<(), Box<dyn std::error::Error>>> {
+        self.summaries.borrow_mut().push(report.clone());
+        Box::pin(async move { self.result() })
+    }
+
+    fn summarizes(&self) -> bool {
+        true
+    }
+}
+
+// 记录每次运行的报告和序列化后的内容（与其他输出收到的 JSON 相同）
+#[derive(Clone, Default)]
+pub struct MemorySink {
+    name: String,
+    reports: Rc<RefCell<Vec<RunReport>>>,
+    contents: Rc<RefCell<Vec<Vec<u8>>>>,
+}
+
+impl MemorySink {
+    pub fn new(name: impl Into<String>) -> Self {
+        Self { name: name.into(), ..Default::default() }
+    }
+
+    pub fn boxed(&self) -> Box<dyn ResultSink> {
+        Box::new(self.clone())
+    }
+
+    pub fn reports(&self) -> Vec<RunReport> {
+        self.reports.borrow().clone()
+    }
+
+    pub fn contents(&self) -> Vec<Vec<u8>> {
+        self.contents.borrow().clone()
+    }
+}
+
+impl ResultSink for MemorySink {
+    fn name(&self) -> String {
+        format!("memory:{}", self.name)
+    }
+
+    fn send<'a>(&'a self, report: &'a RunReport, content: &'a [u8]) -> LocalBoxFuture<'a, Result<(), Box<dyn std::error::Error>>> {
+        self.reports.borrow_mut().push(report.clone());
+        self.contents.borrow_mut().push(content.to_vec());
+        Box::pin(async { Ok(()) })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        config::Config,
+        events::EventSender,
+        fixtures::{self, Fixture},
+        results::Status,
+        runner,
+    };
+    use tokio_util::sync::CancellationToken;
+
+    // 临时目录中的配置：两个账户都指向只返回该响应的本地服务；extra 追加到配置末尾
+    async fn run(name: &str, fixture: &'static Fixture, extra: &str, notifier: &MemoryNotifier, required: bool, sink: &MemorySink) -> (Result<RunReport, Box<dyn std::error::Error>>, std::path::PathBuf) {
+        let server = fixtures::server(fixture).await.unwrap();
+        let dir = std::env::temp_dir().join(format!("web-doubles-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("config.yaml");
+        crate::perms::write_private(&path, format!("\
+provider: glados
+base_url: {}
+max_retries: 1
+retry_delay: 0
+notify_on: all
+log_file: run.log
+accounts:
+  - email: a@example.com
+    cookie: koa:sess=a
+  - email: b@example.com
+    cookie: koa:sess=b
+{}", server.base_url(), extra)).unwrap();
+        let config = Config::load_from_file(path.to_str().unwrap(), None).unwrap();
+        let report = runner::execute_with(&config, false, &CancellationToken::new(), EventSender::default(),
+            Rc::new(vec![notifier.channel(required)]), vec![sink.boxed()]).await;
+        (report, dir)
+    }
+
+    #[tokio::test]
+    async fn records_notifications_and_reports() {
+        let notifier = MemoryNotifier::new("test");
+        let sink = MemorySink::new("test");
+        let (report, dir) = run("success", &fixtures::SUCCESS, "", &notifier, true, &sink).await;
+        let report = report.unwrap();
+        assert_eq!(report.count(Status::Success), 2);
+
+        let notifications = notifier.notifications();
+        let mut accounts: Vec<&str> = notifications.iter().map(|notification| notification.email.as_str()).collect();
+        accounts.sort();
+        assert_eq!(accounts, ["a@example.com", "b@example.com"]);
+        assert!(notifications.iter().all(|notification| notification.status == Status::Success && notification.change.as_deref() == Some("1")));
+        assert_eq!(notifier.summaries().len(), 1);
+        assert_eq!(report.notifications.len(), 1);
+        assert_eq!((report.notifications[0].sent, report.notifications[0].failed), (3, 0));
+
+        assert_eq!(sink.reports().len(), 1);
+        assert_eq!(sink.reports()[0].accounts.len(), 2);
+        let published: RunReport = serde_json::from_slice(&sink.contents()[0]).unwrap();
+        assert_eq!(published.accounts.len(), 2);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test]
+    async fn failing_required_channel_fails_the_run() {
+        let notifier = MemoryNotifier::new("down").failing();
+        let sink = MemorySink::new("test");
+        let (report, dir) = run("failing", &fixtures::EXPIRED, "", &notifier, true, &sink).await;
+        let error = report.unwrap_err().to_string();
+        assert!(error.contains("memory:down"), "{}", error);
+        // 失败的通知和 cookie 失效的提醒仍尝试发送过，结果照常输出
+        let notifications = notifier.notifications();
+        assert!(notifications.iter().all(|notification| notification.status == Status::Failed));
+        assert_eq!(notifications.iter().filter(|notification| notification.text().starts_with("提醒: ")).count(), 2);
+        assert_eq!(notifications.len(), 4);
+        assert_eq!(sink.reports()[0].count(Status::Failed), 2);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test]
+    async fn failing_optional_channel_is_only_counted() {
+        let notifier = MemoryNotifier::new("down").failing();
+        let sink = MemorySink::new("test");
+        let (report, dir) = run("optional", &fixtures::SUCCESS, "", &notifier, false, &sink).await;
+        let report = report.unwrap();
+        assert_eq!(report.notifications[0].failed, 3);
+        assert_eq!(report.notifications[0].last_error.as_deref(), Some("memory:down is configured to fail"));
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test]
+    async fn cookie_reminders_are_notified() {
+        let notifier = MemoryNotifier::new("test");
+        let sink = MemorySink::new("test");
+        let updated = (chrono::Local::now() - chrono::Duration::days(40)).format("%Y-%m-%d");
+        let extra = format!("    cookie_updated_at: \"{}\"\ncookie_reminder:\n  lifetime_days: 30\n  remind_before_days: 3\n", updated);
+        let (report, dir) = run("reminder", &fixtures::SUCCESS, &extra, &notifier, true, &sink).await;
+        assert_eq!(report.unwrap().count(Status::Success), 2);
+        let reminders: Vec<Notification> = notifier.notifications().into_iter()
+            .filter(|notification| notification.text().starts_with("提醒: "))
+            .collect();
+        assert_eq!(reminders.len(), 1);
+        assert_eq!(reminders[0].email, "b@example.com");
+        assert!(reminders[0].message.contains("已使用 40 天"), "{}", reminders[0].message);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test]
+    async fn regressions_are_notified() {
+        let notifier = MemoryNotifier::new("test");
+        let sink = MemorySink::new("test");
+        // 上次运行两个账户都签到成功，本次 cookie 都已失效
+        let history = std::env::temp_dir().join(format!("web-doubles-regressions-{}.jsonl", std::process::id()));
+        let previous: String = ["a@example.com", "b@example.com"].iter()
+            .map(|email| format!("{{\"date\":\"2024-10-14\",\"time\":\"08:00:00\",\"email\":\"{}\",\"status\":\"success\",\"message\":\"ok\",\"change\":\"1\",\"balance\":\"100\"}}\n", email))
+            .collect();
+        std::fs::write(&history, previous).unwrap();
+        let extra = format!("history_file: {}\n", history.display());
+        let (report, dir) = run("regressions", &fixtures::EXPIRED, &extra, &notifier, true, &sink).await;
+        let _ = std::fs::remove_file(&history);
+        let _ = std::fs::remove_dir_all(&dir);
+
+        let report = report.unwrap();
+        let regressions = report.regressions.as_deref().unwrap();
+        assert!(regressions.contains("账户 a@example.com 新增失败"), "{}", regressions);
+        let notified: Vec<Notification> = notifier.notifications().into_iter()
+            .filter(|notification| notification.account == "regressions")
+            .collect();
+        assert_eq!(notified.len(), 1);
+        assert_eq!(notified[0].status, Status::Failed);
+        assert_eq!(notified[0].text(), regressions);
+        assert!(notifier.summaries()[0].to_text().contains(regressions));
+        assert_eq!(sink.reports()[0].regressions.as_deref(), Some(regressions));
+    }
+}
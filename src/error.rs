@@ -0,0 +1,94 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+use crate::catalog::{Classification, Outcome};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorCategory {
+    Network,
+    Timeout,
+    Parse,
+    Provider,
+    InvalidCookie,
+    VerificationRequired,
+    Cancelled,
+    Io,
+    Internal,
+}
+
+impl ErrorCategory {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ErrorCategory::Network => "network",
+            ErrorCategory::Timeout => "timeout",
+            ErrorCategory::Parse => "parse",
+            ErrorCategory::Provider => "provider",
+            ErrorCategory::InvalidCookie => "invalid_cookie",
+            ErrorCategory::VerificationRequired => "verification_required",
+            ErrorCategory::Cancelled => "cancelled",
+            ErrorCategory::Io => "io",
+            ErrorCategory::Internal => "internal",
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct CheckinError {
+    pub category: ErrorCategory,
+    pub message: String,
+    pub classification: Option<Classification>,
+}
+
+impl CheckinError {
+    pub fn new(category: ErrorCategory, message: impl Into<String>) -> Self {
+        Self {
+            category,
+            message: message.into(),
+            classification: None,
+        }
+    }
+
+    pub fn classified(classification: Classification, message: impl Into<String>) -> Self {
+        let category = match classification.outcome {
+            Outcome::AccountExpired => ErrorCategory::InvalidCookie,
+            Outcome::VerificationRequired => ErrorCategory::VerificationRequired,
+            _ => ErrorCategory::Provider,
+        };
+        Self {
+            category,
+            message: message.into(),
+            classification: Some(classification),
+        }
+    }
+
+    pub fn retryable(&self) -> bool {
+        self.category != ErrorCategory::Cancelled
+            && self.classification.is_none_or(|classification| classification.policy.retry)
+    }
+}
+
+impl fmt::Display for CheckinError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for CheckinError {}
+
+impl From<reqwest::Error> for CheckinError {
+    fn from(e: reqwest::Error) -> Self {
+        let category = if e.is_timeout() {
+            ErrorCategory::Timeout
+        } else {
+            ErrorCategory::Network
+        };
+        Self::new(category, e.to_string())
+    }
+}
+
+impl From<std::io::Error> for CheckinError {
+    fn from(e: std::io::Error) -> Self {
+        Self::new(ErrorCategory::Io, e.to_string())
+    }
+}
@@ -0,0 +1,67 @@
+use futures::{channel::mpsc, Stream, StreamExt};
+use serde::Serialize;
+use tokio::io::AsyncWriteExt;
+
+use crate::{
+    results::{AccountReport, RunReport},
+    service::{self, Attempt},
+};
+
+// 运行过程中的事件，外部程序可据此驱动自己的界面或通知，不必解析日志或等待最终报告
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum Event {
+    RunStarted { started_at: String },
+    // 每次签到请求结束（成功或失败）后发送；重试的每一次都单独发送
+    Attempt { email: String, attempt: Attempt },
+    // 账户的最终结果，包括跳过的账户
+    Result { report: AccountReport },
+    RunFinished { report: RunReport },
+}
+
+// 未订阅时发送为空操作；接收端已关闭时丢弃事件，不影响签到
+#[derive(Clone, Default)]
+pub struct EventSender(Option<mpsc::UnboundedSender<Event>>);
+
+impl EventSender {
+    pub fn send(&self, event: Event) {
+        if let Some(sender) = &self.0 {
+            let _ = sender.unbounded_send(event);
+        }
+    }
+}
+
+// 所有 EventSender 释放（运行结束）后流随之结束
+pub fn channel() -> (EventSender, impl Stream<Item = Event>) {
+    let (sender, receiver) = mpsc::unbounded();
+    (EventSender(Some(sender)), receiver)
+}
+
+// web --verbose：每次尝试输出一行到标准错误，便于观察重试过程；其他事件已有日志或结果表格
+pub fn print_attempt(event: &Event) {
+    let Event::Attempt { email, attempt } = event else {
+        return;
+    };
+    let (outcome, detail) = match (&attempt.error_category, &attempt.error) {
+        (Some(category), Some(error)) => ("失败", format!("（{}）: {}", category.as_str(), error)),
+        _ => ("成功", String::new()),
+    };
+    eprintln!("[{}] 账户 {} 第 {} 次尝试{}，耗时 {} ms{}{}", attempt.at, email, attempt.attempt, outcome, attempt.latency_ms, detail,
+        service::describe_headers(&attempt.headers));
+}
+
+// web --events：每个事件一行 JSON，path 为 - 时写到标准输出
+pub async fn write_json_lines(mut events: impl Stream<Item = Event> + Unpin, path: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let mut output: Box<dyn tokio::io::AsyncWrite + Unpin> = if path == "-" {
+        Box::new(tokio::io::stdout())
+    } else {
+        Box::new(tokio::fs::OpenOptions::new().create(true).append(true).open(path).await?)
+    };
+    while let Some(event) = events.next().await {
+        let mut line = serde_json::to_vec(&event)?;
+        line.push(b'\n');
+        output.write_all(&line).await?;
+        output.flush().await?;
+    }
+    Ok(())
+}
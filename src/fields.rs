@@ -0,0 +1,79 @@
+use std::cell::RefCell;
+
+use serde_json::Value;
+
+// 读取服务端响应中的字段，路径以 . 分隔，数字表示数组下标（如 list.0.change）。缺少必需字段或类型不符时
+// 记录一条问题并返回 None，由调用方决定缺省值；问题汇总到结果中，提示服务端可能修改了响应格式，而不是静默按缺省值处理
+pub struct Fields<'a> {
+    root: &'a Value,
+    issues: RefCell<Vec<String>>,
+}
+
+impl<'a> Fields<'a> {
+    pub fn new(root: &'a Value) -> Self {
+        Self { root, issues: RefCell::new(Vec::new()) }
+    }
+
+    pub fn i64(&self, path: &str) -> Option<i64> {
+        self.read(path, true, "integer", Value::as_i64)
+    }
+
+    pub fn str(&self, path: &str) -> Option<&'a str> {
+        self.read(path, true, "string", Value::as_str)
+    }
+
+    pub fn array(&self, path: &str) -> Option<&'a Vec<Value>> {
+        self.read(path, true, "array", Value::as_array)
+    }
+
+    // 可以不存在的字段：只在存在但类型不符时记录
+    pub fn optional<T>(&self, path: &str, expected: &str, convert: impl Fn(&'a Value) -> Option<T>) -> Option<T> {
+        self.read(path, false, expected, convert)
+    }
+
+    pub fn optional_str(&self, path: &str) -> Option<&'a str> {
+        self.optional(path, "string", Value::as_str)
+    }
+
+    pub fn optional_array(&self, path: &str) -> Option<&'a Vec<Value>> {
+        self.optional(path, "array", Value::as_array)
+    }
+
+    pub fn into_issues(self) -> Vec<String> {
+        self.issues.into_inner()
+    }
+
+    fn read<T>(&self, path: &str, required: bool, expected: &str, convert: impl Fn(&'a Value) -> Option<T>) -> Option<T> {
+        let value = path.split('.').try_fold(self.root, |value, segment| match segment.parse::<usize>() {
+            Ok(index) => value.get(index),
+            Err(_) => value.get(segment),
+        });
+        match value {
+            None | Some(Value::Null) => {
+                if required {
+                    self.issues.borrow_mut().push(format!("缺少 {}", path));
+                }
+                None
+            }
+            Some(value) => {
+                let converted = convert(value);
+                if converted.is_none() {
+                    self.issues.borrow_mut().push(format!("{} 为 {}（应为 {}）", path, kind(value), expected));
+                }
+                converted
+            }
+        }
+    }
+}
+
+fn kind(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(number) if number.is_i64() || number.is_u64() => "integer",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
@@ -0,0 +1,74 @@
+use rand::{seq::SliceRandom, Rng};
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+
+struct Browser {
+    user_agent: &'static str,
+    client_hints: Option<(&'static str, &'static str)>,
+}
+
+const BROWSERS: &[Browser] = &[
+    Browser {
+        user_agent: "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/129.0.0.0 Safari/537.36",
+        client_hints: Some((r#""Google Chrome";v="129", "Not=A?Brand";v="8", "Chromium";v="129""#, r#""Windows""#)),
+    },
+    Browser {
+        user_agent: "Mozilla/5.0 (Macintosh; Intel Mac OS X 10_15_7) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/128.0.0.0 Safari/537.36",
+        client_hints: Some((r#""Chromium";v="128", "Not;A=Brand";v="24", "Google Chrome";v="128""#, r#""macOS""#)),
+    },
+    Browser {
+        user_agent: "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/129.0.0.0 Safari/537.36 Edg/129.0.0.0",
+        client_hints: Some((r#""Microsoft Edge";v="129", "Not=A?Brand";v="8", "Chromium";v="129""#, r#""Windows""#)),
+    },
+    Browser {
+        user_agent: "Mozilla/5.0 (Windows NT 10.0; Win64; x64; rv:131.0) Gecko/20100101 Firefox/131.0",
+        client_hints: None,
+    },
+    Browser {
+        user_agent: "Mozilla/5.0 (Macintosh; Intel Mac OS X 10_15_7) AppleWebKit/605.1.15 (KHTML, like Gecko) Version/17.6 Safari/605.1.15",
+        client_hints: None,
+    },
+];
+
+const ACCEPT_LANGUAGES: &[&str] = &[
+    "zh-CN,zh;q=0.9",
+    "zh-CN,zh;q=0.9,en;q=0.8",
+    "zh-CN,zh-TW;q=0.9,zh;q=0.8,en-US;q=0.7,en;q=0.6",
+    "zh,en-US;q=0.9,en;q=0.8",
+    "en-US,en;q=0.9,zh-CN;q=0.8",
+];
+
+// 只改变 HTTP 层可见的请求头（顺序、语言、UA 及 client hints），TLS 指纹由 rustls 决定，无法在此调整
+pub fn randomize(base: HeaderMap, origin: &str) -> HeaderMap {
+    let mut rng = rand::thread_rng();
+    let browser = BROWSERS.choose(&mut rng).expect("browser pool is not empty");
+
+    let mut headers: Vec<(HeaderName, HeaderValue)> = base
+        .into_iter()
+        .filter_map(|(name, value)| name.map(|name| (name, value)))
+        .collect();
+    let mut push = |name: &'static str, value: &str| {
+        if let Ok(value) = HeaderValue::from_str(value) {
+            headers.push((HeaderName::from_static(name), value));
+        }
+    };
+    push("user-agent", browser.user_agent);
+    push("accept", "application/json, text/plain, */*");
+    push("accept-language", ACCEPT_LANGUAGES.choose(&mut rng).expect("language pool is not empty"));
+    push("origin", origin);
+    push("referer", &format!("{}/console/checkin", origin));
+    if let Some((brands, platform)) = browser.client_hints {
+        push("sec-ch-ua", brands);
+        push("sec-ch-ua-mobile", "?0");
+        push("sec-ch-ua-platform", platform);
+    }
+    if rng.gen_bool(0.5) {
+        push("dnt", "1");
+    }
+    headers.shuffle(&mut rng);
+
+    let mut randomized = HeaderMap::with_capacity(headers.len());
+    for (name, value) in headers {
+        randomized.insert(name, value);
+    }
+    randomized
+}
@@ -0,0 +1,178 @@
+// 从真实服务端记录下来的响应（id、邮箱和数值已替换），用于在修改解析逻辑时对照测试：
+//
+//     let result = fixtures::checkin(&fixtures::SUCCESS).await;
+//     let classification = ErrorCatalog::default().classify(fixtures::EXPIRED.status, &fixtures::EXPIRED.json());
+//
+// 仅在测试或启用 fixtures feature（cargo test --features fixtures）时编译
+use crate::{
+    config::Account,
+    logger::Logger,
+    mock::{MockMode, MockServer},
+    secret::SecretString,
+    service::{CheckinFailure, CheckinResult, CheckinService},
+};
+
+#[derive(Debug)]
+pub struct Fixture {
+    pub name: &'static str,
+    pub status: u16,
+    pub body: &'static str,
+    pub json: bool,
+}
+
+impl Fixture {
+    pub fn json(&self) -> serde_json::Value {
+        serde_json::from_str(self.body).unwrap_or(serde_json::Value::Null)
+    }
+}
+
+pub const SUCCESS: Fixture = Fixture {
+    name: "success",
+    status: 200,
+    body: r#"{"code":1,"message":"Checkin! Got 1 Points","points":1,"list":[{"id":1000001,"user_id":100001,"time":1728950400000,"asset":"cash","business":"system:checkin:20241015","change":"1.0000000000000000","balance":"101.0000000000000000","detail":""},{"id":1000000,"user_id":100001,"time":1728864000000,"asset":"cash","business":"system:checkin:20241014","change":"1.0000000000000000","balance":"100.0000000000000000","detail":""}]}"#,
+    json: true,
+};
+
+// 部分套餐签到成功时不返回积分流水
+pub const SUCCESS_EMPTY_LIST: Fixture = Fixture {
+    name: "success_empty_list",
+    status: 200,
+    body: r#"{"code":1,"message":"Checkin! Got 1 Points","points":1,"list":[]}"#,
+    json: true,
+};
+
+pub const ALREADY_CHECKED_IN: Fixture = Fixture {
+    name: "already_checked_in",
+    status: 200,
+    body: r#"{"code":1,"message":"Please Try Tomorrow","list":[{"id":1000001,"user_id":100001,"time":1728950400000,"asset":"cash","business":"system:checkin:20241015","change":"1.0000000000000000","balance":"101.0000000000000000","detail":""}]}"#,
+    json: true,
+};
+
+pub const EXPIRED: Fixture = Fixture {
+    name: "expired",
+    status: 200,
+    body: r#"{"code":-2,"message":"没有权限"}"#,
+    json: true,
+};
+
+pub const MAINTENANCE: Fixture = Fixture {
+    name: "maintenance",
+    status: 200,
+    body: r#"{"code":-1,"message":"系统维护中，请稍后再试"}"#,
+    json: true,
+};
+
+pub const MAINTENANCE_PAGE: Fixture = Fixture {
+    name: "maintenance_page",
+    status: 503,
+    body: "<html><head><title>503 Service Temporarily Unavailable</title></head><body><center><h1>503 Service Temporarily Unavailable</h1></center><hr><center>nginx</center></body></html>",
+    json: false,
+};
+
+pub const RATE_LIMITED: Fixture = Fixture {
+    name: "rate_limited",
+    status: 429,
+    body: r#"{"code":-1,"message":"Too many requests, please slow down"}"#,
+    json: true,
+};
+
+pub const ALL: &[&Fixture] = &[&SUCCESS, &SUCCESS_EMPTY_LIST, &ALREADY_CHECKED_IN, &EXPIRED, &MAINTENANCE, &MAINTENANCE_PAGE, &RATE_LIMITED];
+
+pub fn find(name: &str) -> Option<&'static Fixture> {
+    ALL.iter().copied().find(|fixture| fixture.name == name)
+}
+
+// 启动只返回该响应的本地服务
+pub async fn server(fixture: &'static Fixture) -> std::io::Result<MockServer> {
+    MockServer::start(MockMode::Fixture(fixture)).await
+}
+
+// 用与实际运行相同的 CheckinService 解析该响应（不重试，不写日志）
+pub async fn checkin(fixture: &'static Fixture) -> Result<CheckinResult, CheckinFailure> {
+    let server = server(fixture).await.expect("failed to start fixture server");
+    let service = CheckinService::new(reqwest::Client::new(), Box::new(Discard), 1, 0)
+        .with_base_url(server.base_url());
+    let account = Account {
+        email: "fixture@example.com".to_string(),
+        cookie: SecretString::new("koa:sess=fixture"),
+        ..Default::default()
+    };
+    service.checkin(&account, 0, |_| {}).await
+}
+
+struct Discard;
+
+impl Logger for Discard {
+    fn log(&self, _content: &str) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{catalog::{ErrorCatalog, Outcome}, error::ErrorCategory};
+
+    fn outcome(fixture: &Fixture) -> Option<Outcome> {
+        ErrorCatalog::default().classify(fixture.status, &fixture.json()).map(|classification| classification.outcome)
+    }
+
+    #[test]
+    fn fixtures_are_named_uniquely() {
+        for fixture in ALL {
+            assert_eq!(find(fixture.name).map(|found| found.body), Some(fixture.body));
+            assert_eq!(fixture.json, !fixture.json().is_null(), "{}", fixture.name);
+        }
+        assert!(find("unknown").is_none());
+    }
+
+    #[test]
+    fn catalog_classifies_fixtures() {
+        assert_eq!(outcome(&SUCCESS), None);
+        assert_eq!(outcome(&SUCCESS_EMPTY_LIST), None);
+        assert_eq!(outcome(&ALREADY_CHECKED_IN), Some(Outcome::AlreadyCheckedIn));
+        assert_eq!(outcome(&EXPIRED), Some(Outcome::AccountExpired));
+        assert_eq!(outcome(&MAINTENANCE), Some(Outcome::Maintenance));
+        assert_eq!(outcome(&MAINTENANCE_PAGE), Some(Outcome::Maintenance));
+        assert_eq!(outcome(&RATE_LIMITED), None);
+    }
+
+    #[tokio::test]
+    async fn checkin_parses_success() {
+        let result = checkin(&SUCCESS).await.unwrap();
+        assert_eq!(result.message, "Checkin! Got 1 Points");
+        assert_eq!(result.change.as_deref(), Some("1"));
+        assert_eq!(result.balance.as_deref(), Some("101"));
+        assert_eq!(result.list.len(), 2);
+        assert!(result.attempts.iter().all(|attempt| attempt.schema_issues.is_empty()));
+    }
+
+    #[tokio::test]
+    async fn checkin_accepts_empty_list() {
+        let result = checkin(&SUCCESS_EMPTY_LIST).await.unwrap();
+        assert_eq!(result.change, None);
+        assert!(result.list.is_empty());
+    }
+
+    #[tokio::test]
+    async fn checkin_reports_failures() {
+        let failure = checkin(&EXPIRED).await.unwrap_err();
+        assert_eq!(failure.error.category, ErrorCategory::InvalidCookie);
+        let failure = checkin(&MAINTENANCE).await.unwrap_err();
+        assert_eq!(failure.error.classification.map(|classification| classification.outcome), Some(Outcome::Maintenance));
+        // 非 JSON 的维护页面按解析失败处理，可以重试
+        let failure = checkin(&MAINTENANCE_PAGE).await.unwrap_err();
+        assert_eq!(failure.error.category, ErrorCategory::Parse);
+        assert!(failure.error.retryable());
+        let failure = checkin(&RATE_LIMITED).await.unwrap_err();
+        assert!(failure.error.retryable());
+    }
+
+    // 今日已签到按成功记录，积分流水为之前的签到
+    #[tokio::test]
+    async fn checkin_already_checked_in() {
+        let result = checkin(&ALREADY_CHECKED_IN).await.unwrap();
+        assert_eq!(result.message, "Please Try Tomorrow");
+        assert_eq!(result.list.len(), 1);
+    }
+}
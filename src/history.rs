@@ -0,0 +1,150 @@
+use chrono::NaiveDate;
+use chrono_tz::Tz;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{HashMap, HashSet},
+    io::{BufRead, BufReader, Write},
+    path::PathBuf,
+    time::Duration,
+};
+
+use crate::{
+    clock,
+    config::account_key,
+    error::ErrorCategory,
+    logger,
+    results::Status,
+    service::{self, Attempt, CheckinFailure, CheckinResult},
+};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HistoryEntry {
+    pub date: String,
+    pub time: String,
+    pub email: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub provider: Option<String>,
+    #[serde(default = "default_status")]
+    pub status: Status,
+    pub message: String,
+    pub change: Option<String>,
+    pub balance: Option<String>,
+    #[serde(default)]
+    pub list: Vec<serde_json::Value>,
+    #[serde(default)]
+    pub error_category: Option<ErrorCategory>,
+    #[serde(default)]
+    pub attempts: Vec<Attempt>,
+    #[serde(default)]
+    pub duration_ms: u64,
+    #[serde(default)]
+    pub backoff_ms: u64,
+}
+
+impl HistoryEntry {
+    pub fn key(&self) -> String {
+        account_key(self.provider.as_deref(), &self.email)
+    }
+}
+
+fn default_status() -> Status {
+    Status::Success
+}
+
+impl HistoryEntry {
+    pub fn from_result(provider: Option<&str>, email: &str, result: &CheckinResult, duration: Duration, tz: Tz) -> Self {
+        let now = chrono::Local::now();
+        Self {
+            date: clock::provider_today(tz).format("%Y-%m-%d").to_string(),
+            time: now.format("%Y-%m-%d %H:%M:%S").to_string(),
+            email: email.to_string(),
+            provider: provider.map(str::to_string),
+            status: Status::Success,
+            message: result.message.clone(),
+            change: result.change.clone(),
+            balance: result.balance.clone(),
+            list: result.list.clone(),
+            error_category: None,
+            attempts: result.attempts.clone(),
+            duration_ms: duration.as_millis() as u64,
+            backoff_ms: service::backoff_ms(&result.attempts),
+        }
+    }
+
+    pub fn from_failure(provider: Option<&str>, email: &str, failure: &CheckinFailure, duration: Duration, tz: Tz) -> Self {
+        let now = chrono::Local::now();
+        Self {
+            date: clock::provider_today(tz).format("%Y-%m-%d").to_string(),
+            time: now.format("%Y-%m-%d %H:%M:%S").to_string(),
+            email: email.to_string(),
+            provider: provider.map(str::to_string),
+            status: Status::Failed,
+            message: failure.error.message.clone(),
+            change: None,
+            balance: None,
+            list: Vec::new(),
+            error_category: Some(failure.error.category),
+            attempts: failure.attempts.clone(),
+            duration_ms: duration.as_millis() as u64,
+            backoff_ms: service::backoff_ms(&failure.attempts),
+        }
+    }
+}
+
+pub struct HistoryStore {
+    file_path: PathBuf,
+}
+
+impl HistoryStore {
+    pub fn new(file_path: impl Into<PathBuf>) -> Self {
+        Self {
+            file_path: file_path.into(),
+        }
+    }
+
+    pub fn append(&self, entry: &HistoryEntry) -> Result<(), Box<dyn std::error::Error>> {
+        let line = serde_json::to_string(entry)?;
+        logger::ensure_parent_dir(&self.file_path)?;
+        let mut file = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.file_path)?;
+        writeln!(file, "{}", line)?;
+        Ok(())
+    }
+
+    pub fn for_each(&self, mut f: impl FnMut(HistoryEntry)) -> Result<(), Box<dyn std::error::Error>> {
+        let file = match std::fs::File::open(&self.file_path) {
+            Ok(file) => file,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+            Err(e) => return Err(e.into()),
+        };
+        for line in BufReader::new(file).lines() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            f(serde_json::from_str(&line)?);
+        }
+        Ok(())
+    }
+
+    pub fn checked_in_on(&self, date: NaiveDate) -> Result<HashSet<String>, Box<dyn std::error::Error>> {
+        let date = date.format("%Y-%m-%d").to_string();
+        let mut keys = HashSet::new();
+        self.for_each(|entry| {
+            if entry.date == date && entry.status == Status::Success {
+                keys.insert(entry.key());
+            }
+        })?;
+        Ok(keys)
+    }
+
+    pub fn latest(&self) -> Result<HashMap<String, HistoryEntry>, Box<dyn std::error::Error>> {
+        let mut latest = HashMap::new();
+        self.for_each(|entry| {
+            latest.insert(entry.key(), entry);
+        })?;
+        Ok(latest)
+    }
+}
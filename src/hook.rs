@@ -0,0 +1,93 @@
+use std::process::Stdio;
+use tokio::{process::Command, time::Duration};
+
+use crate::{
+    config::{account_key, Account, Config},
+    logger::Logger,
+    results::{AccountReport, Status},
+};
+
+// 账户最终结果确定后执行的 shell 命令；账户中的 on_success/on_failure 覆盖全局配置，跳过的账户不执行
+#[derive(Default)]
+pub struct Hooks {
+    on_success: Option<String>,
+    on_failure: Option<String>,
+    timeout: Duration,
+}
+
+impl Hooks {
+    pub fn new(config: &Config) -> Self {
+        Self {
+            on_success: config.on_success.clone(),
+            on_failure: config.on_failure.clone(),
+            timeout: Duration::from_secs(config.hook_timeout),
+        }
+    }
+
+    pub async fn run(&self, account: &Account, report: &AccountReport, logger: &dyn Logger) {
+        let (name, command) = match report.status {
+            Status::Success => ("on_success", account.on_success.as_ref().or(self.on_success.as_ref())),
+            Status::Failed => ("on_failure", account.on_failure.as_ref().or(self.on_failure.as_ref())),
+            Status::Skipped => return,
+        };
+        let Some(command) = command.filter(|command| !command.trim().is_empty()) else {
+            return;
+        };
+        if let Err(e) = self.execute(command, account, report).await {
+            let error_log = format!("[{}] 账户 {} 的 {} 钩子执行失败: {}",
+                chrono::Local::now().format("%Y-%m-%d %H:%M:%S"),
+                account_key(report.provider.as_deref(), &report.email), name, e);
+            eprintln!("{}", error_log);
+            if let Err(log_err) = logger.log(&error_log) {
+                eprintln!("记录日志失败: {}", log_err);
+            }
+        }
+    }
+
+    async fn execute(&self, command: &str, account: &Account, report: &AccountReport) -> Result<(), Box<dyn std::error::Error>> {
+        let mut process = if cfg!(windows) {
+            let mut process = Command::new("cmd");
+            process.arg("/C");
+            process
+        } else {
+            let mut process = Command::new("sh");
+            process.arg("-c");
+            process
+        };
+        let child = process.arg(command)
+            .envs(environment(account, report))
+            .stdin(Stdio::null())
+            .stdout(Stdio::inherit())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true)
+            .spawn()?;
+        let output = tokio::time::timeout(self.timeout, child.wait_with_output()).await
+            .map_err(|_| format!("timed out after {} seconds", self.timeout.as_secs()))??;
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            let last_line = stderr.lines().rev().find(|line| !line.trim().is_empty()).unwrap_or("");
+            return Err(format!("{} {}", output.status, last_line.trim()).trim_end().to_string().into());
+        }
+        Ok(())
+    }
+}
+
+// cookie 不传给钩子；未知的值设为空字符串，脚本中无需判断变量是否存在
+fn environment(account: &Account, report: &AccountReport) -> Vec<(&'static str, String)> {
+    vec![
+        ("WEB_ACCOUNT", account_key(report.provider.as_deref(), &report.email)),
+        ("WEB_EMAIL", report.email.clone()),
+        ("WEB_PROVIDER", report.provider.clone().unwrap_or_default()),
+        ("WEB_STATUS", report.status.as_str().to_string()),
+        ("WEB_MESSAGE", report.message.clone().unwrap_or_default()),
+        ("WEB_CHANGE", report.change.clone().unwrap_or_default()),
+        ("WEB_BALANCE", report.balance.clone().unwrap_or_default()),
+        ("WEB_ERROR", report.error.clone().unwrap_or_default()),
+        ("WEB_ERROR_CATEGORY", report.error_category.map(|category| category.as_str().to_string()).unwrap_or_default()),
+        ("WEB_ATTEMPTS", report.attempts.len().to_string()),
+        ("WEB_DURATION_MS", report.duration_ms.to_string()),
+        ("WEB_BACKOFF_MS", report.backoff_ms.to_string()),
+        ("WEB_TOKEN", account.token.clone().unwrap_or_default()),
+        ("WEB_COOKIE_UPDATED_AT", account.cookie_updated_at.clone().unwrap_or_default()),
+    ]
+}
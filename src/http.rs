@@ -0,0 +1,155 @@
+use std::future::Future;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+const MAX_REQUEST_SIZE: usize = 1024 * 1024;
+
+pub struct Request {
+    pub method: String,
+    pub path: String,
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+impl Request {
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+pub struct Response {
+    pub status: u16,
+    pub content_type: &'static str,
+    pub body: Vec<u8>,
+}
+
+impl Response {
+    pub fn json(status: u16, body: impl Into<Vec<u8>>) -> Self {
+        Self {
+            status,
+            content_type: "application/json",
+            body: body.into(),
+        }
+    }
+
+    pub fn text(status: u16, body: impl Into<Vec<u8>>) -> Self {
+        Self {
+            status,
+            content_type: "text/plain; charset=utf-8",
+            body: body.into(),
+        }
+    }
+}
+
+pub async fn serve_connection<S, F, Fut>(mut stream: S, mut handler: F) -> std::io::Result<()>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+    F: FnMut(Request) -> Fut,
+    Fut: Future<Output = Response>,
+{
+    let mut buf = Vec::new();
+    while let Some(request) = read_request(&mut stream, &mut buf).await? {
+        let response = handler(request).await;
+        write_response(&mut stream, &response).await?;
+    }
+    Ok(())
+}
+
+async fn read_request<S: AsyncRead + Unpin>(stream: &mut S, buf: &mut Vec<u8>) -> std::io::Result<Option<Request>> {
+    let mut chunk = [0u8; 4096];
+    let header_end = loop {
+        if let Some(pos) = buf.windows(4).position(|w| w == b"\r\n\r\n") {
+            break pos + 4;
+        }
+        if buf.len() > MAX_REQUEST_SIZE {
+            return Err(std::io::Error::new(std::io::ErrorKind::InvalidData, "request headers too large"));
+        }
+        let n = stream.read(&mut chunk).await?;
+        if n == 0 {
+            return Ok(None);
+        }
+        buf.extend_from_slice(&chunk[..n]);
+    };
+
+    let head = String::from_utf8_lossy(&buf[..header_end]).into_owned();
+    let mut lines = head.split("\r\n");
+    let mut request_line = lines.next().unwrap_or_default().split_whitespace();
+    let method = request_line.next().unwrap_or_default().to_string();
+    let path = request_line.next().unwrap_or_default().to_string();
+    let headers: Vec<(String, String)> = lines
+        .filter_map(|line| line.split_once(':'))
+        .map(|(key, value)| (key.trim().to_string(), value.trim().to_string()))
+        .collect();
+    let content_length = headers
+        .iter()
+        .find(|(key, _)| key.eq_ignore_ascii_case("content-length"))
+        .and_then(|(_, value)| value.parse::<usize>().ok())
+        .unwrap_or(0);
+    if content_length > MAX_REQUEST_SIZE {
+        return Err(std::io::Error::new(std::io::ErrorKind::InvalidData, "request body too large"));
+    }
+
+    while buf.len() < header_end + content_length {
+        let n = stream.read(&mut chunk).await?;
+        if n == 0 {
+            return Ok(None);
+        }
+        buf.extend_from_slice(&chunk[..n]);
+    }
+    let body = buf[header_end..header_end + content_length].to_vec();
+    buf.drain(..header_end + content_length);
+
+    Ok(Some(Request { method, path, headers, body }))
+}
+
+async fn write_response<S: AsyncWrite + Unpin>(stream: &mut S, response: &Response) -> std::io::Result<()> {
+    let reason = reqwest::StatusCode::from_u16(response.status).ok()
+        .and_then(|status| status.canonical_reason())
+        .unwrap_or("");
+    let head = format!(
+        "HTTP/1.1 {} {}\r\nContent-Type: {}\r\nContent-Length: {}\r\n\r\n",
+        response.status, reason, response.content_type, response.body.len()
+    );
+    stream.write_all(head.as_bytes()).await?;
+    stream.write_all(&response.body).await?;
+    stream.flush().await
+}
+
+// 发送一个 GET 请求并读取完整响应，返回状态码和响应体；发送后关闭写入端，服务端回复后随即断开
+pub async fn get<S: AsyncRead + AsyncWrite + Unpin>(mut stream: S, path: &str) -> std::io::Result<(u16, Vec<u8>)> {
+    let request = format!("GET {} HTTP/1.1\r\nHost: localhost\r\n\r\n", path);
+    stream.write_all(request.as_bytes()).await?;
+    stream.shutdown().await?;
+    let mut response = Vec::new();
+    stream.read_to_end(&mut response).await?;
+    let invalid = |message: &str| std::io::Error::new(std::io::ErrorKind::InvalidData, message.to_string());
+    let header_end = response.windows(4).position(|w| w == b"\r\n\r\n")
+        .ok_or_else(|| invalid("incomplete response"))? + 4;
+    let status = String::from_utf8_lossy(&response[..header_end])
+        .split_whitespace()
+        .nth(1)
+        .and_then(|status| status.parse::<u16>().ok())
+        .ok_or_else(|| invalid("invalid status line"))?;
+    Ok((status, response[header_end..].to_vec()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn head(status: u16) -> String {
+        let mut written = Vec::new();
+        write_response(&mut written, &Response::text(status, "body")).await.unwrap();
+        String::from_utf8(written).unwrap().lines().next().unwrap_or_default().to_string()
+    }
+
+    #[tokio::test]
+    async fn status_lines_use_canonical_reasons() {
+        assert_eq!(head(200).await, "HTTP/1.1 200 OK");
+        assert_eq!(head(403).await, "HTTP/1.1 403 Forbidden");
+        assert_eq!(head(503).await, "HTTP/1.1 503 Service Unavailable");
+        assert_eq!(head(599).await, "HTTP/1.1 599 ");
+    }
+}
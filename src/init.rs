@@ -0,0 +1,236 @@
+use std::{io::IsTerminal, path::Path};
+
+use serde_json::Value;
+use tokio_util::sync::CancellationToken;
+
+use crate::{
+    cli::InitArgs,
+    config::{Account, Config},
+    lint, perms, runner,
+    secret::SecretString,
+};
+
+// 需要询问的字段：(键, 提示, 是否可留空)
+type Field = (&'static str, &'static str, bool);
+
+// 通知渠道及其字段；email 的 to 以逗号分隔多个收件人
+const CHANNELS: &[(&str, &[Field])] = &[
+    ("webhook", &[("url", "webhook 地址", false)]),
+    ("telegram", &[("bot_token", "Bot token（@BotFather 创建 bot 获得）", false), ("chat_id", "chat_id", false)]),
+    ("serverchan", &[("token", "SendKey", false)]),
+    ("pushplus", &[("token", "token", false)]),
+    ("bark", &[("token", "device key", false)]),
+    ("dingtalk", &[("url", "机器人 webhook 地址", false), ("secret", "加签 secret（未开启加签直接回车）", true)]),
+    ("wecom", &[("url", "机器人 webhook 地址", false)]),
+    ("feishu", &[("url", "机器人 webhook 地址", false), ("secret", "签名校验 secret（未开启直接回车）", true)]),
+    ("email", &[
+        ("host", "SMTP 服务器（使用 STARTTLS，端口 587）", false),
+        ("username", "SMTP 用户名", false),
+        ("password", "SMTP 密码或授权码", false),
+        ("from", "发件人", false),
+        ("to", "收件人（多个以逗号分隔）", false),
+    ]),
+];
+
+struct Answers {
+    accounts: Vec<(String, String)>,
+    max_retries: u32,
+    retry_delay: u64,
+    notifiers: Vec<(&'static str, Vec<(&'static str, Value)>)>,
+}
+
+// web init：逐项询问后生成配置文件（0600）；cookie 默认用只读的状态接口验证，写入后重新加载确认配置有效
+pub async fn run(path: &str, args: &InitArgs) -> Result<(), Box<dyn std::error::Error>> {
+    if Path::new(path).exists() && !args.force {
+        return Err(format!("{} already exists, use --force to overwrite it", path).into());
+    }
+    if !path.ends_with(".yaml") && !path.ends_with(".yml") {
+        return Err(format!("init writes YAML, use a .yaml path instead of {}", path).into());
+    }
+    eprintln!("将生成配置文件 {}，方括号中为默认值，直接回车使用默认值", path);
+
+    let mut answers = Answers { accounts: Vec::new(), max_retries: 3, retry_delay: 5, notifiers: Vec::new() };
+    loop {
+        let email = if answers.accounts.is_empty() {
+            ask("账户邮箱: ", None)?
+        } else {
+            ask("继续添加账户的邮箱（直接回车结束）: ", Some(""))?
+        };
+        if email.is_empty() {
+            if answers.accounts.is_empty() {
+                eprintln!("至少需要一个账户");
+                continue;
+            }
+            break;
+        }
+        if answers.accounts.iter().any(|(existing, _)| *existing == email) {
+            eprintln!("账户 {} 已添加", email);
+            continue;
+        }
+        if let Some(cookie) = ask_cookie(&answers, &email, args.no_verify).await? {
+            answers.accounts.push((email, cookie));
+        }
+    }
+
+    answers.max_retries = ask_number("最大重试次数", answers.max_retries, 1)?;
+    answers.retry_delay = ask_number("重试间隔（秒）", answers.retry_delay, 0)?;
+
+    let names: Vec<&str> = CHANNELS.iter().map(|(name, _)| *name).collect();
+    eprintln!("可选的通知渠道: {}", names.join(", "));
+    loop {
+        let name = ask("添加通知渠道（输入名称，直接回车结束）: ", Some(""))?;
+        if name.is_empty() {
+            break;
+        }
+        let Some((name, fields)) = CHANNELS.iter().find(|(channel, _)| *channel == name) else {
+            eprintln!("未知的通知渠道 {}，可选: {}", name, names.join(", "));
+            continue;
+        };
+        let mut values = Vec::new();
+        for (key, prompt, optional) in fields.iter() {
+            let value = ask(&format!("{} {}: ", name, prompt), optional.then_some(""))?;
+            if value.is_empty() {
+                continue;
+            }
+            let value = if *key == "to" {
+                Value::from(value.split(',').map(str::trim).filter(|to| !to.is_empty()).collect::<Vec<_>>())
+            } else {
+                Value::from(value)
+            };
+            values.push((*key, value));
+        }
+        answers.notifiers.push((name, values));
+    }
+
+    // 先写到同一目录下的临时文件并加载一次（相对路径按同一目录解析），确认有效后再替换，--force 时不会丢失原文件
+    let content = render(&answers)?;
+    let target = Path::new(path);
+    if let Some(dir) = target.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        std::fs::create_dir_all(dir).map_err(|e| format!("failed to create {}: {}", dir.display(), e))?;
+    }
+    let file_name = target.file_name().map(|name| name.to_string_lossy().into_owned()).unwrap_or_default();
+    let tmp_path = target.with_file_name(format!(".init-{}", file_name)).to_string_lossy().into_owned();
+    perms::write_private(&tmp_path, &content).map_err(|e| format!("failed to write {}: {}", tmp_path, e))?;
+    if let Err(e) = Config::load_from_file(&tmp_path, None) {
+        let _ = std::fs::remove_file(&tmp_path);
+        return Err(format!("the generated config would not load, nothing was written: {}", e).into());
+    }
+    std::fs::rename(&tmp_path, path).map_err(|e| format!("failed to write {}: {}", path, e))?;
+    println!("已生成配置文件 {}（{} 个账户，{} 个通知渠道），其他选项见 config_bak.yaml 中的说明；可先运行 web -c {} --dry-run 检查",
+        path, answers.accounts.len(), answers.notifiers.len(), path);
+    Ok(())
+}
+
+// 询问并验证 cookie；验证失败时可以重新输入，或不验证直接保存，返回 None 表示放弃该账户
+async fn ask_cookie(answers: &Answers, email: &str, no_verify: bool) -> Result<Option<String>, Box<dyn std::error::Error>> {
+    loop {
+        let cookie = ask(&format!("账户 {} 的 cookie: ", email), None)?;
+        if let Some(problem) = lint::cookie_problem(&cookie) {
+            eprintln!("cookie {}", problem);
+            continue;
+        }
+        if no_verify {
+            return Ok(Some(cookie));
+        }
+        match verify(answers, email, &cookie).await {
+            Ok(left_days) => {
+                eprintln!("cookie 验证通过，剩余天数: {}", left_days.as_deref().unwrap_or("-"));
+                return Ok(Some(cookie));
+            }
+            Err(e) => eprintln!("cookie 验证失败: {}", e),
+        }
+        if confirm("重新输入 cookie？", true)? {
+            continue;
+        }
+        return Ok(confirm("不验证，仍然保存该账户？", false)?.then_some(cookie));
+    }
+}
+
+// 用只包含该账户的临时配置查询状态接口，不签到
+async fn verify(answers: &Answers, email: &str, cookie: &str) -> Result<Option<String>, Box<dyn std::error::Error>> {
+    let probe = Answers {
+        accounts: vec![(email.to_string(), cookie.to_string())],
+        max_retries: answers.max_retries,
+        retry_delay: answers.retry_delay,
+        notifiers: Vec::new(),
+    };
+    let config: Config = serde_yaml::from_str(&render(&probe)?)?;
+    let instance = config.default_instance();
+    let service = runner::build_service(&config, &instance, &CancellationToken::new()).await?;
+    let account = Account {
+        email: email.to_string(),
+        cookie: SecretString::new(cookie.to_string()),
+        ..Account::default()
+    };
+    Ok(service.status(&account).await?.left_days)
+}
+
+// 字符串按 JSON 转义写出（也是合法的 YAML），与 web account 写入的格式一致
+fn render(answers: &Answers) -> Result<String, Box<dyn std::error::Error>> {
+    let today = chrono::Local::now().format("%Y-%m-%d").to_string();
+    let mut content = format!("# 由 web init 于 {} 生成；其他选项（代理、计划、钩子等）见 config_bak.yaml 中的说明\n", today);
+    content.push_str("# 本文件包含 cookie，请保持只有所有者可读写（chmod 600）\n");
+    content.push_str("accounts:\n");
+    for (email, cookie) in &answers.accounts {
+        content.push_str(&format!("  - email: {}\n    cookie: {}\n    cookie_updated_at: {}\n",
+            serde_json::to_string(email)?, serde_json::to_string(cookie)?, serde_json::to_string(&today)?));
+    }
+    content.push_str(&format!("\n# 重试配置\nmax_retries: {}\nretry_delay: {}\n", answers.max_retries, answers.retry_delay));
+    content.push_str("\n# 日志文件（相对于本文件所在目录）\nlog_file: \"logs/checkin.log\"\n");
+    if !answers.notifiers.is_empty() {
+        content.push_str("\n# 通知渠道\nnotifiers:\n");
+        for (name, values) in &answers.notifiers {
+            content.push_str(&format!("  - type: {}\n", name));
+            for (key, value) in values {
+                content.push_str(&format!("    {}: {}\n", key, serde_json::to_string(value)?));
+            }
+        }
+    }
+    Ok(content)
+}
+
+// 读取一行回答；default 为 None 时不接受空回答。标准输入不是终端时不打印提示，便于用管道传入回答
+fn ask(prompt: &str, default: Option<&str>) -> Result<String, Box<dyn std::error::Error>> {
+    let interactive = std::io::stdin().is_terminal();
+    loop {
+        if interactive {
+            eprint!("{}", prompt);
+        }
+        let mut line = String::new();
+        if std::io::stdin().read_line(&mut line)? == 0 {
+            return Err("unexpected end of input".into());
+        }
+        let answer = line.trim();
+        match (answer.is_empty(), default) {
+            (false, _) => return Ok(answer.to_string()),
+            (true, Some(default)) => return Ok(default.to_string()),
+            (true, None) if interactive => continue,
+            (true, None) => return Err(format!("missing answer for: {}", prompt.trim_end_matches([' ', ':'])).into()),
+        }
+    }
+}
+
+fn ask_number<T: std::str::FromStr + std::fmt::Display + PartialOrd + From<u8>>(prompt: &str, default: T, min: u8) -> Result<T, Box<dyn std::error::Error>> {
+    loop {
+        let answer = ask(&format!("{} [{}]: ", prompt, default), Some(""))?;
+        if answer.is_empty() {
+            return Ok(default);
+        }
+        match answer.parse::<T>() {
+            Ok(value) if value >= T::from(min) => return Ok(value),
+            _ => eprintln!("请输入不小于 {} 的整数", min),
+        }
+    }
+}
+
+fn confirm(prompt: &str, default: bool) -> Result<bool, Box<dyn std::error::Error>> {
+    let hint = if default { "[Y/n]" } else { "[y/N]" };
+    loop {
+        match ask(&format!("{} {} ", prompt, hint), Some(""))?.to_lowercase().as_str() {
+            "" => return Ok(default),
+            "y" | "yes" => return Ok(true),
+            "n" | "no" => return Ok(false),
+            _ => eprintln!("请输入 y 或 n"),
+        }
+    }
+}
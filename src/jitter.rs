@@ -0,0 +1,24 @@
+use chrono::NaiveDate;
+use rand::Rng;
+
+use crate::{
+    config::AccountJitterConfig,
+    state_store::{AccountState, RunOffset},
+};
+
+// 账户相对运行开始的签到延迟（秒）：首次随机生成，之后每天在前一天的基础上最多漂移 drift 秒，
+// 保存在账户状态中，进程重启后各账户仍保持大致固定且互不相同的签到时间；同一天内多次运行使用相同的值
+pub fn advance(state: &mut AccountState, config: &AccountJitterConfig, today: NaiveDate, rng: &mut impl Rng) -> u64 {
+    let date = today.format("%Y-%m-%d").to_string();
+    let max_offset = config.max_offset;
+    let secs = match &state.run_offset {
+        Some(offset) if offset.date == date => return offset.secs.min(max_offset),
+        Some(offset) => {
+            let drift = config.drift.min(max_offset) as i64;
+            (offset.secs.min(max_offset) as i64 + rng.gen_range(-drift..=drift)).clamp(0, max_offset as i64) as u64
+        }
+        None => rng.gen_range(0..=max_offset),
+    };
+    state.run_offset = Some(RunOffset { secs, date });
+    secs
+}
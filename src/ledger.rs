@@ -0,0 +1,150 @@
+use chrono::NaiveDate;
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+use crate::{
+    cli::ReconcileArgs,
+    config::Config,
+    history::{HistoryEntry, HistoryStore},
+    output::{self, TableRow},
+    results::Status,
+};
+
+// 取整后的余额/变化值之间允许的误差；有完整精度（响应中的 list）时使用更小的误差
+const ROUNDED_TOLERANCE: f64 = 1.0;
+const EXACT_TOLERANCE: f64 = 0.000001;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Finding {
+    Ok,
+    Adjustment,
+    MissedRun,
+}
+
+impl Finding {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Finding::Ok => "ok",
+            Finding::Adjustment => "adjustment",
+            Finding::MissedRun => "missed_run",
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct LedgerRow {
+    pub account: String,
+    pub time: String,
+    pub finding: Finding,
+    pub previous_balance: f64,
+    pub change: f64,
+    pub balance: f64,
+    pub discrepancy: f64,
+    pub missed_days: i64,
+}
+
+impl TableRow for LedgerRow {
+    const HEADERS: &'static [&'static str] = &["account", "time", "finding", "previous_balance", "change", "balance", "discrepancy", "missed_days"];
+
+    fn cells(&self) -> Vec<String> {
+        vec![
+            self.account.clone(),
+            self.time.clone(),
+            self.finding.as_str().to_string(),
+            self.previous_balance.to_string(),
+            self.change.to_string(),
+            self.balance.to_string(),
+            format!("{:+}", self.discrepancy),
+            self.missed_days.to_string(),
+        ]
+    }
+}
+
+struct Point {
+    date: Option<NaiveDate>,
+    time: String,
+    change: f64,
+    balance: f64,
+    exact: bool,
+}
+
+impl Point {
+    // 优先使用响应 list 中的完整精度数值，旧记录或没有 list 时退回到按整数记录的 change/balance
+    fn from_entry(entry: &HistoryEntry) -> Option<Self> {
+        if entry.status != Status::Success {
+            return None;
+        }
+        let exact = entry.list.first().and_then(|item| {
+            let change = item["change"].as_str()?.parse().ok()?;
+            let balance = item["balance"].as_str()?.parse().ok()?;
+            Some((change, balance))
+        });
+        let (change, balance, exact) = match exact {
+            Some((change, balance)) => (change, balance, true),
+            None => (
+                entry.change.as_deref().unwrap_or("0").parse().ok()?,
+                entry.balance.as_deref()?.parse().ok()?,
+                false,
+            ),
+        };
+        Some(Self {
+            date: NaiveDate::parse_from_str(&entry.date, "%Y-%m-%d").ok(),
+            time: entry.time.clone(),
+            change,
+            balance,
+            exact,
+        })
+    }
+}
+
+pub fn run(config: &Config, args: &ReconcileArgs) -> Result<(), Box<dyn std::error::Error>> {
+    let mut ledgers: BTreeMap<String, Vec<Point>> = BTreeMap::new();
+    HistoryStore::new(&config.history_file).for_each(|entry| {
+        let account = entry.key();
+        if args.account.as_ref().is_some_and(|filter| *filter != account && *filter != entry.email) {
+            return;
+        }
+        if let Some(point) = Point::from_entry(&entry) {
+            ledgers.entry(account).or_default().push(point);
+        }
+    })?;
+
+    let mut rows = Vec::new();
+    for (account, points) in &ledgers {
+        for pair in points.windows(2) {
+            let row = reconcile(account, &pair[0], &pair[1]);
+            if args.all || row.finding != Finding::Ok {
+                rows.push(row);
+            }
+        }
+    }
+    output::print(&rows, args.output.format)
+}
+
+// 同一天重复运行（今日已签到）的 change 为 0，余额应保持不变
+fn reconcile(account: &str, previous: &Point, current: &Point) -> LedgerRow {
+    let tolerance = if previous.exact && current.exact { EXACT_TOLERANCE } else { ROUNDED_TOLERANCE };
+    let discrepancy = ((current.balance - (previous.balance + current.change)) * 1e6).round() / 1e6;
+    let missed_days = match (previous.date, current.date) {
+        (Some(previous), Some(current)) => ((current - previous).num_days() - 1).max(0),
+        _ => 0,
+    };
+    let finding = if discrepancy.abs() > tolerance {
+        Finding::Adjustment
+    } else if missed_days > 0 {
+        Finding::MissedRun
+    } else {
+        Finding::Ok
+    };
+    LedgerRow {
+        account: account.to_string(),
+        time: current.time.clone(),
+        finding,
+        previous_balance: previous.balance,
+        change: current.change,
+        balance: current.balance,
+        discrepancy: if discrepancy.abs() > tolerance { discrepancy } else { 0.0 },
+        missed_days,
+    }
+}
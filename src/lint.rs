@@ -0,0 +1,119 @@
+use reqwest::header::HeaderValue;
+use std::collections::HashSet;
+
+use crate::{config::Config, perms};
+
+// 超过该次数时服务端故障期间每个账户会长时间重试，且容易触发限流
+const MAX_SANE_RETRIES: u32 = 10;
+
+// web lint-config / web validate：加载配置（语法错误、未知的键、不合法的值直接报错），再检查加载时不检查的
+// 错误（所有来源中重复的账户、格式错误的 cookie、无法写入的日志目录），最后列出可以运行但可能有问题的配置。
+// 不访问网络；有错误时以非零状态退出
+pub fn run(config: &Config, config_path: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let errors = check(config);
+    for error in &errors {
+        println!("错误: {}", error);
+    }
+    let warnings = lint(config, config_path)?;
+    for warning in &warnings {
+        println!("警告: {}", warning);
+    }
+    if !errors.is_empty() {
+        return Err(format!("config has {} error(s) and {} warning(s)", errors.len(), warnings.len()).into());
+    }
+    if warnings.is_empty() {
+        println!("配置有效，没有警告");
+    } else {
+        println!("配置有效，{} 条警告", warnings.len());
+    }
+    Ok(())
+}
+
+fn check(config: &Config) -> Vec<String> {
+    let mut errors = Vec::new();
+    // include 之间的重复在加载时已报错；这里还覆盖 accounts_file 和同一部署的 providers
+    let mut keys = HashSet::new();
+    for instance in config.provider_instances() {
+        let accounts = match instance.accounts() {
+            Ok(accounts) => accounts,
+            Err(e) => {
+                errors.push(e.to_string());
+                continue;
+            }
+        };
+        for account in accounts {
+            let account = match account {
+                Ok(account) => account,
+                Err(e) => {
+                    errors.push(e.to_string());
+                    continue;
+                }
+            };
+            let key = instance.key(&account.email);
+            if !keys.insert(key.clone()) {
+                errors.push(format!("账户 {} 重复定义", key));
+            }
+            for (index, cookie) in std::iter::once(&account.cookie).chain(&account.cookies).enumerate() {
+                if let Some(problem) = cookie_problem(cookie.expose()) {
+                    errors.push(format!("账户 {} 的 cookie #{} {}", key, index + 1, problem));
+                }
+            }
+        }
+    }
+
+    // 同一目录只报告一次
+    let mut failed = HashSet::new();
+    for file in [&config.log_file, &config.history_file, &config.state_file, &config.audit_log] {
+        if let Err(e) = perms::writable_dir(file) {
+            if failed.insert(e.clone()) {
+                errors.push(format!("无法写入 {}: {}", file, e));
+            }
+        }
+    }
+    errors
+}
+
+// 这些 cookie 在发送前就会失败或一定被服务端拒绝
+pub fn cookie_problem(cookie: &str) -> Option<&'static str> {
+    if cookie.trim().is_empty() {
+        return Some("为空");
+    }
+    if HeaderValue::from_str(cookie).is_err() {
+        return Some("包含请求头中不允许的字符（如换行）");
+    }
+    let pairs = cookie.split(';').filter_map(|pair| pair.split_once('='));
+    if !pairs.into_iter().any(|(name, _)| !name.trim().is_empty()) {
+        return Some("不是 name=value 形式");
+    }
+    None
+}
+
+fn lint(config: &Config, config_path: &str) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+    let mut warnings = Vec::new();
+
+    // 账户无法读取时 check 已报错
+    for accounts in config.shared_cookies().unwrap_or_default() {
+        warnings.push(format!("账户 {} 使用了相同的 cookie", accounts.join("、")));
+    }
+
+    if config.max_retries > MAX_SANE_RETRIES {
+        warnings.push(format!("max_retries 为 {}，服务端故障时每个账户会重试很久并可能触发限流（建议不超过 {}）",
+            config.max_retries, MAX_SANE_RETRIES));
+    }
+    for (name, profile) in &config.profiles {
+        if let Some(max_retries) = profile.max_retries.filter(|&max_retries| max_retries > MAX_SANE_RETRIES) {
+            warnings.push(format!("profile {} 的 max_retries 为 {}（建议不超过 {}）", name, max_retries, MAX_SANE_RETRIES));
+        }
+    }
+
+    if config.notifiers.is_empty() {
+        warnings.push("未配置 notifiers，签到失败或 cookie 失效时不会收到通知".to_string());
+    }
+
+    for path in perms::credential_files(config, config_path) {
+        if let Some(mode) = perms::exposed(&path) {
+            warnings.push(format!("{} 的权限为 {:o}，同组或其他用户可以读取其中的 cookie 或密钥，运行 web --fix-perms 修改为 600", path, mode));
+        }
+    }
+    Ok(warnings)
+}
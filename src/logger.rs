@@ -1,27 +1,254 @@
-use std::{io::Write, path::PathBuf};
+use std::{
+    borrow::Cow,
+    collections::BTreeMap,
+    fs::File,
+    io::Write,
+    path::{Path, PathBuf},
+    sync::{mpsc, Arc, Mutex, OnceLock, Weak},
+    thread::JoinHandle,
+};
 
 pub trait Logger {
     fn log(&self, content: &str) -> std::io::Result<()>;
 }
 
+// 同一路径的所有 FileLogger 共用一个写入线程（进程内登记），并发调用 log 时整行写入、互不穿插；
+// log 只把内容发送到通道，写入失败由写入线程输出到标准错误。最后一个 FileLogger 释放时等待写完
 pub struct FileLogger {
     file_path: PathBuf,
+    max_line: Option<usize>,
+    archive_dir: Option<PathBuf>,
+    writer: OnceLock<Arc<Writer>>,
+}
+
+static WRITERS: Mutex<BTreeMap<PathBuf, Weak<Writer>>> = Mutex::new(BTreeMap::new());
+
+// 用线程而不是 tokio 任务：Drop 中需要同步等待剩余日志写完，运行时关闭时任务会被直接丢弃
+struct Writer {
+    sender: Option<mpsc::Sender<String>>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl Writer {
+    fn spawn(mut file: LogFile) -> std::io::Result<Self> {
+        let (sender, receiver) = mpsc::channel::<String>();
+        let thread = std::thread::Builder::new().name("log-writer".to_string()).spawn(move || {
+            while let Ok(first) = receiver.recv() {
+                // 一次取出已排队的全部日志，按行首时间戳排序后写入
+                let mut batch = vec![first];
+                batch.extend(receiver.try_iter());
+                sort_by_timestamp(&mut batch);
+                for content in batch {
+                    if let Err(e) = file.log(&content) {
+                        eprintln!("记录日志失败: {}", e);
+                    }
+                }
+            }
+            if let Err(e) = file.flush_repeated() {
+                eprintln!("记录日志失败: {}", e);
+            }
+        })?;
+        Ok(Self { sender: Some(sender), thread: Some(thread) })
+    }
+}
+
+impl Drop for Writer {
+    fn drop(&mut self) {
+        drop(self.sender.take());
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+struct LogFile {
+    file_path: PathBuf,
+    max_line: Option<usize>,
+    archive_dir: Option<PathBuf>,
+    file: Option<File>,
+    buf: Vec<u8>,
+    last: String,
+    repeated: u32,
+    archived: u32,
+}
+
+impl LogFile {
+    // 与上一条内容相同（忽略行首时间戳）的连续消息只计数，内容变化或日志关闭时写入一行重复次数
+    fn log(&mut self, content: &str) -> std::io::Result<()> {
+        let message = without_timestamp(content);
+        if !self.last.is_empty() && self.last == message {
+            self.repeated += 1;
+            return Ok(());
+        }
+        self.flush_repeated()?;
+        self.last = message.to_string();
+        let content = self.truncate(content);
+        self.write_line(&content)
+    }
+
+    fn write_line(&mut self, content: &str) -> std::io::Result<()> {
+        if self.file.is_none() {
+            self.file = Some(open_log_file(&self.file_path)?);
+        }
+        self.buf.clear();
+        self.buf.extend_from_slice(content.as_bytes());
+        self.buf.push(b'\n');
+        let result = self.file.as_mut().map_or(Ok(()), |f| f.write_all(&self.buf));
+        if result.is_err() {
+            self.file = None;
+        }
+        result
+    }
+
+    fn flush_repeated(&mut self) -> std::io::Result<()> {
+        if self.repeated == 0 {
+            return Ok(());
+        }
+        let summary = format!("[{}] 上一条消息重复 {} 次",
+            chrono::Local::now().format("%Y-%m-%d %H:%M:%S"), self.repeated);
+        self.repeated = 0;
+        self.write_line(&summary)
+    }
+
+    // 超过 max_line 字节的日志截断并加上标记；配置了 archive_dir 时完整内容另存一份，标记中给出文件路径
+    fn truncate<'a>(&mut self, content: &'a str) -> Cow<'a, str> {
+        let Some(max_line) = self.max_line.filter(|max_line| content.len() > *max_line) else {
+            return Cow::Borrowed(content);
+        };
+        let mut end = max_line;
+        while !content.is_char_boundary(end) {
+            end -= 1;
+        }
+        let pointer = match &self.archive_dir {
+            Some(dir) => match archive(dir, &mut self.archived, content) {
+                Ok(path) => format!("，完整内容见 {}", path.display()),
+                Err(e) => {
+                    eprintln!("保存完整日志失败: {}", e);
+                    String::new()
+                }
+            },
+            None => String::new(),
+        };
+        Cow::Owned(format!("{}…[已截断，共 {} 字节{}]", &content[..end], content.len(), pointer))
+    }
 }
 
 impl FileLogger {
     pub fn new(file_path: impl Into<PathBuf>) -> Self {
         Self {
             file_path: file_path.into(),
+            max_line: None,
+            archive_dir: None,
+            writer: OnceLock::new(),
         }
     }
+
+    // 同一路径已有写入线程时沿用其设置
+    pub fn with_max_line(mut self, max_line: Option<usize>, archive_dir: Option<impl Into<PathBuf>>) -> Self {
+        self.max_line = max_line;
+        self.archive_dir = archive_dir.map(Into::into);
+        self
+    }
+
+    // 第一次写日志时才创建或加入写入线程，只构造不写入的 FileLogger 不启动线程
+    fn writer(&self) -> std::io::Result<&Arc<Writer>> {
+        if let Some(writer) = self.writer.get() {
+            return Ok(writer);
+        }
+        let mut writers = WRITERS.lock().unwrap_or_else(|e| e.into_inner());
+        writers.retain(|_, writer| writer.strong_count() > 0);
+        let writer = match writers.get(&self.file_path).and_then(Weak::upgrade) {
+            Some(writer) => writer,
+            None => {
+                let writer = Arc::new(Writer::spawn(LogFile {
+                    file_path: self.file_path.clone(),
+                    max_line: self.max_line,
+                    archive_dir: self.archive_dir.clone(),
+                    file: None,
+                    buf: Vec::new(),
+                    last: String::new(),
+                    repeated: 0,
+                    archived: 0,
+                })?);
+                writers.insert(self.file_path.clone(), Arc::downgrade(&writer));
+                writer
+            }
+        };
+        Ok(self.writer.get_or_init(|| writer))
+    }
 }
 
 impl Logger for FileLogger {
     fn log(&self, content: &str) -> std::io::Result<()> {
-        std::fs::OpenOptions::new()
-            .create(true)
-            .append(true)
-            .open(&self.file_path)
-            .and_then(|mut file| file.write_all(format!("{}{}", content, "\n").as_bytes()))
+        let sender = self.writer()?.sender.as_ref()
+            .ok_or_else(|| std::io::Error::other("log writer closed"))?;
+        sender.send(content.to_string())
+            .map_err(|_| std::io::Error::other("log writer closed"))
+    }
+}
+
+// 稳定排序；没有时间戳的行（如多行内容的续行）跟随前一行
+fn sort_by_timestamp(batch: &mut [String]) {
+    if batch.len() < 2 {
+        return;
+    }
+    let mut previous = String::new();
+    let mut keyed: Vec<(String, String)> = batch.iter_mut().map(|content| {
+        if let Some(timestamp) = timestamp(content) {
+            previous = timestamp.to_string();
+        }
+        (previous.clone(), std::mem::take(content))
+    }).collect();
+    keyed.sort_by(|a, b| a.0.cmp(&b.0));
+    for (slot, (_, content)) in batch.iter_mut().zip(keyed) {
+        *slot = content;
+    }
+}
+
+fn timestamp(content: &str) -> Option<&str> {
+    let (timestamp, _) = content.strip_prefix('[')?.split_once("] ")?;
+    (timestamp.len() == 19).then_some(timestamp)
+}
+
+fn without_timestamp(content: &str) -> &str {
+    match timestamp(content) {
+        Some(timestamp) => &content[timestamp.len() + 3..],
+        None => content,
     }
-}
\ No newline at end of file
+}
+
+pub fn ensure_parent_dir(path: &Path) -> std::io::Result<()> {
+    match path.parent().filter(|parent| !parent.as_os_str().is_empty()) {
+        Some(parent) => std::fs::create_dir_all(parent),
+        None => Ok(()),
+    }
+}
+
+// 同一秒内可能有多个日志实例写入，文件已存在时递增序号
+fn archive(dir: &Path, sequence: &mut u32, content: &str) -> std::io::Result<PathBuf> {
+    std::fs::create_dir_all(dir)?;
+    let timestamp = chrono::Local::now().format("%Y%m%d-%H%M%S");
+    loop {
+        *sequence += 1;
+        let path = dir.join(format!("{}-{}.log", timestamp, sequence));
+        match std::fs::OpenOptions::new().write(true).create_new(true).open(&path) {
+            Ok(mut file) => {
+                file.write_all(content.as_bytes())?;
+                return Ok(path);
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => continue,
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+fn open_log_file(path: &Path) -> std::io::Result<File> {
+    ensure_parent_dir(path)?;
+    let file = std::fs::OpenOptions::new().create(true).append(true).open(path)?;
+    // 新建的日志文件在 Windows 上写入 UTF-8 BOM，避免记事本按 GBK 打开中文乱码
+    #[cfg(windows)]
+    if file.metadata()?.len() == 0 {
+        (&file).write_all(b"\xEF\xBB\xBF")?;
+    }
+    Ok(file)
+}
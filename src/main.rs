@@ -1,47 +1,178 @@
-use futures::future::join_all;
-use reqwest;
+use clap::Parser;
+use futures::StreamExt;
 
+mod account;
+mod accounts;
+mod artifacts;
+mod audit;
+mod backoff;
+mod bench;
+mod catalog;
+mod cli;
+mod clock;
 mod config;
+mod config_edit;
+mod console;
+mod cookie_age;
+mod cookie_pool;
+mod cron;
+mod daemon;
+mod daemon_lock;
+mod diff;
+mod doctor;
+#[cfg(any(test, feature = "doubles"))]
+#[cfg_attr(not(test), expect(dead_code))]
+mod doubles;
+mod error;
+mod events;
+mod fields;
+mod fingerprint;
+#[cfg(any(test, feature = "fixtures"))]
+#[cfg_attr(not(test), expect(dead_code))]
+mod fixtures;
+mod history;
+mod hook;
+mod http;
+mod init;
+mod jitter;
+mod ledger;
+mod lint;
 mod logger;
+mod mock;
+mod network;
+mod notifier;
+mod output;
+mod perms;
+mod queue;
+mod rate_limit;
+mod redact;
+mod results;
+mod run_state;
+mod runner;
+mod secret;
+mod serve;
 mod service;
+mod shutdown;
+mod signing;
+mod sink;
+mod smtp;
+mod state_store;
+mod stats;
+mod status;
+mod systemd;
+mod template;
+mod trigger;
 
+use cli::{CheckinArgs, Cli, Command};
 use config::Config;
-use logger::FileLogger;
-use service::CheckinService;
+use output::OutputFormat;
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    let config = Config::load_from_file("config.yaml")?;
+    console::init();
+    let cli = Cli::parse();
+    if cli.fix_perms {
+        return perms::fix(&load_config(&cli)?, &cli.config_path()?);
+    }
+    for path in cli.config_paths().unwrap_or_else(|_| cli.config.clone()) {
+        perms::warn_exposed_config(&path);
+    }
+    match cli.command {
+        Some(Command::Checkin(ref args)) => run_checkin(&cli, args).await,
+        Some(Command::Bench(args)) => bench::run(&args).await,
+        Some(Command::Status(ref args)) => status::run(&load_config(&cli)?, args).await,
+        Some(Command::History(ref args)) => stats::history(&load_config(&cli)?, args).await,
+        Some(Command::ListAccounts(ref args)) => account::list(&load_config(&cli)?, args),
+        Some(Command::Stats(ref args)) => stats::run(&load_config(&cli)?, args),
+        Some(Command::Diff(ref args)) => diff::run(&load_config(&cli)?, args),
+        Some(Command::Reconcile(ref args)) => ledger::run(&load_config(&cli)?, args),
+        Some(Command::Account(ref args)) => account::run(&load_config(&cli)?, &cli.config_path()?, args).await,
+        Some(Command::ShowRequest(ref args)) => account::show_request(&load_config(&cli)?, args).await,
+        Some(Command::Notify(ref args)) => notifier::run(&load_config(&cli)?, args).await,
+        Some(Command::LintConfig) => lint::run(&load_config(&cli)?, &cli.config_path()?),
+        Some(Command::Doctor(ref args)) => doctor::run(&cli, args).await,
+        Some(Command::Init(ref args)) => init::run(&cli.init_path()?, args).await,
+        Some(Command::Serve(ref args)) => serve::run(&cli.config_path()?, cli.profile.clone(), args).await,
+        None => run_checkin(&cli, &cli.checkin).await,
+    }
+}
 
-    let client = reqwest::Client::builder().build()?;
-    let logger = Box::new(FileLogger::new(&config.log_file));
-    let service = CheckinService::new(
-        client,
-        logger,
-        config.max_retries,
-        config.retry_delay,
-    );
+fn load_config(cli: &Cli) -> Result<Config, Box<dyn std::error::Error>> {
+    Config::load_from_file(&cli.config_path()?, cli.profile.as_deref())
+}
 
-    let futures = config.accounts.into_iter().map(|account| {
-        let service = &service;
-        async move {
-            let result = service.checkin(&account).await;
-            match result {
-                Ok(_) => (),
-                Err(e) => {
-                    let error_log = format!("[{}] 账户 {} 处理失败: {}", 
-                        chrono::Local::now().format("%Y-%m-%d %H:%M:%S"),
-                        account.email, e);
-                    eprintln!("{}", error_log);
-                    if let Err(log_err) = service.logger.log(&error_log) {
-                        eprintln!("记录日志失败: {}", log_err);
-                    }
-                }
-            }
+async fn run_checkin(cli: &Cli, args: &CheckinArgs) -> Result<(), Box<dyn std::error::Error>> {
+    if args.events.as_deref() == Some("-") && !matches!(args.output, OutputFormat::Table) {
+        return Err("--events - and --output both write to stdout, write the events to a file instead".into());
+    }
+    console::set_result_format(args.output);
+    let paths = cli.config_paths()?;
+    if paths.len() > 1 {
+        return run_checkins(cli, args, &paths).await;
+    }
+    let mut config = Config::load_with_overrides(&paths[0], cli.profile.as_deref(), &args.overrides.profile())?;
+    if args.test_email {
+        return notifier::send_test_email(&config).await;
+    }
+    if args.daemon {
+        return daemon::run(cli, args).await;
+    }
+    args.filter.apply(&mut config)?;
+    if args.dry_run {
+        return runner::dry_run(&config).await;
+    }
+    runner::ensure_allowed_window(&config, args.force)?;
+    let shutdown = shutdown::install();
+    if args.events.is_none() && !args.verbose {
+        runner::execute(&config, args.resume, &shutdown).await?;
+        return Ok(());
+    }
+    let (sender, events) = events::channel();
+    let verbose = args.verbose;
+    let events = events.inspect(move |event| {
+        if verbose {
+            events::print_attempt(event);
         }
     });
-
-    join_all(futures).await;
-
+    let (report, written) = tokio::join!(
+        runner::execute_with_events(&config, args.resume, &shutdown, sender),
+        async {
+            match &args.events {
+                Some(path) => events::write_json_lines(events, path).await
+                    .map_err(|e| format!("failed to write events to {}: {}", path, e)),
+                None => {
+                    events.for_each(|_| async {}).await;
+                    Ok(())
+                }
+            }
+        });
+    written?;
+    report?;
     Ok(())
-}
\ No newline at end of file
+}
+
+// 多个配置文件：各自加载（--only/--skip/--tag 未选中任何账户的配置跳过），同时签到，共用一个汇总和退出状态
+async fn run_checkins(cli: &Cli, args: &CheckinArgs, paths: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+    if args.daemon || args.test_email || args.events.is_some() {
+        return Err("--daemon, --test-email and --events accept a single --config".into());
+    }
+    if !matches!(args.output, OutputFormat::Table) {
+        return Err("--output other than table accepts a single --config".into());
+    }
+    let mut configs = Vec::new();
+    for path in paths {
+        let mut config = Config::load_with_overrides(path, cli.profile.as_deref(), &args.overrides.profile())
+            .map_err(|e| format!("{}: {}", path, e))?;
+        if args.filter.retain(&mut config)? {
+            configs.push((path.clone(), config));
+        }
+    }
+    if configs.is_empty() {
+        return Err("no accounts match --only/--skip/--tag".into());
+    }
+    if args.dry_run {
+        return runner::dry_run_many(&configs).await;
+    }
+    let shutdown = shutdown::install();
+    runner::execute_many(&configs, args.resume, args.force, &shutdown).await
+}
@@ -0,0 +1,78 @@
+use rand::Rng;
+use std::net::SocketAddr;
+use tokio::net::TcpListener;
+
+use crate::http::{self, Request, Response};
+
+const CHECKIN_RESPONSE: &str = r#"{"code":1,"message":"Checkin! Got 1 Points","list":[{"change":"1.0000000000000000","balance":"100.0000000000000000"}]}"#;
+const ALREADY_CHECKED_IN_RESPONSE: &str = r#"{"code":1,"message":"Please Try Tomorrow","list":[{"change":"0.0000000000000000","balance":"100.0000000000000000"}]}"#;
+const LOGIN_REQUIRED_RESPONSE: &str = r#"{"code":-2,"message":"没有权限"}"#;
+const RATE_LIMITED_RESPONSE: &str = r#"{"code":-1,"message":"Too many requests, please slow down"}"#;
+const STATUS_RESPONSE: &str = r#"{"code":0,"data":{"email":"demo@example.com","leftDays":"42.000000000000000000","vip":1,"emailVerified":true}}"#;
+
+#[derive(Debug, Clone, Copy)]
+pub enum MockMode {
+    Success,
+    Demo,
+    #[cfg(any(test, feature = "fixtures"))]
+    Fixture(&'static crate::fixtures::Fixture),
+}
+
+pub struct MockServer {
+    addr: SocketAddr,
+}
+
+impl MockServer {
+    pub async fn start(mode: MockMode) -> std::io::Result<Self> {
+        let listener = TcpListener::bind("127.0.0.1:0").await?;
+        let addr = listener.local_addr()?;
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                tokio::spawn(http::serve_connection(stream, move |request| async move {
+                    respond(mode, &request)
+                }));
+            }
+        });
+        Ok(Self { addr })
+    }
+
+    pub fn base_url(&self) -> String {
+        format!("http://{}", self.addr)
+    }
+}
+
+// 最近三天每天一次签到的积分流水
+fn points_response() -> String {
+    let now = chrono::Utc::now().timestamp_millis();
+    let history: Vec<serde_json::Value> = (0..3)
+        .map(|days_ago| serde_json::json!({
+            "time": now - days_ago * 86_400_000,
+            "asset": "cny",
+            "business": "system:checkin",
+            "change": "1.0000000000000000",
+            "balance": format!("{}.0000000000000000", 100 - days_ago),
+        }))
+        .collect();
+    serde_json::json!({"code": 0, "points": "100.0000000000000000", "history": history}).to_string()
+}
+
+fn respond(mode: MockMode, request: &Request) -> Response {
+    match request.path.as_str() {
+        "/api/user/status" => Response::json(200, STATUS_RESPONSE),
+        "/api/user/points" => Response::json(200, points_response()),
+        "/api/user/checkin" => match mode {
+            MockMode::Success => Response::json(200, CHECKIN_RESPONSE),
+            MockMode::Demo => match rand::thread_rng().gen_range(0..10) {
+                0..=5 => Response::json(200, CHECKIN_RESPONSE),
+                6 => Response::json(200, ALREADY_CHECKED_IN_RESPONSE),
+                7 => Response::json(200, LOGIN_REQUIRED_RESPONSE),
+                _ => Response::json(429, RATE_LIMITED_RESPONSE),
+            },
+            #[cfg(any(test, feature = "fixtures"))]
+            MockMode::Fixture(fixture) if fixture.json => Response::json(fixture.status, fixture.body),
+            #[cfg(any(test, feature = "fixtures"))]
+            MockMode::Fixture(fixture) => Response::text(fixture.status, fixture.body),
+        },
+        _ => Response::text(404, "not found"),
+    }
+}
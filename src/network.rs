@@ -0,0 +1,86 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use tokio::{
+    net::TcpStream,
+    sync::Mutex,
+    time::{sleep, timeout, Duration},
+};
+use tokio_util::sync::CancellationToken;
+
+use crate::error::{CheckinError, ErrorCategory};
+
+const PROBE_TIMEOUT: Duration = Duration::from_secs(5);
+const MAX_PROBE_INTERVAL: Duration = Duration::from_secs(120);
+
+// 网络整体不可用（如夜间路由器重启）时的等待额度，同一服务实例的所有账户共享；
+// 并发的账户排队等待同一次恢复，而不是各自计入失败
+pub struct NetworkWait {
+    target: String,
+    max_wait: Duration,
+    initial_delay: Duration,
+    waited: Mutex<Duration>,
+    recoveries: AtomicU64,
+}
+
+impl NetworkWait {
+    pub fn new(target: String, max_wait: Duration, initial_delay: Duration) -> Self {
+        Self {
+            target,
+            max_wait,
+            initial_delay,
+            waited: Mutex::new(Duration::ZERO),
+            recoveries: AtomicU64::new(0),
+        }
+    }
+
+    // 请求发出前记录，用于判断失败之后网络是否已由其他账户等到恢复
+    pub fn recoveries(&self) -> u64 {
+        self.recoveries.load(Ordering::SeqCst)
+    }
+
+    // 建立 TCP 连接即视为网络可用（包含 DNS 解析）
+    async fn probe(&self) -> bool {
+        matches!(timeout(PROBE_TIMEOUT, TcpStream::connect(&self.target)).await, Ok(Ok(_)))
+    }
+
+    // 网络可用时立即返回 false（按普通失败处理）；不可用时按指数退避重新探测，
+    // 在额度内恢复则返回 true，调用方重新发起本次请求且不计入重试次数
+    pub async fn wait(&self, since: u64, shutdown: Option<&CancellationToken>, log: impl Fn(&str)) -> Result<bool, CheckinError> {
+        let mut waited = self.waited.lock().await;
+        if self.recoveries() != since {
+            return Ok(true);
+        }
+        if self.probe().await {
+            return Ok(false);
+        }
+        if *waited >= self.max_wait {
+            return Ok(false);
+        }
+        log(&format!("无法连接 {}，网络可能不可用，等待恢复（最多再等 {} 秒）",
+            self.target, (self.max_wait - *waited).as_secs()));
+        let mut delay = self.initial_delay;
+        while *waited < self.max_wait {
+            let step = delay.min(self.max_wait - *waited);
+            match shutdown {
+                Some(shutdown) => tokio::select! {
+                    _ = sleep(step) => {}
+                    _ = shutdown.cancelled() => return Err(CheckinError::new(ErrorCategory::Cancelled, "运行已取消")),
+                },
+                None => sleep(step).await,
+            }
+            *waited += step;
+            if self.probe().await {
+                self.recoveries.fetch_add(1, Ordering::SeqCst);
+                log(&format!("网络已恢复（等待 {} 秒），继续签到", waited.as_secs()));
+                return Ok(true);
+            }
+            delay = (delay * 2).min(MAX_PROBE_INTERVAL);
+        }
+        log(&format!("等待 {} 秒后网络仍不可用，按失败处理", waited.as_secs()));
+        Ok(false)
+    }
+}
+
+pub fn probe_target(url: &str) -> Option<String> {
+    let url = reqwest::Url::parse(url).ok()?;
+    Some(format!("{}:{}", url.host_str()?, url.port_or_known_default()?))
+}
@@ -0,0 +1,1071 @@
+use base64::{engine::general_purpose::STANDARD, Engine};
+use futures::future::LocalBoxFuture;
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::{cell::RefCell, collections::BTreeMap, path::{Path, PathBuf}, rc::Rc, time::{Duration, Instant}};
+
+use crate::{
+    cli::{NotifyArgs, NotifyCommand, OutputArgs},
+    config::{account_key, Config, MessageTemplates, NotifierConfig},
+    console,
+    logger::Logger,
+    output::{self, TableRow},
+    queue::DiskQueue,
+    rate_limit::RateLimiter,
+    redact,
+    results::{AccountReport, RunReport, RunSummary, Status},
+    runner,
+    secret::SecretString,
+    smtp::{self, Message, SmtpServer},
+    template::{self, MessageVars},
+};
+
+type HmacSha256 = Hmac<Sha256>;
+
+const NOTIFY_TIMEOUT: Duration = Duration::from_secs(15);
+// 限流摘要中最多列出的账户数
+const DIGEST_ACCOUNTS: usize = 10;
+
+// 单个账户的最终签到结果；跳过的账户不发送通知
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Notification {
+    pub time: String,
+    pub account: String,
+    pub email: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub provider: Option<String>,
+    pub status: Status,
+    pub message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub change: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub balance: Option<String>,
+    #[serde(skip)]
+    text: String,
+}
+
+impl Notification {
+    pub fn from_report(report: &AccountReport, messages: &MessageTemplates) -> Option<Self> {
+        let message = match report.status {
+            Status::Success => report.message.clone().unwrap_or_default(),
+            Status::Failed => report.error.clone().unwrap_or_default(),
+            Status::Skipped => return None,
+        };
+        let account = account_key(report.provider.as_deref(), &report.email);
+        let template = match report.status {
+            Status::Success => &messages.success_notification,
+            _ => &messages.failure_notification,
+        };
+        let text = MessageVars {
+            account: &account,
+            email: &report.email,
+            provider: report.provider.as_deref(),
+            status: report.status.as_str(),
+            message: &message,
+            change: report.change.as_deref(),
+            balance: report.balance.as_deref(),
+        }.render(template);
+        Some(Self {
+            time: chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string(),
+            account,
+            email: report.email.clone(),
+            provider: report.provider.clone(),
+            status: report.status,
+            message,
+            change: report.change.clone(),
+            balance: report.balance.clone(),
+            text,
+        })
+    }
+
+    // notify_on 为 summary 时运行结束后发给各渠道的一条汇总消息；account 为 summary，message 与 text 均为汇总文本
+    pub fn summary(summary: &RunSummary) -> Self {
+        let text = summary.to_text();
+        Self {
+            time: chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string(),
+            account: "summary".to_string(),
+            email: String::new(),
+            provider: None,
+            status: if summary.failed > 0 { Status::Failed } else { Status::Success },
+            message: text.clone(),
+            change: Some(format!("{:+}", summary.total_change)),
+            balance: None,
+            text,
+        }
+    }
+
+    // 与上次运行相比的退化，正文为 diff::summary 的输出，状态记为 failed
+    pub fn regressions(summary: &str) -> Self {
+        Self {
+            time: chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string(),
+            account: "regressions".to_string(),
+            email: String::new(),
+            provider: None,
+            status: Status::Failed,
+            message: summary.to_string(),
+            change: None,
+            balance: None,
+            text: summary.to_string(),
+        }
+    }
+
+    // 需要人工处理的提醒（cookie 即将过期、账户等待验证等），状态记为 failed
+    pub fn reminder(account: &str, email: &str, provider: Option<&str>, reminder: &str) -> Self {
+        Self {
+            time: chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string(),
+            account: account.to_string(),
+            email: email.to_string(),
+            provider: provider.map(str::to_string),
+            status: Status::Failed,
+            message: reminder.to_string(),
+            change: None,
+            balance: None,
+            text: format!("提醒: {}", reminder),
+        }
+    }
+
+    // 因渠道限流未发送的通知合并成的一条摘要；account 为 digest，有失败时状态为 failed
+    fn digest(held: &[Notification], per_hour: u32) -> Self {
+        let mut accounts: Vec<String> = held.iter().take(DIGEST_ACCOUNTS)
+            .map(|notification| format!("{} {}", notification.account, notification.status.as_str()))
+            .collect();
+        if held.len() > DIGEST_ACCOUNTS {
+            accounts.push(format!("等 {} 条", held.len()));
+        }
+        let text = format!("另有 {} 条通知因超过每小时 {} 条的限制未发送: {}", held.len(), per_hour, accounts.join("、"));
+        Self {
+            time: chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string(),
+            account: "digest".to_string(),
+            email: String::new(),
+            provider: None,
+            status: if held.iter().any(|notification| notification.status == Status::Failed) { Status::Failed } else { Status::Success },
+            message: text.clone(),
+            change: None,
+            balance: None,
+            text,
+        }
+    }
+
+    // 适合聊天类渠道的一行文本，按 messages.success_notification/failure_notification 生成
+    pub fn text(&self) -> String {
+        self.text.clone()
+    }
+}
+
+// 渠道服务端的地址，web doctor 用来检查是否可达（不发送消息）；HTTP 渠道只保留协议、主机和端口，不含地址中的 token
+pub enum Endpoint {
+    Http(String),
+    Tcp { host: String, port: u16 },
+}
+
+impl Endpoint {
+    fn origin(url: &str) -> Self {
+        Endpoint::Http(redact::origin(url))
+    }
+}
+
+pub trait Notifier {
+    fn name(&self) -> String;
+    fn endpoint(&self) -> Endpoint;
+    fn notify<'a>(&'a self, notification: &'a Notification) -> LocalBoxFuture<'a, Result<(), Box<dyn std::error::Error>>>;
+
+    // 整次运行结束后调用一次；逐账户发送的渠道不需要实现
+    fn summarize<'a>(&'a self, _report: &'a RunReport) -> LocalBoxFuture<'a, Result<(), Box<dyn std::error::Error>>> {
+        Box::pin(async { Ok(()) })
+    }
+
+    // 是否逐账户发送通知、是否在运行结束时发送汇总；不发送的一侧不调用，也不计入投递统计
+    fn per_account(&self) -> bool {
+        true
+    }
+
+    fn summarizes(&self) -> bool {
+        false
+    }
+}
+
+// 本次运行中一个渠道的投递情况，写入运行报告
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Delivery {
+    pub channel: String,
+    pub required: bool,
+    pub sent: u32,
+    pub failed: u32,
+    // 因 max_per_hour 未逐条发送、合并到摘要中的通知数
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub suppressed: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_error: Option<String>,
+}
+
+fn is_zero(value: &u32) -> bool {
+    *value == 0
+}
+
+// 通知渠道及其投递统计；所有服务实例共用同一组渠道，统计覆盖整次运行
+pub struct Channel {
+    notifier: Box<dyn Notifier>,
+    delivery: RefCell<Delivery>,
+    retry: Option<RetryQueue>,
+    limit: Option<ChannelLimit>,
+}
+
+// 配置 max_per_hour 时的每小时额度（令牌桶，保存到文件）；超出的通知留到运行结束时合并为一条摘要
+struct ChannelLimit {
+    limiter: RateLimiter,
+    per_hour: u32,
+    held: RefCell<Vec<Notification>>,
+}
+
+// 配置 notify_queue 时每个渠道一个重试队列，目录名由渠道配置（不含 required）的哈希区分
+struct RetryQueue {
+    queue: DiskQueue,
+    max_age: chrono::Duration,
+}
+
+// 等待补发的通知；保存生成好的通知文本，补发时不受之后修改的 messages 模板影响
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+enum Pending {
+    Notification { notification: Notification, text: String },
+    // email 等渠道的汇总，补发时按保存的运行报告重新生成邮件
+    Summary { report: RunReport },
+}
+
+#[derive(Serialize, Deserialize)]
+struct Queued {
+    // RFC 3339
+    queued_at: String,
+    #[serde(flatten)]
+    pending: Pending,
+}
+
+impl Channel {
+    fn new(notifier: Box<dyn Notifier>, required: bool, retry: Option<RetryQueue>) -> Self {
+        let delivery = Delivery {
+            channel: notifier.name(),
+            required,
+            sent: 0,
+            failed: 0,
+            suppressed: 0,
+            last_error: None,
+        };
+        Self { notifier, delivery: RefCell::new(delivery), retry, limit: None }
+    }
+
+    pub fn with_max_per_hour(mut self, max_per_hour: Option<u32>, file_path: impl Into<PathBuf>) -> Self {
+        self.limit = max_per_hour.map(|per_hour| ChannelLimit {
+            limiter: RateLimiter::open(per_hour, file_path),
+            per_hour,
+            held: RefCell::new(Vec::new()),
+        });
+        self
+    }
+
+    // 额度用完时暂存通知，返回 true 表示本次不发送
+    fn hold(&self, notification: &Notification) -> bool {
+        let Some(limit) = &self.limit else {
+            return false;
+        };
+        if limit.limiter.try_acquire().is_none() {
+            return false;
+        }
+        limit.held.borrow_mut().push(notification.clone());
+        self.delivery.borrow_mut().suppressed += 1;
+        true
+    }
+
+    // 不经过配置直接使用给定的通知实现，不带重试队列
+    #[cfg(any(test, feature = "doubles"))]
+    pub fn from_notifier(notifier: Box<dyn Notifier>, required: bool) -> Self {
+        Self::new(notifier, required, None)
+    }
+
+    async fn send(&self, pending: &Pending) -> Result<(), Box<dyn std::error::Error>> {
+        match pending {
+            Pending::Notification { notification, .. } => self.notifier.notify(notification).await,
+            Pending::Summary { report } => self.notifier.summarize(report).await,
+        }
+    }
+
+    // 发送失败且配置了 notify_queue 时保存到队列，下次运行开始时补发
+    async fn deliver(&self, pending: Pending) -> Result<(), Box<dyn std::error::Error>> {
+        let result = self.send(&pending).await;
+        let Some(retry) = &self.retry else {
+            return result;
+        };
+        result.or_else(|e| {
+            let queued = Queued { queued_at: chrono::Local::now().to_rfc3339(), pending };
+            let backlog = retry.queue.push(&serde_json::to_vec(&queued)?)?;
+            Err(format!("{}; queued for retry ({} pending)", e, backlog).into())
+        })
+    }
+
+    // 按顺序补发积压的通知，遇到发送失败即停止，其余留到下次运行；超过 max_age 或无法解析的直接丢弃。
+    // 返回补发成功的条数
+    async fn resend(&self, retry: &RetryQueue) -> Result<usize, Box<dyn std::error::Error>> {
+        let mut delivered = 0;
+        for path in retry.queue.pending()? {
+            let queued = serde_json::from_slice::<Queued>(&std::fs::read(&path)?).map_err(|e| e.to_string())
+                .and_then(|queued| Ok((chrono::DateTime::parse_from_rfc3339(&queued.queued_at).map_err(|e| e.to_string())?, queued)));
+            let mut queued = match queued {
+                Ok((queued_at, queued)) if chrono::Local::now().fixed_offset() - queued_at <= retry.max_age => queued,
+                Ok((queued_at, _)) => {
+                    eprintln!("[{}] 丢弃 {} 生成的通知，超过 {} 小时仍未能通过 {} 发送",
+                        chrono::Local::now().format("%Y-%m-%d %H:%M:%S"), queued_at.format("%Y-%m-%d %H:%M:%S"),
+                        retry.max_age.num_hours(), self.delivery.borrow().channel);
+                    std::fs::remove_file(&path)?;
+                    continue;
+                }
+                Err(e) => {
+                    eprintln!("[{}] 丢弃无法解析的待补发通知 {}: {}",
+                        chrono::Local::now().format("%Y-%m-%d %H:%M:%S"), path.display(), e);
+                    std::fs::remove_file(&path)?;
+                    continue;
+                }
+            };
+            // text 不属于通知 JSON，单独保存
+            if let Pending::Notification { notification, text } = &mut queued.pending {
+                notification.text = std::mem::take(text);
+            }
+            self.send(&queued.pending).await
+                .map_err(|e| format!("{} (delivered {} queued before failing)", e, delivered))?;
+            std::fs::remove_file(&path)?;
+            delivered += 1;
+        }
+        Ok(delivered)
+    }
+
+    fn record(&self, result: Result<(), Box<dyn std::error::Error>>, logger: &dyn Logger) {
+        let mut delivery = self.delivery.borrow_mut();
+        match result {
+            Ok(()) => delivery.sent += 1,
+            Err(e) => {
+                delivery.failed += 1;
+                delivery.last_error = Some(e.to_string());
+                let error_log = format!("[{}] 通知 {} 发送失败: {}",
+                    chrono::Local::now().format("%Y-%m-%d %H:%M:%S"), delivery.channel, e);
+                eprintln!("{}", error_log);
+                if let Err(log_err) = logger.log(&error_log) {
+                    eprintln!("记录日志失败: {}", log_err);
+                }
+            }
+        }
+    }
+}
+
+pub fn build(config: &Config) -> Result<Rc<Vec<Channel>>, Box<dyn std::error::Error>> {
+    let channels = config.notifiers.iter()
+        .map(|notifier| {
+            let retry = match &config.notify_queue {
+                Some(queue) => Some(RetryQueue {
+                    queue: DiskQueue::new(Path::new(&queue.dir).join(queue_name(notifier)?)),
+                    max_age: chrono::Duration::hours(queue.max_age as i64),
+                }),
+                None => None,
+            };
+            let limit_file = config.notify_rate_limit_file(&queue_name(notifier)?);
+            Ok(Channel::new(create(config, notifier.clone())?, notifier.required(), retry)
+                .with_max_per_hour(notifier.max_per_hour(), limit_file))
+        })
+        .collect::<Result<Vec<_>, Box<dyn std::error::Error>>>()?;
+    Ok(Rc::new(channels))
+}
+
+// <type>-<配置哈希>；同类型的多个渠道（如两个钉钉机器人）各自排队和限流，修改 required 或 max_per_hour 不影响已积压的通知
+fn queue_name(notifier: &NotifierConfig) -> Result<String, Box<dyn std::error::Error>> {
+    let mut identity = serde_json::to_value(notifier)?;
+    let kind = identity["type"].as_str().unwrap_or("notifier").to_string();
+    if let Some(fields) = identity.as_object_mut() {
+        fields.remove("required");
+        fields.remove("max_per_hour");
+    }
+    Ok(format!("{}-{}", kind, &hex::encode(Sha256::digest(identity.to_string().as_bytes()))[..12]))
+}
+
+// 运行开始时补发之前运行中发送失败的通知；结果只记录日志，不计入本次运行的投递统计
+pub async fn resend_queued(channels: &[Channel], logger: &dyn Logger) {
+    for channel in channels {
+        let Some(retry) = &channel.retry else {
+            continue;
+        };
+        let log_content = match channel.resend(retry).await {
+            Ok(0) => continue,
+            Ok(delivered) => format!("[{}] 已通过 {} 补发 {} 条之前发送失败的通知",
+                chrono::Local::now().format("%Y-%m-%d %H:%M:%S"), channel.delivery.borrow().channel, delivered),
+            Err(e) => format!("[{}] 通过 {} 补发通知失败，留待下次运行: {}",
+                chrono::Local::now().format("%Y-%m-%d %H:%M:%S"), channel.delivery.borrow().channel, e),
+        };
+        console::progress(&log_content);
+        if let Err(log_err) = logger.log(&log_content) {
+            eprintln!("记录日志失败: {}", log_err);
+        }
+    }
+}
+
+// 依次发送到所有渠道，发送失败只记录日志和统计（配置 notify_queue 时保存待补发），不影响签到结果
+pub async fn notify(channels: &[Channel], notification: &Notification, logger: &dyn Logger) {
+    for channel in channels.iter().filter(|channel| channel.notifier.per_account()) {
+        if channel.hold(notification) {
+            continue;
+        }
+        let pending = Pending::Notification { notification: notification.clone(), text: notification.text.clone() };
+        let result = channel.deliver(pending).await;
+        channel.record(result, logger);
+    }
+}
+
+fn create(config: &Config, notifier: NotifierConfig) -> Result<Box<dyn Notifier>, Box<dyn std::error::Error>> {
+    Ok(match notifier {
+        NotifierConfig::Webhook { url, headers, body_template, content_type, .. } => Box::new(WebhookNotifier {
+            client: runner::http_client(config)?,
+            url,
+            headers,
+            body_template,
+            content_type,
+        }),
+        NotifierConfig::Telegram { bot_token, chat_id, endpoint, .. } => Box::new(TelegramNotifier {
+            client: runner::http_client(config)?,
+            bot_token,
+            chat_id,
+            endpoint: endpoint.unwrap_or_else(|| "https://api.telegram.org".to_string()),
+        }),
+        NotifierConfig::ServerChan { token, endpoint, .. } => Box::new(PushNotifier::new(config, PushService::ServerChan, token, endpoint)?),
+        NotifierConfig::PushPlus { token, endpoint, .. } => Box::new(PushNotifier::new(config, PushService::PushPlus, token, endpoint)?),
+        NotifierConfig::Bark { token, endpoint, .. } => Box::new(PushNotifier::new(config, PushService::Bark, token, endpoint)?),
+        NotifierConfig::DingTalk { url, secret, .. } => Box::new(BotNotifier::new(config, BotPlatform::DingTalk, url, secret)?),
+        NotifierConfig::WeCom { url, .. } => Box::new(BotNotifier::new(config, BotPlatform::WeCom, url, None)?),
+        NotifierConfig::Feishu { url, secret, .. } => Box::new(BotNotifier::new(config, BotPlatform::Feishu, url, secret)?),
+        NotifierConfig::Email { host, port, tls, username, password, from, to, .. } => Box::new(EmailNotifier {
+            server: SmtpServer {
+                port: port.unwrap_or(tls.default_port()),
+                host,
+                tls,
+                username,
+                password,
+            },
+            from,
+            to,
+        }),
+    })
+}
+
+pub struct ChannelEndpoint {
+    pub channel: String,
+    pub required: bool,
+    pub endpoint: Endpoint,
+}
+
+// 每个渠道的服务端地址，供 web doctor 检查
+pub fn endpoints(config: &Config) -> Result<Vec<ChannelEndpoint>, Box<dyn std::error::Error>> {
+    config.notifiers.iter()
+        .map(|notifier_config| {
+            let notifier = create(config, notifier_config.clone())?;
+            Ok(ChannelEndpoint {
+                channel: notifier.name(),
+                required: notifier_config.required(),
+                endpoint: notifier.endpoint(),
+            })
+        })
+        .collect()
+}
+
+// 运行结束时把因限流暂存的通知合并为一条摘要发送；摘要不占用额度，每个渠道每次运行最多一条
+pub async fn send_digests(channels: &[Channel], logger: &dyn Logger) {
+    for channel in channels {
+        let Some(limit) = &channel.limit else {
+            continue;
+        };
+        let held = limit.held.take();
+        if held.is_empty() {
+            continue;
+        }
+        let notification = Notification::digest(&held, limit.per_hour);
+        let pending = Pending::Notification { notification: notification.clone(), text: notification.text.clone() };
+        let result = channel.deliver(pending).await;
+        channel.record(result, logger);
+    }
+}
+
+// 运行结束后的汇总通知（只发给发送汇总的渠道），失败只记录日志和统计
+pub async fn summarize(channels: &[Channel], report: &RunReport, logger: &dyn Logger) {
+    for channel in channels.iter().filter(|channel| channel.notifier.summarizes()) {
+        let result = channel.deliver(Pending::Summary { report: report.clone() }).await;
+        channel.record(result, logger);
+    }
+}
+
+// 有过发送的渠道（含失败）；邮件等只在汇总时发送的渠道在 summarize 之后才有记录
+pub fn deliveries(channels: &[Channel]) -> Vec<Delivery> {
+    channels.iter()
+        .map(|channel| channel.delivery.borrow().clone())
+        .filter(|delivery| delivery.sent + delivery.failed + delivery.suppressed > 0)
+        .collect()
+}
+
+// 每个渠道一行投递统计；必需渠道有失败时返回错误，使本次运行失败
+pub fn check_deliveries(deliveries: &[Delivery], logger: &dyn Logger) -> Result<(), Box<dyn std::error::Error>> {
+    let mut failed = Vec::new();
+    for delivery in deliveries {
+        let mut log_content = format!("[{}] 通知 {}: 成功 {}，失败 {}",
+            chrono::Local::now().format("%Y-%m-%d %H:%M:%S"), delivery.channel, delivery.sent, delivery.failed);
+        if delivery.suppressed > 0 {
+            log_content.push_str(&format!("，超出限流合并为摘要 {}", delivery.suppressed));
+        }
+        if delivery.failed > 0 {
+            if delivery.required {
+                failed.push(delivery.channel.as_str());
+            } else {
+                log_content.push_str("（非必需渠道，不影响运行结果）");
+            }
+        }
+        console::progress(&log_content);
+        if let Err(log_err) = logger.log(&log_content) {
+            eprintln!("记录日志失败: {}", log_err);
+        }
+    }
+    if !failed.is_empty() {
+        return Err(format!("failed to deliver notifications via {}", failed.join(", ")).into());
+    }
+    Ok(())
+}
+
+// 用配置中的账户列表构造一份未签到的报告，用于测试汇总邮件
+fn test_report(config: &Config) -> Result<RunReport, Box<dyn std::error::Error>> {
+    let mut accounts = Vec::new();
+    for instance in config.provider_instances() {
+        for account in instance.accounts()? {
+            accounts.push(AccountReport::skipped(&account?.email, "测试邮件，未执行签到").with_provider(instance.name.as_deref()));
+        }
+    }
+    let now = chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string();
+    Ok(RunReport {
+        started_at: now.clone(),
+        finished_at: now,
+        accounts,
+        notifications: Vec::new(),
+        regressions: None,
+    })
+}
+
+// web --test-email：发送到所有 email 通知
+pub async fn send_test_email(config: &Config) -> Result<(), Box<dyn std::error::Error>> {
+    let report = test_report(config)?;
+    let notifiers = config.notifiers.iter()
+        .filter(|notifier| matches!(notifier, NotifierConfig::Email { .. }))
+        .map(|notifier| create(config, notifier.clone()))
+        .collect::<Result<Vec<_>, _>>()?;
+    if notifiers.is_empty() {
+        return Err("no email notifier configured".into());
+    }
+    for notifier in &notifiers {
+        notifier.summarize(&report).await.map_err(|e| format!("{}: {}", notifier.name(), e))?;
+        println!("已发送测试邮件: {}", notifier.name());
+    }
+    Ok(())
+}
+
+#[derive(Serialize)]
+pub struct TestRow {
+    pub channel: String,
+    pub required: bool,
+    pub ok: bool,
+    // 从开始发送到渠道返回（email 含测试汇总邮件）的耗时
+    pub latency_ms: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl TableRow for TestRow {
+    const HEADERS: &'static [&'static str] = &["channel", "required", "result", "latency", "error"];
+
+    fn cells(&self) -> Vec<String> {
+        vec![
+            self.channel.clone(),
+            if self.required { "yes" } else { "no" }.to_string(),
+            if self.ok { "ok" } else { "failed" }.to_string(),
+            output::seconds(self.latency_ms),
+            output::cell(self.error.as_deref()),
+        ]
+    }
+}
+
+pub async fn run(config: &Config, args: &NotifyArgs) -> Result<(), Box<dyn std::error::Error>> {
+    match &args.command {
+        NotifyCommand::Test(output) => test(config, output).await,
+    }
+}
+
+// web notify test：逐个渠道发送测试消息并列出结果
+async fn test(config: &Config, args: &OutputArgs) -> Result<(), Box<dyn std::error::Error>> {
+    if config.notifiers.is_empty() {
+        return Err("no notifiers configured".into());
+    }
+    let rows = send_tests(config).await?;
+    output::print(&rows, args.format)?;
+    let failed = rows.iter().filter(|row| !row.ok).count();
+    if failed > 0 {
+        return Err(format!("{} of {} notification channels failed", failed, rows.len()).into());
+    }
+    Ok(())
+}
+
+// 逐个渠道发送测试消息（web notify test、web doctor --send-test）：逐账户渠道发送一条测试通知，email 发送测试汇总邮件
+pub async fn send_tests(config: &Config) -> Result<Vec<TestRow>, Box<dyn std::error::Error>> {
+    let report = test_report(config)?;
+    let message = "测试通知，未执行签到".to_string();
+    let account = "test@example.com";
+    let notification = Notification {
+        time: chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string(),
+        account: account.to_string(),
+        email: account.to_string(),
+        provider: None,
+        status: Status::Success,
+        text: MessageVars {
+            account,
+            email: account,
+            provider: None,
+            status: Status::Success.as_str(),
+            message: &message,
+            change: None,
+            balance: None,
+        }.render(&config.messages.success_notification),
+        message,
+        change: None,
+        balance: None,
+    };
+    let mut rows = Vec::new();
+    for notifier_config in &config.notifiers {
+        let notifier = create(config, notifier_config.clone())?;
+        let started = Instant::now();
+        let result = async {
+            if notifier.per_account() {
+                notifier.notify(&notification).await?;
+            }
+            if notifier.summarizes() {
+                notifier.summarize(&report).await?;
+            }
+            Ok::<(), Box<dyn std::error::Error>>(())
+        }.await;
+        rows.push(TestRow {
+            channel: notifier.name(),
+            required: notifier_config.required(),
+            ok: result.is_ok(),
+            latency_ms: started.elapsed().as_millis() as u64,
+            error: result.err().map(|e| e.to_string()),
+        });
+    }
+    Ok(rows)
+}
+
+// 默认 POST 通知 JSON：{"time","account","email","provider","status","message","change","balance"}；
+// 配置 body_template 时按模板生成请求体，content_type 为 JSON 时替换的值会做 JSON 字符串转义
+pub struct WebhookNotifier {
+    client: reqwest::Client,
+    url: String,
+    headers: BTreeMap<String, String>,
+    body_template: Option<String>,
+    content_type: String,
+}
+
+impl WebhookNotifier {
+    fn body(&self, notification: &Notification) -> Result<String, Box<dyn std::error::Error>> {
+        let Some(body_template) = &self.body_template else {
+            return Ok(serde_json::to_string(notification)?);
+        };
+        let json = self.content_type.contains("json");
+        let text = notification.text();
+        let values: Vec<(&str, String)> = [
+            ("account", notification.account.as_str()),
+            ("email", notification.email.as_str()),
+            ("provider", notification.provider.as_deref().unwrap_or("")),
+            ("status", notification.status.as_str()),
+            ("message", notification.message.as_str()),
+            ("change", notification.change.as_deref().unwrap_or("")),
+            ("balance", notification.balance.as_deref().unwrap_or("")),
+            ("time", notification.time.as_str()),
+            ("text", text.as_str()),
+        ].into_iter().map(|(name, value)| {
+            if json {
+                (name, template::json_escape(value))
+            } else {
+                (name, value.to_string())
+            }
+        }).collect();
+        let vars: Vec<(&str, &str)> = values.iter().map(|(name, value)| (*name, value.as_str())).collect();
+        Ok(template::render(body_template, &vars))
+    }
+}
+
+impl Notifier for WebhookNotifier {
+    fn name(&self) -> String {
+        format!("webhook:{}", redact::origin(&self.url))
+    }
+
+    fn endpoint(&self) -> Endpoint {
+        Endpoint::origin(&self.url)
+    }
+
+    fn notify<'a>(&'a self, notification: &'a Notification) -> LocalBoxFuture<'a, Result<(), Box<dyn std::error::Error>>> {
+        Box::pin(async move {
+            let mut request = self.client.post(&self.url)
+                .timeout(NOTIFY_TIMEOUT)
+                .header("Content-Type", &self.content_type)
+                .body(self.body(notification)?);
+            for (name, value) in &self.headers {
+                request = request.header(name, value);
+            }
+            request.send().await
+                .and_then(reqwest::Response::error_for_status)
+                .map_err(|e| e.without_url())?;
+            Ok(())
+        })
+    }
+}
+
+// Bot API sendMessage；bot_token 只出现在请求地址中，错误信息里会被替换掉
+pub struct TelegramNotifier {
+    client: reqwest::Client,
+    bot_token: SecretString,
+    chat_id: String,
+    endpoint: String,
+}
+
+impl Notifier for TelegramNotifier {
+    fn name(&self) -> String {
+        format!("telegram:{}", self.chat_id)
+    }
+
+    fn endpoint(&self) -> Endpoint {
+        Endpoint::origin(&self.endpoint)
+    }
+
+    fn notify<'a>(&'a self, notification: &'a Notification) -> LocalBoxFuture<'a, Result<(), Box<dyn std::error::Error>>> {
+        Box::pin(async move {
+            let url = format!("{}/bot{}/sendMessage", self.endpoint.trim_end_matches('/'), self.bot_token.expose());
+            let response = self.client.post(url)
+                .timeout(NOTIFY_TIMEOUT)
+                .json(&serde_json::json!({
+                    "chat_id": self.chat_id,
+                    "text": notification.text(),
+                    "disable_web_page_preview": true,
+                }))
+                .send()
+                .await
+                .map_err(|e| self.bot_token.redact(&e.to_string()))?;
+            let status = response.status();
+            if !status.is_success() {
+                let body: serde_json::Value = response.json().await.unwrap_or_default();
+                return Err(format!("telegram returned {}: {}", status,
+                    body["description"].as_str().unwrap_or("no description")).into());
+            }
+            Ok(())
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum PushService {
+    ServerChan,
+    PushPlus,
+    Bark,
+}
+
+impl PushService {
+    fn name(&self) -> &'static str {
+        match self {
+            PushService::ServerChan => "serverchan",
+            PushService::PushPlus => "pushplus",
+            PushService::Bark => "bark",
+        }
+    }
+
+    fn default_endpoint(&self) -> &'static str {
+        match self {
+            PushService::ServerChan => "https://sctapi.ftqq.com",
+            PushService::PushPlus => "https://www.pushplus.plus",
+            PushService::Bark => "https://api.day.app",
+        }
+    }
+
+    // 接口返回 HTTP 200 时仍可能失败，以响应中的 code 为准
+    fn success_code(&self) -> i64 {
+        match self {
+            PushService::ServerChan => 0,
+            PushService::PushPlus | PushService::Bark => 200,
+        }
+    }
+}
+
+// ServerChan Turbo、PushPlus、Bark：标题为账户和结果，正文为一行摘要
+pub struct PushNotifier {
+    client: reqwest::Client,
+    service: PushService,
+    token: SecretString,
+    endpoint: String,
+}
+
+impl PushNotifier {
+    fn new(config: &Config, service: PushService, token: SecretString, endpoint: Option<String>) -> Result<Self, Box<dyn std::error::Error>> {
+        Ok(Self {
+            client: runner::http_client(config)?,
+            service,
+            token,
+            endpoint: endpoint.unwrap_or_else(|| service.default_endpoint().to_string()).trim_end_matches('/').to_string(),
+        })
+    }
+
+    fn request(&self, notification: &Notification) -> reqwest::RequestBuilder {
+        let title = match notification.status {
+            Status::Success => format!("签到成功: {}", notification.account),
+            _ => format!("签到失败: {}", notification.account),
+        };
+        let text = notification.text();
+        match self.service {
+            PushService::ServerChan => self.client.post(format!("{}/{}.send", self.endpoint, self.token.expose()))
+                .form(&[("title", title.as_str()), ("desp", text.as_str())]),
+            PushService::PushPlus => self.client.post(format!("{}/send", self.endpoint))
+                .json(&serde_json::json!({
+                    "token": self.token.expose(),
+                    "title": title,
+                    "content": text,
+                    "template": "txt",
+                })),
+            PushService::Bark => self.client.post(format!("{}/push", self.endpoint))
+                .json(&serde_json::json!({
+                    "device_key": self.token.expose(),
+                    "title": title,
+                    "body": text,
+                    "group": "web",
+                })),
+        }
+    }
+}
+
+impl Notifier for PushNotifier {
+    fn name(&self) -> String {
+        self.service.name().to_string()
+    }
+
+    fn endpoint(&self) -> Endpoint {
+        Endpoint::origin(&self.endpoint)
+    }
+
+    fn notify<'a>(&'a self, notification: &'a Notification) -> LocalBoxFuture<'a, Result<(), Box<dyn std::error::Error>>> {
+        Box::pin(async move {
+            let response = self.request(notification)
+                .timeout(NOTIFY_TIMEOUT)
+                .send()
+                .await
+                .map_err(|e| self.token.redact(&e.to_string()))?;
+            let status = response.status();
+            let body: serde_json::Value = response.json().await.unwrap_or_default();
+            if !status.is_success() || body["code"].as_i64() != Some(self.service.success_code()) {
+                let message = body["message"].as_str().or(body["msg"].as_str()).unwrap_or("no message");
+                return Err(format!("{} returned {}: {}", self.service.name(), status, self.token.redact(message)).into());
+            }
+            Ok(())
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum BotPlatform {
+    DingTalk,
+    WeCom,
+    Feishu,
+}
+
+impl BotPlatform {
+    fn name(&self) -> &'static str {
+        match self {
+            BotPlatform::DingTalk => "dingtalk",
+            BotPlatform::WeCom => "wecom",
+            BotPlatform::Feishu => "feishu",
+        }
+    }
+}
+
+// 钉钉、企业微信、飞书群机器人；webhook 地址中带有 access token，因此名称和错误信息中都不包含地址
+pub struct BotNotifier {
+    client: reqwest::Client,
+    platform: BotPlatform,
+    url: reqwest::Url,
+    secret: Option<SecretString>,
+}
+
+impl BotNotifier {
+    fn new(config: &Config, platform: BotPlatform, url: String, secret: Option<SecretString>) -> Result<Self, Box<dyn std::error::Error>> {
+        Ok(Self {
+            client: runner::http_client(config)?,
+            platform,
+            url: reqwest::Url::parse(&url)?,
+            secret,
+        })
+    }
+
+    fn request(&self, notification: &Notification) -> Result<reqwest::RequestBuilder, Box<dyn std::error::Error>> {
+        let text = notification.text();
+        Ok(match self.platform {
+            // 钉钉：sign = base64(hmac_sha256(key=secret, "<毫秒时间戳>\n<secret>"))，附加在地址参数中
+            BotPlatform::DingTalk => {
+                let mut url = self.url.clone();
+                if let Some(secret) = &self.secret {
+                    let timestamp = chrono::Utc::now().timestamp_millis().to_string();
+                    let sign = hmac_base64(secret.expose().as_bytes(), format!("{}\n{}", timestamp, secret.expose()).as_bytes())?;
+                    url.query_pairs_mut().append_pair("timestamp", &timestamp).append_pair("sign", &sign);
+                }
+                self.client.post(url).json(&serde_json::json!({
+                    "msgtype": "text",
+                    "text": { "content": text },
+                }))
+            }
+            BotPlatform::WeCom => self.client.post(self.url.clone()).json(&serde_json::json!({
+                "msgtype": "text",
+                "text": { "content": text },
+            })),
+            // 飞书：sign = base64(hmac_sha256(key="<秒级时间戳>\n<secret>", 空消息))，放在请求体中
+            BotPlatform::Feishu => {
+                let mut body = serde_json::json!({
+                    "msg_type": "text",
+                    "content": { "text": text },
+                });
+                if let Some(secret) = &self.secret {
+                    let timestamp = chrono::Utc::now().timestamp().to_string();
+                    body["timestamp"] = timestamp.clone().into();
+                    body["sign"] = hmac_base64(format!("{}\n{}", timestamp, secret.expose()).as_bytes(), b"")?.into();
+                }
+                self.client.post(self.url.clone()).json(&body)
+            }
+        })
+    }
+}
+
+impl Notifier for BotNotifier {
+    fn name(&self) -> String {
+        self.platform.name().to_string()
+    }
+
+    fn endpoint(&self) -> Endpoint {
+        Endpoint::origin(self.url.as_str())
+    }
+
+    fn notify<'a>(&'a self, notification: &'a Notification) -> LocalBoxFuture<'a, Result<(), Box<dyn std::error::Error>>> {
+        Box::pin(async move {
+            let response = self.request(notification)?
+                .timeout(NOTIFY_TIMEOUT)
+                .send()
+                .await
+                .map_err(|e| e.without_url())?;
+            let status = response.status();
+            let body: serde_json::Value = response.json().await.unwrap_or_default();
+            // 钉钉/企业微信返回 errcode/errmsg，飞书返回 code/msg
+            let code = body["errcode"].as_i64().or(body["code"].as_i64());
+            if !status.is_success() || code != Some(0) {
+                let message = body["errmsg"].as_str().or(body["msg"].as_str()).unwrap_or("no message");
+                return Err(format!("{} returned {}: {}", self.platform.name(), status, message).into());
+            }
+            Ok(())
+        })
+    }
+}
+
+fn hmac_base64(key: &[u8], message: &[u8]) -> Result<String, Box<dyn std::error::Error>> {
+    let mut mac = HmacSha256::new_from_slice(key)?;
+    mac.update(message);
+    Ok(STANDARD.encode(mac.finalize().into_bytes()))
+}
+
+// SMTP 汇总邮件：纯文本 + HTML 表格，主题中带成功/失败/跳过数量
+pub struct EmailNotifier {
+    server: SmtpServer,
+    from: String,
+    to: Vec<String>,
+}
+
+impl Notifier for EmailNotifier {
+    fn name(&self) -> String {
+        format!("email:{}", self.to.join(","))
+    }
+
+    fn endpoint(&self) -> Endpoint {
+        Endpoint::Tcp { host: self.server.host.clone(), port: self.server.port }
+    }
+
+    fn notify<'a>(&'a self, _notification: &'a Notification) -> LocalBoxFuture<'a, Result<(), Box<dyn std::error::Error>>> {
+        Box::pin(async { Ok(()) })
+    }
+
+    fn per_account(&self) -> bool {
+        false
+    }
+
+    fn summarizes(&self) -> bool {
+        true
+    }
+
+    fn summarize<'a>(&'a self, report: &'a RunReport) -> LocalBoxFuture<'a, Result<(), Box<dyn std::error::Error>>> {
+        Box::pin(async move {
+            let message = Message {
+                from: self.from.clone(),
+                to: self.to.clone(),
+                subject: format!("签到结果：成功 {}，失败 {}，跳过 {}",
+                    report.count(Status::Success), report.count(Status::Failed), report.count(Status::Skipped)),
+                text: report.to_text(),
+                html: report.to_html(),
+            };
+            smtp::send(&self.server, &message).await
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        doubles::MemoryNotifier,
+        error::{CheckinError, ErrorCategory},
+        service::CheckinFailure,
+    };
+
+    struct Discard;
+
+    impl Logger for Discard {
+        fn log(&self, _content: &str) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    fn failed(email: &str) -> Notification {
+        let failure = CheckinFailure::new(CheckinError::new(ErrorCategory::InvalidCookie, "cookie 已失效"), Vec::new());
+        Notification::from_report(&AccountReport::failed(email, &failure), &MessageTemplates::default()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn max_per_hour_holds_the_rest_for_one_digest() {
+        let path = std::env::temp_dir().join(format!("web-notify-limit-{}.json", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let notifier = MemoryNotifier::new("limited");
+        let channels = vec![notifier.channel(true).with_max_per_hour(Some(1), &path)];
+        for email in ["a@example.com", "b@example.com", "c@example.com"] {
+            notify(&channels, &failed(email), &Discard).await;
+        }
+        // 第一条照常发送，之后的暂存到运行结束
+        let sent: Vec<String> = notifier.notifications().into_iter().map(|notification| notification.email).collect();
+        assert_eq!(sent, ["a@example.com"]);
+        assert_eq!(deliveries(&channels)[0].suppressed, 2);
+
+        // 摘要只发送一次，暂存的通知发送后即清空
+        send_digests(&channels, &Discard).await;
+        send_digests(&channels, &Discard).await;
+        let _ = std::fs::remove_file(&path);
+        let notifications = notifier.notifications();
+        assert_eq!(notifications.len(), 2);
+        assert_eq!(notifications[1].account, "digest");
+        assert_eq!(notifications[1].status, Status::Failed);
+        assert_eq!(notifications[1].text(), "另有 2 条通知因超过每小时 1 条的限制未发送: b@example.com failed、c@example.com failed");
+        let delivery = &deliveries(&channels)[0];
+        assert_eq!((delivery.sent, delivery.suppressed), (2, 2));
+    }
+}
@@ -0,0 +1,71 @@
+use clap::ValueEnum;
+use serde::Serialize;
+
+#[derive(Debug, Clone, Copy, Default, ValueEnum)]
+pub enum OutputFormat {
+    #[default]
+    Table,
+    Json,
+    Yaml,
+    // 与表格相同的列，带表头，按 RFC 4180 转义
+    Csv,
+}
+
+pub trait TableRow {
+    const HEADERS: &'static [&'static str];
+
+    fn cells(&self) -> Vec<String>;
+}
+
+pub fn print<T: Serialize + TableRow>(rows: &[T], format: OutputFormat) -> Result<(), Box<dyn std::error::Error>> {
+    match format {
+        OutputFormat::Table => print_table(rows),
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(rows)?),
+        OutputFormat::Yaml => print!("{}", serde_yaml::to_string(rows)?),
+        OutputFormat::Csv => print_csv(rows),
+    }
+    Ok(())
+}
+
+fn print_table<T: TableRow>(rows: &[T]) {
+    let cells: Vec<Vec<String>> = rows.iter().map(TableRow::cells).collect();
+    let mut widths: Vec<usize> = T::HEADERS.iter().map(|header| header.chars().count()).collect();
+    for row in &cells {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+
+    let headers: Vec<String> = T::HEADERS.iter().map(|header| header.to_string()).collect();
+    for row in std::iter::once(&headers).chain(&cells) {
+        let line: Vec<String> = row.iter().zip(&widths)
+            .map(|(cell, width)| format!("{:<width$}", cell, width = width))
+            .collect();
+        println!("{}", line.join("  ").trim_end());
+    }
+}
+
+fn print_csv<T: TableRow>(rows: &[T]) {
+    let headers: Vec<String> = T::HEADERS.iter().map(|header| header.to_string()).collect();
+    for row in std::iter::once(headers).chain(rows.iter().map(TableRow::cells)) {
+        let line: Vec<String> = row.iter().map(|cell| csv_field(cell)).collect();
+        println!("{}", line.join(","));
+    }
+}
+
+fn csv_field(cell: &str) -> String {
+    if cell.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", cell.replace('"', "\"\""))
+    } else {
+        cell.to_string()
+    }
+}
+
+pub fn cell(value: Option<&str>) -> String {
+    value.unwrap_or("-").to_string()
+}
+
+// 毫秒数显示为秒，保留一位小数
+pub fn seconds(ms: u64) -> String {
+    format!("{:.1}s", ms as f64 / 1000.0)
+}
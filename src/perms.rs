@@ -0,0 +1,93 @@
+use std::{
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+use crate::config::Config;
+
+// 创建或覆盖只有所有者可读写（0600）的文件；用于包含 cookie 的配置和状态文件，
+// 创建时即为 0600，不会在写入期间短暂地对其他用户可读
+pub fn write_private(path: impl AsRef<Path>, content: impl AsRef<[u8]>) -> std::io::Result<()> {
+    let path = path.as_ref();
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    let mut file = options.open(path)?;
+    // 文件已存在（例如上次中断留下的临时文件）时 mode 不生效，需要显式修改
+    restrict(path)?;
+    file.write_all(content.as_ref())?;
+    Ok(())
+}
+
+#[cfg(unix)]
+pub fn restrict(path: impl AsRef<Path>) -> std::io::Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+    std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o600))
+}
+
+#[cfg(not(unix))]
+pub fn restrict(_path: impl AsRef<Path>) -> std::io::Result<()> {
+    Ok(())
+}
+
+// 同组或其他用户可读时返回文件权限位；文件不存在或非 Unix 平台返回 None
+#[cfg(unix)]
+pub fn exposed(path: impl AsRef<Path>) -> Option<u32> {
+    use std::os::unix::fs::PermissionsExt;
+    let mode = std::fs::metadata(path).ok()?.permissions().mode() & 0o777;
+    (mode & 0o044 != 0).then_some(mode)
+}
+
+#[cfg(not(unix))]
+pub fn exposed(_path: impl AsRef<Path>) -> Option<u32> {
+    None
+}
+
+// 文件所在目录存在（不存在时创建）且可以写入，返回该目录
+pub fn writable_dir(file: &str) -> Result<PathBuf, String> {
+    let dir = Path::new(file).parent().filter(|dir| !dir.as_os_str().is_empty()).unwrap_or_else(|| Path::new("."));
+    std::fs::create_dir_all(dir).map_err(|e| format!("无法创建 {}: {}", dir.display(), e))?;
+    let probe = dir.join(".web-probe");
+    std::fs::write(&probe, b"").map_err(|e| format!("{} 不可写: {}", dir.display(), e))?;
+    let _ = std::fs::remove_file(&probe);
+    Ok(dir.to_path_buf())
+}
+
+// 每次启动时检查主配置文件，只输出到标准错误，不影响运行
+pub fn warn_exposed_config(config_path: &str) {
+    if let Some(mode) = exposed(config_path) {
+        eprintln!("[{}] 警告: 配置文件 {} 的权限为 {:o}，同组或其他用户可以读取其中的 cookie；运行 web -c {} --fix-perms 修改为 600",
+            chrono::Local::now().format("%Y-%m-%d %H:%M:%S"), config_path, mode, config_path);
+    }
+}
+
+// 包含 cookie、token 或私钥的文件：主配置、include、accounts_file、signing_key
+pub fn credential_files(config: &Config, config_path: &str) -> Vec<String> {
+    let base_dir = Path::new(config_path).parent().unwrap_or_else(|| Path::new(""));
+    let mut files = vec![config_path.to_string()];
+    files.extend(config.include.iter().map(|include| base_dir.join(include).to_string_lossy().into_owned()));
+    files.extend(config.provider_instances().into_iter().filter_map(|instance| instance.accounts_file));
+    files.extend(config.signing_key.clone());
+    files.dedup();
+    files
+}
+
+// web --fix-perms：把同组或其他用户可读的凭据文件改为 0600 后退出
+pub fn fix(config: &Config, config_path: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let mut fixed = 0;
+    for path in credential_files(config, config_path) {
+        if let Some(mode) = exposed(&path) {
+            restrict(&path).map_err(|e| format!("failed to change permissions of {}: {}", path, e))?;
+            println!("已将 {} 的权限从 {:o} 修改为 600", path, mode);
+            fixed += 1;
+        }
+    }
+    if fixed == 0 {
+        println!("凭据文件的权限均无需修改");
+    }
+    Ok(())
+}
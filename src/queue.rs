@@ -0,0 +1,46 @@
+use std::path::PathBuf;
+
+const MAX_QUEUED: usize = 100;
+
+// 发送失败等待重试的内容（结果输出、通知），每条一个文件，文件名以 UTC 时间开头，按文件名排序即为发送顺序
+pub struct DiskQueue {
+    dir: PathBuf,
+}
+
+impl DiskQueue {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    pub fn pending(&self) -> std::io::Result<Vec<PathBuf>> {
+        let entries = match std::fs::read_dir(&self.dir) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e),
+        };
+        let mut paths = Vec::new();
+        for entry in entries {
+            let path = entry?.path();
+            if path.extension().is_some_and(|extension| extension == "json") {
+                paths.push(path);
+            }
+        }
+        paths.sort();
+        Ok(paths)
+    }
+
+    // 超过上限时丢弃最旧的一条，返回当前积压数量
+    pub fn push(&self, content: &[u8]) -> std::io::Result<usize> {
+        std::fs::create_dir_all(&self.dir)?;
+        let name = format!("{}-{:08x}.json", chrono::Utc::now().format("%Y%m%dT%H%M%S%.3fZ"), rand::random::<u32>());
+        std::fs::write(self.dir.join(name), content)?;
+        let pending = self.pending()?;
+        let excess = pending.len().saturating_sub(MAX_QUEUED);
+        for path in &pending[..excess] {
+            eprintln!("[{}] 重试队列已满，丢弃 {}",
+                chrono::Local::now().format("%Y-%m-%d %H:%M:%S"), path.display());
+            std::fs::remove_file(path)?;
+        }
+        Ok(pending.len() - excess)
+    }
+}
@@ -0,0 +1,125 @@
+use serde::{Deserialize, Serialize};
+use std::{path::PathBuf, sync::Mutex, time::Duration};
+
+use crate::logger;
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Bucket {
+    tokens: f64,
+    updated_at: f64,
+}
+
+pub struct RateLimiter {
+    capacity: f64,
+    refill_per_sec: f64,
+    file_path: PathBuf,
+    bucket: Mutex<Bucket>,
+}
+
+fn now_secs() -> f64 {
+    chrono::Utc::now().timestamp_millis() as f64 / 1000.0
+}
+
+impl RateLimiter {
+    // 令牌桶状态持久化到文件，连续多次短时间运行也共享同一个每小时额度
+    pub fn open(requests_per_hour: u32, file_path: impl Into<PathBuf>) -> Self {
+        let file_path = file_path.into();
+        let capacity = requests_per_hour as f64;
+        let bucket = std::fs::read_to_string(&file_path)
+            .ok()
+            .and_then(|content| serde_json::from_str::<Bucket>(&content).ok())
+            .map(|bucket| Bucket {
+                tokens: bucket.tokens.min(capacity),
+                updated_at: bucket.updated_at,
+            })
+            .unwrap_or(Bucket {
+                tokens: capacity,
+                updated_at: now_secs(),
+            });
+        Self {
+            capacity,
+            refill_per_sec: capacity / 3600.0,
+            file_path,
+            bucket: Mutex::new(bucket),
+        }
+    }
+
+    pub fn capacity(&self) -> f64 {
+        self.capacity
+    }
+
+    // 取到令牌返回 None，否则返回需要等待的时间
+    pub fn try_acquire(&self) -> Option<Duration> {
+        let mut bucket = self.bucket.lock().unwrap_or_else(|e| e.into_inner());
+        let now = now_secs();
+        let elapsed = (now - bucket.updated_at).max(0.0);
+        bucket.tokens = (bucket.tokens + elapsed * self.refill_per_sec).min(self.capacity);
+        bucket.updated_at = now;
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            self.save(&bucket);
+            return None;
+        }
+        Some(Duration::from_secs_f64((1.0 - bucket.tokens) / self.refill_per_sec))
+    }
+
+    fn save(&self, bucket: &Bucket) {
+        let result = serde_json::to_vec(bucket)
+            .map_err(std::io::Error::from)
+            .and_then(|content| {
+                logger::ensure_parent_dir(&self.file_path)?;
+                std::fs::write(&self.file_path, content)
+            });
+        if let Err(e) = result {
+            eprintln!("保存限流状态失败: {}", e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn state_file(name: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("web-rate-limit-{}-{}.json", name, std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        path
+    }
+
+    #[test]
+    fn bucket_starts_full_and_empties() {
+        let path = state_file("empties");
+        let limiter = RateLimiter::open(3, &path);
+        assert_eq!(limiter.capacity(), 3.0);
+        assert!((0..3).all(|_| limiter.try_acquire().is_none()));
+        // 每小时 3 个令牌，一个令牌需要约 1200 秒
+        let wait = limiter.try_acquire().unwrap();
+        assert!(wait > Duration::from_secs(1190) && wait <= Duration::from_secs(1200), "{:?}", wait);
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn state_is_shared_across_runs() {
+        let path = state_file("shared");
+        assert!(RateLimiter::open(2, &path).try_acquire().is_none());
+        let limiter = RateLimiter::open(2, &path);
+        assert!(limiter.try_acquire().is_none());
+        assert!(limiter.try_acquire().is_some());
+        // 降低额度时保存的令牌数不超过新的容量
+        assert!(RateLimiter::open(1, &path).try_acquire().is_some());
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn tokens_refill_over_time() {
+        let path = state_file("refill");
+        let bucket = Bucket { tokens: 0.0, updated_at: now_secs() - 1800.0 };
+        std::fs::write(&path, serde_json::to_vec(&bucket).unwrap()).unwrap();
+        // 每小时 4 个，半小时补充 2 个
+        let limiter = RateLimiter::open(4, &path);
+        assert!(limiter.try_acquire().is_none());
+        assert!(limiter.try_acquire().is_none());
+        assert!(limiter.try_acquire().is_some());
+        let _ = std::fs::remove_file(&path);
+    }
+}
@@ -0,0 +1,281 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    config::account_key,
+    error::ErrorCategory,
+    notifier::Delivery,
+    output::{self, TableRow},
+    service::{self, Attempt, CheckinFailure, CheckinResult, StageTimings},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Status {
+    Success,
+    Failed,
+    Skipped,
+}
+
+impl Status {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Status::Success => "success",
+            Status::Failed => "failed",
+            Status::Skipped => "skipped",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AccountReport {
+    pub email: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub provider: Option<String>,
+    pub status: Status,
+    pub message: Option<String>,
+    pub change: Option<String>,
+    pub balance: Option<String>,
+    pub error: Option<String>,
+    pub error_category: Option<ErrorCategory>,
+    pub attempts: Vec<Attempt>,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub retries_truncated: bool,
+    // 处理该账户的总耗时（含所有尝试和重试等待，不含签到偏移的等待）
+    #[serde(default)]
+    pub duration_ms: u64,
+    // 其中重试等待的时间
+    #[serde(default)]
+    pub backoff_ms: u64,
+    #[serde(skip)]
+    pub timings: Option<StageTimings>,
+}
+
+impl AccountReport {
+    pub fn with_provider(mut self, provider: Option<&str>) -> Self {
+        self.provider = provider.map(str::to_string);
+        self
+    }
+
+    pub fn with_duration(mut self, duration: std::time::Duration) -> Self {
+        self.duration_ms = duration.as_millis() as u64;
+        self
+    }
+
+    pub fn success(email: &str, result: &CheckinResult) -> Self {
+        Self {
+            email: email.to_string(),
+            provider: None,
+            status: Status::Success,
+            message: Some(result.message.clone()),
+            change: result.change.clone(),
+            balance: result.balance.clone(),
+            error: None,
+            error_category: None,
+            attempts: result.attempts.clone(),
+            retries_truncated: false,
+            duration_ms: 0,
+            backoff_ms: service::backoff_ms(&result.attempts),
+            timings: Some(result.timings),
+        }
+    }
+
+    pub fn failed(email: &str, failure: &CheckinFailure) -> Self {
+        Self {
+            email: email.to_string(),
+            provider: None,
+            status: Status::Failed,
+            message: None,
+            change: None,
+            balance: None,
+            error: Some(failure.error.message.clone()),
+            error_category: Some(failure.error.category),
+            attempts: failure.attempts.clone(),
+            retries_truncated: failure.retries_truncated,
+            duration_ms: 0,
+            backoff_ms: service::backoff_ms(&failure.attempts),
+            timings: None,
+        }
+    }
+
+    pub fn skipped(email: &str, reason: &str) -> Self {
+        Self {
+            email: email.to_string(),
+            provider: None,
+            status: Status::Skipped,
+            message: Some(reason.to_string()),
+            change: None,
+            balance: None,
+            error: None,
+            error_category: None,
+            attempts: Vec::new(),
+            retries_truncated: false,
+            duration_ms: 0,
+            backoff_ms: 0,
+            timings: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RunReport {
+    pub started_at: String,
+    pub finished_at: String,
+    pub accounts: Vec<AccountReport>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub notifications: Vec<Delivery>,
+    // 与上次运行相比的退化（新增失败、余额下降），见 diff::summary
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub regressions: Option<String>,
+}
+
+// 整次运行的汇总：各状态的账户数、成功账户的积分变化合计和失败原因
+#[derive(Debug, Clone, Serialize)]
+pub struct RunSummary {
+    pub success: usize,
+    pub failed: usize,
+    pub skipped: usize,
+    pub total_change: i64,
+    pub duration_ms: u64,
+    pub backoff_ms: u64,
+    pub failures: Vec<(String, String)>,
+    // 签到响应中缺少或类型不符的字段（各次尝试去重），提示服务端可能修改了响应格式
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub schema_warnings: Vec<(String, Vec<String>)>,
+}
+
+impl RunSummary {
+    pub fn to_text(&self) -> String {
+        let mut text = format!("签到汇总: 成功 {}，失败 {}，跳过 {}，积分变化 {:+}，账户耗时合计 {}（重试等待 {}）",
+            self.success, self.failed, self.skipped, self.total_change, output::seconds(self.duration_ms), output::seconds(self.backoff_ms));
+        for (account, error) in &self.failures {
+            text.push_str(&format!("\n{}: {}", account, error));
+        }
+        for (account, issues) in &self.schema_warnings {
+            text.push_str(&format!("\n警告: 账户 {} 的签到响应与预期格式不符，服务端可能修改了接口: {}", account, issues.join("；")));
+        }
+        text
+    }
+}
+
+impl TableRow for AccountReport {
+    const HEADERS: &'static [&'static str] = &["account", "status", "change", "balance", "attempts", "duration", "backoff", "message"];
+
+    fn cells(&self) -> Vec<String> {
+        vec![
+            account_key(self.provider.as_deref(), &self.email),
+            self.status.as_str().to_string(),
+            output::cell(self.change.as_deref()),
+            output::cell(self.balance.as_deref()),
+            self.attempts.len().to_string(),
+            output::seconds(self.duration_ms),
+            output::seconds(self.backoff_ms),
+            output::cell(self.error.as_ref().or(self.message.as_ref()).map(String::as_str)),
+        ]
+    }
+}
+
+impl RunReport {
+    pub fn count(&self, status: Status) -> usize {
+        self.accounts.iter().filter(|account| account.status == status).count()
+    }
+
+    // 变化值无法解析为整数（如服务端返回格式变化）时不计入合计
+    pub fn summary(&self) -> RunSummary {
+        RunSummary {
+            success: self.count(Status::Success),
+            failed: self.count(Status::Failed),
+            skipped: self.count(Status::Skipped),
+            total_change: self.accounts.iter()
+                .filter(|account| account.status == Status::Success)
+                .filter_map(|account| account.change.as_deref()?.parse::<i64>().ok())
+                .sum(),
+            duration_ms: self.accounts.iter().map(|account| account.duration_ms).sum(),
+            backoff_ms: self.accounts.iter().map(|account| account.backoff_ms).sum(),
+            failures: self.accounts.iter()
+                .filter(|account| account.status == Status::Failed)
+                .map(|account| (account_key(account.provider.as_deref(), &account.email), account.error.clone().unwrap_or_default()))
+                .collect(),
+            schema_warnings: self.accounts.iter()
+                .filter_map(|account| {
+                    let mut issues: Vec<String> = Vec::new();
+                    for issue in account.attempts.iter().flat_map(|attempt| &attempt.schema_issues) {
+                        if !issues.contains(issue) {
+                            issues.push(issue.clone());
+                        }
+                    }
+                    (!issues.is_empty()).then(|| (account_key(account.provider.as_deref(), &account.email), issues))
+                })
+                .collect(),
+        }
+    }
+
+    // 纯文本版本，每个账户一行
+    pub fn to_text(&self) -> String {
+        let mut text = format!("{} - {}\n成功 {}，失败 {}，跳过 {}\n\n",
+            self.started_at, self.finished_at,
+            self.count(Status::Success), self.count(Status::Failed), self.count(Status::Skipped));
+        for account in &self.accounts {
+            let name = match &account.provider {
+                Some(provider) => format!("{}/{}", provider, account.email),
+                None => account.email.clone(),
+            };
+            let message = account.error.as_ref().or(account.message.as_ref()).map_or("", String::as_str);
+            text.push_str(&format!("{} [{}] 变化 {} 余额 {} 尝试 {} 次 耗时 {} {}\n",
+                name, account.status.as_str(),
+                account.change.as_deref().unwrap_or("-"),
+                account.balance.as_deref().unwrap_or("-"),
+                account.attempts.len(), output::seconds(account.duration_ms),
+                message));
+        }
+        if let Some(regressions) = &self.regressions {
+            text.push_str(&format!("\n{}\n", regressions));
+        }
+        for delivery in &self.notifications {
+            text.push_str(&format!("\n通知 {}: 成功 {}，失败 {}", delivery.channel, delivery.sent, delivery.failed));
+        }
+        text
+    }
+
+    // 适合在手机邮件客户端中查看的精简表格，样式全部内联
+    pub fn to_html(&self) -> String {
+        let cell = "padding:4px 8px;border-bottom:1px solid #ddd;text-align:left";
+        let mut html = format!(
+            "<!DOCTYPE html><html><head><meta charset=\"utf-8\"><meta name=\"viewport\" content=\"width=device-width\"></head>\
+             <body style=\"font-family:sans-serif;font-size:14px\"><p>{} - {}</p>\
+             <table style=\"border-collapse:collapse\"><tr><th style=\"{cell}\">账户</th><th style=\"{cell}\">状态</th>\
+             <th style=\"{cell}\">变化</th><th style=\"{cell}\">余额</th><th style=\"{cell}\">信息</th></tr>",
+            escape(&self.started_at), escape(&self.finished_at));
+        for account in &self.accounts {
+            let (label, color) = match account.status {
+                Status::Success => ("成功", "#2e7d32"),
+                Status::Failed => ("失败", "#c62828"),
+                Status::Skipped => ("跳过", "#757575"),
+            };
+            let name = match &account.provider {
+                Some(provider) => format!("{}/{}", provider, account.email),
+                None => account.email.clone(),
+            };
+            let message = account.error.as_ref().or(account.message.as_ref()).map_or("", String::as_str);
+            html.push_str(&format!(
+                "<tr><td style=\"{cell}\">{}</td><td style=\"{cell};color:{}\">{}</td><td style=\"{cell}\">{}</td>\
+                 <td style=\"{cell}\">{}</td><td style=\"{cell}\">{}</td></tr>",
+                escape(&name), color, label,
+                escape(account.change.as_deref().unwrap_or("-")),
+                escape(account.balance.as_deref().unwrap_or("-")),
+                escape(message)));
+        }
+        html.push_str("</table>");
+        if let Some(regressions) = &self.regressions {
+            html.push_str(&format!("<p style=\"color:#c62828\">{}</p>", escape(regressions).replace('\n', "<br>")));
+        }
+        html.push_str("</body></html>");
+        html
+    }
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
@@ -0,0 +1,161 @@
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeSet,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+};
+
+use crate::{console, logger, perms, state_store::StateStore};
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct RunState {
+    date: String,
+    finished: bool,
+    completed: BTreeSet<String>,
+    // 本次运行的开始时间、需要签到的账户数（含已完成的）和 spread_minutes 时间窗的结束时间，
+    // 供 web status 和 web serve 的 /status 显示进度
+    #[serde(default)]
+    started_at: Option<String>,
+    #[serde(default)]
+    total: Option<usize>,
+    #[serde(default)]
+    ends_at: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Progress {
+    pub date: String,
+    pub started_at: Option<String>,
+    pub completed: usize,
+    pub total: Option<usize>,
+    pub ends_at: Option<String>,
+}
+
+impl Progress {
+    pub fn to_text(&self) -> String {
+        let total = self.total.map_or_else(|| "?".to_string(), |total| total.to_string());
+        let mut text = format!("运行未结束（开始于 {}）：已完成 {}/{} 个账户",
+            self.started_at.as_deref().unwrap_or(&self.date), self.completed, total);
+        if let Some(ends_at) = &self.ends_at {
+            text.push_str(&format!("，分散签到至 {}", ends_at));
+        }
+        text
+    }
+}
+
+// 进行中或中断后未续跑的运行的进度；没有运行记录或最近一次运行已结束时返回 None
+pub fn progress(file_path: impl AsRef<Path>) -> Result<Option<Progress>, Box<dyn std::error::Error>> {
+    let file_path = file_path.as_ref();
+    let state: RunState = match std::fs::read_to_string(file_path) {
+        Ok(content) => serde_json::from_str(&content)
+            .map_err(|e| format!("failed to parse state file {}: {}", file_path.display(), e))?,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e.into()),
+    };
+    Ok((!state.finished).then_some(Progress {
+        date: state.date,
+        started_at: state.started_at,
+        completed: state.completed.len(),
+        total: state.total,
+        ends_at: state.ends_at,
+    }))
+}
+
+// 每个账户已用掉的重试次数保存在 StateStore 中，这里只记录本次运行的进度
+pub struct RunStateStore {
+    file_path: PathBuf,
+    state: Mutex<RunState>,
+    store: Arc<dyn StateStore>,
+}
+
+impl RunStateStore {
+    // 上次运行未正常结束且属于同一天时自动续跑；force_resume 时忽略日期
+    pub fn open(
+        file_path: impl Into<PathBuf>,
+        today: NaiveDate,
+        force_resume: bool,
+        store: Arc<dyn StateStore>,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        let file_path = file_path.into();
+        let today = today.format("%Y-%m-%d").to_string();
+        let previous = match std::fs::read_to_string(&file_path) {
+            Ok(content) => Some(serde_json::from_str::<RunState>(&content)
+                .map_err(|e| format!("failed to parse state file {}: {}", file_path.display(), e))?),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => None,
+            Err(e) => return Err(e.into()),
+        };
+        let mut state = match previous {
+            Some(previous) if !previous.finished && (force_resume || previous.date == today) => {
+                console::progress(&format!("[{}] 继续上次未完成的运行（{}），已完成 {} 个账户",
+                    chrono::Local::now().format("%Y-%m-%d %H:%M:%S"),
+                    previous.date, previous.completed.len()));
+                previous
+            }
+            _ => {
+                for (key, state) in store.all()? {
+                    if state.retries_used > 0 {
+                        store.update(&key, |state| state.retries_used = 0)?;
+                    }
+                }
+                RunState {
+                    date: today,
+                    ..Default::default()
+                }
+            }
+        };
+        state.started_at = Some(chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string());
+        let run_state = Self {
+            file_path,
+            state: Mutex::new(state),
+            store,
+        };
+        run_state.save()?;
+        Ok(run_state)
+    }
+
+    pub fn is_completed(&self, email: &str) -> bool {
+        self.lock().completed.contains(email)
+    }
+
+    pub fn attempts_used(&self, email: &str) -> Result<u32, Box<dyn std::error::Error>> {
+        Ok(self.store.load(email)?.retries_used)
+    }
+
+    pub fn record_attempts(&self, email: &str, attempts: u32) -> Result<(), Box<dyn std::error::Error>> {
+        self.store.update(email, |state| state.retries_used = attempts)
+    }
+
+    pub fn complete(&self, email: &str) -> Result<(), Box<dyn std::error::Error>> {
+        self.store.update(email, |state| state.retries_used = 0)?;
+        self.lock().completed.insert(email.to_string());
+        self.save()
+    }
+
+    pub fn plan(&self, total: usize, ends_at: Option<String>) -> Result<(), Box<dyn std::error::Error>> {
+        {
+            let mut state = self.lock();
+            state.total = Some(total);
+            state.ends_at = ends_at;
+        }
+        self.save()
+    }
+
+    pub fn finish(&self) -> Result<(), Box<dyn std::error::Error>> {
+        self.lock().finished = true;
+        self.save()
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, RunState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn save(&self) -> Result<(), Box<dyn std::error::Error>> {
+        let content = serde_json::to_vec_pretty(&*self.lock())?;
+        let tmp_path = self.file_path.with_extension("tmp");
+        logger::ensure_parent_dir(&self.file_path)?;
+        perms::write_private(&tmp_path, content)?;
+        std::fs::rename(&tmp_path, &self.file_path)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,939 @@
+use chrono::NaiveDate;
+use chrono_tz::Tz;
+use futures::{future, FutureExt, StreamExt};
+use rand::{rngs::StdRng, SeedableRng};
+use serde::Serialize;
+use std::{cell::Cell, collections::{HashMap, HashSet}, panic::AssertUnwindSafe, rc::Rc, sync::Arc};
+use tokio::time::{Duration, Instant};
+use tokio_util::sync::CancellationToken;
+
+use crate::{
+    artifacts::{Har, RunArtifacts},
+    catalog::{ErrorCatalog, Outcome},
+    clock,
+    config::{account_key, Account, AccountJitterConfig, Config, NotifyOn, ProviderConfig, ProviderKind, SharedCookie},
+    console,
+    cookie_age::CookieTracker,
+    cookie_pool,
+    diff,
+    error::{CheckinError, ErrorCategory},
+    events::{Event, EventSender},
+    history::{HistoryEntry, HistoryStore},
+    hook::Hooks,
+    jitter,
+    logger::Logger,
+    mock::{MockMode, MockServer},
+    network::{self, NetworkWait},
+    notifier::{self, Channel, Notification},
+    output::{self, OutputFormat, TableRow},
+    results::{AccountReport, RunReport, Status},
+    run_state::RunStateStore,
+    service::{CheckinFailure, CheckinService, DEFAULT_BASE_URL},
+    sink::{self, ResultSink},
+    state_store::{self, AccountState, AttentionFlag, StateStore},
+    template::MessageVars,
+};
+
+pub fn http_client(config: &Config) -> Result<reqwest::Client, Box<dyn std::error::Error>> {
+    let mut builder = reqwest::Client::builder();
+    if let Some(proxy) = &config.proxy {
+        builder = builder.proxy(reqwest::Proxy::all(proxy)?);
+    }
+    Ok(builder.build()?)
+}
+
+pub async fn build_service(
+    config: &Config,
+    instance: &ProviderConfig,
+    shutdown: &CancellationToken,
+) -> Result<CheckinService, Box<dyn std::error::Error>> {
+    let client = http_client(config)?;
+    let logger = Box::new(config.file_logger());
+    let base_url = instance.base_url.as_deref().unwrap_or(DEFAULT_BASE_URL);
+    let network_wait = match (&config.network_wait, instance.kind) {
+        (Some(wait), ProviderKind::Glados) => network::probe_target(config.proxy.as_deref().unwrap_or(base_url))
+            .map(|target| Arc::new(NetworkWait::new(target,
+                Duration::from_secs(wait.max_wait), Duration::from_secs(wait.initial_delay)))),
+        _ => None,
+    };
+    let service = CheckinService::new(
+        client,
+        logger,
+        config.max_retries,
+        config.retry_delay,
+    )
+    .with_randomized_fingerprint(config.randomize_fingerprint)
+    .with_timezone(config.reset_timezone()?)
+    .with_rate_limiter(config.rate_limiter(instance))
+    .with_backoff(config.backoff_strategy())
+    .with_body_filter(config.body_filter()?)
+    .with_capture_headers(config.capture_headers.clone())
+    .with_error_catalog(ErrorCatalog::new(&config.error_catalog))
+    .with_shutdown(config.cancel_sleep_on_shutdown.then(|| shutdown.clone()))
+    .with_messages(config.messages.clone())
+    .with_network_wait(network_wait);
+
+    match instance.kind {
+        ProviderKind::Glados => Ok(match &instance.base_url {
+            Some(base_url) => service.with_base_url(base_url),
+            None => service,
+        }),
+        ProviderKind::Demo => {
+            let server = MockServer::start(MockMode::Demo).await?;
+            eprintln!("[{}] {} 使用演示服务 {}，不会访问真实接口",
+                chrono::Local::now().format("%Y-%m-%d %H:%M:%S"),
+                instance.label(), server.base_url());
+            Ok(service.with_base_url(server.base_url()))
+        }
+    }
+}
+
+// 返回与上次运行相比的退化，随运行结果输出并作为一条失败通知发送
+fn report_regressions(config: &Config, today: NaiveDate) -> Result<Option<String>, Box<dyn std::error::Error>> {
+    let history = HistoryStore::new(&config.history_file);
+    let Some(previous) = diff::previous_date(&history, today)? else {
+        return Ok(None);
+    };
+    let summary = diff::summary(&diff::compare(&history, previous, today)?);
+    if let Some(summary) = &summary {
+        let log_content = format!("[{}] 提醒: {}",
+            chrono::Local::now().format("%Y-%m-%d %H:%M:%S"), summary);
+        console::progress(&log_content);
+        config.file_logger().log(&log_content)?;
+    }
+    Ok(summary)
+}
+
+// 任一服务实例处于维护时段时返回需要推迟的时长（取最晚结束的窗口）
+pub fn maintenance_remaining(config: &Config) -> Result<Option<Duration>, Box<dyn std::error::Error>> {
+    let now = clock::provider_now(config.reset_timezone()?).time();
+    let mut longest = None;
+    for instance in config.provider_instances() {
+        longest = longest.max(instance.maintenance_remaining(now)?);
+    }
+    Ok(longest.and_then(|remaining| remaining.to_std().ok()))
+}
+
+#[derive(Debug, Serialize)]
+pub struct DryRunRow {
+    pub account: String,
+    pub action: String,
+    pub request: Option<String>,
+    pub left_days: Option<String>,
+    pub error: Option<String>,
+}
+
+impl TableRow for DryRunRow {
+    const HEADERS: &'static [&'static str] = &["account", "action", "request", "left_days", "error"];
+
+    fn cells(&self) -> Vec<String> {
+        vec![
+            self.account.clone(),
+            self.action.clone(),
+            output::cell(self.request.as_deref()),
+            output::cell(self.left_days.as_deref()),
+            self.error.clone().unwrap_or_default(),
+        ]
+    }
+}
+
+// web --dry-run：按正式运行的规则逐个账户决定签到或跳过，构造签到请求（请求头、请求体模板）并写日志，
+// 但不发送签到请求，只用只读的状态接口验证 cookie；不写历史、运行状态和账户状态，不通知、不执行钩子、不输出结果
+pub async fn dry_run(config: &Config) -> Result<(), Box<dyn std::error::Error>> {
+    let tz = config.reset_timezone()?;
+    let checked_in = HistoryStore::new(&config.history_file).checked_in_on(clock::provider_today(tz))?;
+    let store = state_store::open(&config.state_store)?;
+    let shared = shared_cookies(config, config.shared_cookies()?);
+    let logger = config.file_logger();
+    let shutdown = CancellationToken::new();
+    let mut rows = Vec::new();
+    for instance in config.provider_instances() {
+        let maintenance = instance.maintenance_remaining(clock::provider_now(tz).time())?;
+        let service = build_service(config, &instance, &shutdown).await?;
+        for account in instance.accounts()? {
+            let account = match account {
+                Ok(account) => account,
+                Err(e) => {
+                    eprintln!("{}", e);
+                    continue;
+                }
+            };
+            let key = instance.key(&account.email);
+            let skipped = if account.disabled {
+                Some("已停用".to_string())
+            } else if maintenance.is_some() {
+                Some("服务端维护中".to_string())
+            } else if checked_in.contains(&key) {
+                Some("今日已签到".to_string())
+            } else if let Some(others) = shared.get(&key) {
+                Some(format!("与账户 {} 使用了相同的 cookie（shared_cookie: refuse）", others))
+            } else if store.load(&key)?.flag.is_some() {
+                Some("等待人工验证".to_string())
+            } else {
+                None
+            };
+            if let Some(reason) = skipped {
+                rows.push(DryRunRow { account: key, action: format!("skip: {}", reason), request: None, left_days: None, error: None });
+                continue;
+            }
+
+            let cookie = cookie_pool::ordered(&account, &store.load(&key)?).remove(0).1.clone();
+            let account = Account { cookie, ..account };
+            let mut row = DryRunRow { account: key.clone(), action: "checkin".to_string(), request: None, left_days: None, error: None };
+            match service.checkin_request(&account) {
+                Ok(request) => row.request = Some(format!("{} {}", request.method(), request.url())),
+                Err(e) => row.error = Some(account.cookie.redact(&e.to_string())),
+            }
+            if row.error.is_none() {
+                match service.status(&account).await {
+                    Ok(status) => row.left_days = status.left_days,
+                    Err(e) => row.error = Some(e.to_string()),
+                }
+            }
+            let log_content = format!("[{}] 试运行: 账户 {} {}",
+                chrono::Local::now().format("%Y-%m-%d %H:%M:%S"), key,
+                match (&row.request, &row.error) {
+                    (_, Some(error)) => format!("检查失败: {}", error),
+                    (Some(request), None) => format!("将发送 {}（未发送）", request),
+                    (None, None) => String::new(),
+                });
+            if let Err(e) = logger.log(&log_content) {
+                eprintln!("记录日志失败: {}", e);
+            }
+            rows.push(row);
+        }
+    }
+    output::print(&rows, console::result_format())?;
+    let failed = rows.iter().filter(|row| row.error.is_some()).count();
+    if failed > 0 {
+        return Err(format!("{} account(s) failed the dry run", failed).into());
+    }
+    Ok(())
+}
+
+// 多个配置依次试运行，每个配置的结果表格之前打印配置路径
+pub async fn dry_run_many(configs: &[(String, Config)]) -> Result<(), Box<dyn std::error::Error>> {
+    let mut failed = Vec::new();
+    for (path, config) in configs {
+        println!("{}:", path);
+        if let Err(e) = dry_run(config).await {
+            eprintln!("{}: {}", path, e);
+            failed.push(path.as_str());
+        }
+    }
+    if !failed.is_empty() {
+        return Err(format!("dry run failed for {}", failed.join(", ")).into());
+    }
+    Ok(())
+}
+
+// 多个 --config：各配置在同一进程中同时运行，使用各自的日志、通知、历史和运行状态，
+// 结束后输出每个配置和全部配置的汇总；有配置无法运行（加载后的检查、allowed_window 等）时返回错误
+pub async fn execute_many(configs: &[(String, Config)], resume: bool, force: bool, shutdown: &CancellationToken) -> Result<(), Box<dyn std::error::Error>> {
+    for (i, (path, config)) in configs.iter().enumerate() {
+        if let Some((other, _)) = configs[..i].iter()
+            .find(|(_, other)| other.history_file == config.history_file || other.state_file == config.state_file) {
+            return Err(format!("{} and {} use the same history_file or state_file, set different paths in each config", other, path).into());
+        }
+    }
+    let results = futures::future::join_all(configs.iter().map(|(_, config)| async move {
+        ensure_allowed_window(config, force)?;
+        execute(config, resume, shutdown).await
+    })).await;
+
+    let mut accounts = Vec::new();
+    let mut failed = Vec::new();
+    for ((path, _), result) in configs.iter().zip(results) {
+        match result {
+            Ok(report) => {
+                let summary = report.summary();
+                console::progress(&format!("[{}] {}: 成功 {}，失败 {}，跳过 {}",
+                    chrono::Local::now().format("%Y-%m-%d %H:%M:%S"), path, summary.success, summary.failed, summary.skipped));
+                accounts.extend(report.accounts);
+            }
+            Err(e) => {
+                eprintln!("[{}] {}: 运行失败: {}", chrono::Local::now().format("%Y-%m-%d %H:%M:%S"), path, e);
+                failed.push(path.as_str());
+            }
+        }
+    }
+    let total = RunReport { started_at: String::new(), finished_at: String::new(), accounts, notifications: Vec::new(), regressions: None };
+    console::progress(&format!("[{}] 全部 {} 个配置的{}",
+        chrono::Local::now().format("%Y-%m-%d %H:%M:%S"), configs.len(), total.summary().to_text()));
+    if !failed.is_empty() {
+        return Err(format!("{} of {} config(s) failed: {}", failed.len(), configs.len(), failed.join(", ")).into());
+    }
+    Ok(())
+}
+
+// 防止定时任务配置错误在可疑时段签到；force 时只打印警告
+pub fn ensure_allowed_window(config: &Config, force: bool) -> Result<(), Box<dyn std::error::Error>> {
+    let Some(window) = &config.allowed_window else {
+        return Ok(());
+    };
+    let (start, end) = window.bounds()?;
+    let now = clock::provider_now(config.reset_timezone()?).time();
+    if clock::within(now, start, end) {
+        return Ok(());
+    }
+    let message = format!("current time {} ({}) is outside allowed_window {}-{}",
+        now.format("%H:%M"), config.timezone, window.start, window.end);
+    if !force {
+        return Err(format!("{}, use --force to run anyway", message).into());
+    }
+    eprintln!("[{}] 警告: 当前时间不在允许的时段内，已使用 --force 继续运行（{}）",
+        chrono::Local::now().format("%Y-%m-%d %H:%M:%S"), message);
+    Ok(())
+}
+
+pub struct Pipeline {
+    pub provider: Option<String>,
+    pub service: CheckinService,
+    pub history: HistoryStore,
+    pub tz: Tz,
+    pub run_state: Option<RunStateStore>,
+    pub state: Option<Arc<dyn StateStore>>,
+    pub jitter: Option<AccountJitterConfig>,
+    pub spread: Option<Rc<Spread>>,
+    pub started: Instant,
+    pub hooks: Hooks,
+    pub notify_on: NotifyOn,
+    pub failure_threshold: u32,
+}
+
+// spread_minutes：本次待签到的账户按处理顺序均匀分布在时间窗内，第 i 个账户在运行开始 i * 窗口 / 账户数 后签到
+pub struct Spread {
+    window: Duration,
+    accounts: usize,
+    next: Cell<usize>,
+}
+
+impl Spread {
+    pub fn new(window: Duration, accounts: usize) -> Self {
+        Self { window, accounts, next: Cell::new(0) }
+    }
+
+    fn next_offset(&self) -> Duration {
+        let index = self.next.replace(self.next.get() + 1);
+        self.window.mul_f64(index as f64 / self.accounts.max(1) as f64)
+    }
+}
+
+pub async fn execute(config: &Config, resume: bool, shutdown: &CancellationToken) -> Result<RunReport, Box<dyn std::error::Error>> {
+    execute_with_events(config, resume, shutdown, EventSender::default()).await
+}
+
+// 与 execute 相同，运行过程中同时向 events 发送事件（见 events::channel）
+pub async fn execute_with_events(config: &Config, resume: bool, shutdown: &CancellationToken, events: EventSender) -> Result<RunReport, Box<dyn std::error::Error>> {
+    let sinks = sink::build(config)?;
+    let notifiers = notifier::build(config)?;
+    execute_with(config, resume, shutdown, events, notifiers, sinks).await
+}
+
+// 使用给定的通知渠道和结果输出代替配置中的 notifiers/sinks（测试中传入 doubles 中的内存实现）
+pub async fn execute_with(
+    config: &Config,
+    resume: bool,
+    shutdown: &CancellationToken,
+    events: EventSender,
+    notifiers: Rc<Vec<Channel>>,
+    sinks: Vec<Box<dyn ResultSink>>,
+) -> Result<RunReport, Box<dyn std::error::Error>> {
+    let logger = config.file_logger();
+    notifier::resend_queued(&notifiers, &logger).await;
+    // 排查材料只是附带的，目录创建或写入失败时记录后照常签到
+    let artifacts = config.artifacts.as_ref().and_then(|artifacts| match RunArtifacts::create(artifacts) {
+        Ok(artifacts) => Some(artifacts),
+        Err(e) => {
+            warn(&logger, &format!("创建运行材料目录 {} 失败: {}", artifacts.dir, e));
+            None
+        }
+    });
+    let mut report = run(config, resume, shutdown, &notifiers, &events, artifacts.as_ref().map(RunArtifacts::har)).await?;
+    let summary = report.summary();
+    // 运行结束时打印每个账户的结果表格和汇总，汇总同时写入日志；--output 为其他格式时即使没有账户也输出（空列表）
+    let format = console::result_format();
+    if !report.accounts.is_empty() || !matches!(format, OutputFormat::Table) {
+        output::print(&report.accounts, format)?;
+    }
+    let log_content = format!("[{}] {}", chrono::Local::now().format("%Y-%m-%d %H:%M:%S"), summary.to_text());
+    console::progress(&log_content);
+    logger.log(&log_content)?;
+    if config.notify_on == NotifyOn::Summary {
+        notifier::notify(&notifiers, &Notification::summary(&summary), &logger).await;
+    }
+    if let Some(regressions) = &report.regressions {
+        notifier::notify(&notifiers, &Notification::regressions(regressions), &logger).await;
+    }
+    notifier::send_digests(&notifiers, &logger).await;
+    // 汇总邮件中也带上逐账户渠道的投递情况
+    report.notifications = notifier::deliveries(&notifiers);
+    notifier::summarize(&notifiers, &report, &logger).await;
+    report.notifications = notifier::deliveries(&notifiers);
+    if let Some(artifacts) = &artifacts {
+        match artifacts.finish(&report) {
+            Ok(()) => console::progress(&format!("[{}] 本次运行的结果和请求记录保存在 {}",
+                chrono::Local::now().format("%Y-%m-%d %H:%M:%S"), artifacts.dir().display())),
+            Err(e) => warn(&logger, &format!("保存运行材料到 {} 失败: {}", artifacts.dir().display(), e)),
+        }
+    }
+    events.send(Event::RunFinished { report: report.clone() });
+    sink::publish(&sinks, &report).await?;
+    notifier::check_deliveries(&report.notifications, &logger)?;
+    Ok(report)
+}
+
+async fn run(
+    config: &Config,
+    resume: bool,
+    shutdown: &CancellationToken,
+    notifiers: &Rc<Vec<Channel>>,
+    events: &EventSender,
+    har: Option<Rc<Har>>,
+) -> Result<RunReport, Box<dyn std::error::Error>> {
+    let started_at = chrono::Local::now();
+    events.send(Event::RunStarted { started_at: started_at.format("%Y-%m-%d %H:%M:%S").to_string() });
+    let tz = config.reset_timezone()?;
+    let today = clock::provider_today(tz);
+    let history = HistoryStore::new(&config.history_file);
+    let run_started = Instant::now();
+    let run_deadline = config.run_deadline.map(|deadline| run_started + Duration::from_secs(deadline));
+    let store = state_store::open(&config.state_store)?;
+    let mut run_state = Some(RunStateStore::open(&config.state_file, today, resume, store.clone())?);
+    let checked_in = history.checked_in_on(today)?;
+    let AccountScan { completed, pending, shared } = scan_accounts(config, &checked_in, run_state.as_ref())?;
+    let spread = (config.spread_minutes > 0).then(|| Rc::new(Spread::new(Duration::from_secs(config.spread_minutes * 60), pending)));
+    if let Some(run_state) = &run_state {
+        let ends_at = spread.as_ref().map(|_| {
+            (started_at + chrono::Duration::minutes(config.spread_minutes as i64)).format("%Y-%m-%d %H:%M:%S").to_string()
+        });
+        if let Some(ends_at) = &ends_at {
+            console::progress(&format!("[{}] {} 个账户将分散在 {} 分钟内签到，预计 {} 结束",
+                chrono::Local::now().format("%Y-%m-%d %H:%M:%S"), pending, config.spread_minutes, ends_at));
+        }
+        run_state.plan(completed + pending, ends_at)?;
+    }
+    let shared = shared_cookies(config, shared);
+    let cookie_tracker = config.cookie_reminder.as_ref().map(|reminder| {
+        CookieTracker::new(store.clone(), reminder.lifetime_days, reminder.remind_before_days)
+    });
+    let mut shuffler = config.shuffle_accounts.then(|| {
+        let seed = config.shuffle_seed.unwrap_or_else(|| {
+            let seed = rand::random();
+            console::progress(&format!("[{}] 账户顺序随机种子: {}（设置 shuffle_seed 可复现）",
+                chrono::Local::now().format("%Y-%m-%d %H:%M:%S"), seed));
+            seed
+        });
+        StdRng::seed_from_u64(seed)
+    });
+
+    let mut reports = Vec::new();
+    let mut history = Some(history);
+    for instance in config.provider_instances() {
+        if shutdown.is_cancelled() {
+            break;
+        }
+        if let Some(remaining) = instance.maintenance_remaining(clock::provider_now(tz).time())? {
+            console::progress(&format!("[{}] {} 处于服务端维护时段，跳过本次签到（约 {} 分钟后结束）",
+                chrono::Local::now().format("%Y-%m-%d %H:%M:%S"),
+                instance.label(), remaining.num_minutes() + 1));
+            for account in instance.accounts()?.flatten().filter(|account| !account.disabled) {
+                reports.push(emit_result(events, AccountReport::skipped(&account.email, "服务端维护中").with_provider(instance.name.as_deref())));
+            }
+            continue;
+        }
+        let service = build_service(config, &instance, shutdown).await?
+            .with_notifiers(notifiers.clone())
+            .with_events(events.clone())
+            .with_har(har.clone())
+            .with_deadlines(config.account_deadline.map(Duration::from_secs), run_deadline);
+        if let Some(preflight) = config.preflight.as_ref().filter(|_| instance.kind == ProviderKind::Glados) {
+            // 服务端不可达时每个账户记一次失败，不逐个请求、重试直至超时；不计入连续失败次数，也不逐账户通知
+            if let Err(e) = service.preflight(Duration::from_secs(preflight.timeout)).await {
+                let log_content = format!("[{}] {} 服务端不可达，本次不签到该实例的账户: {}",
+                    chrono::Local::now().format("%Y-%m-%d %H:%M:%S"), instance.label(), e);
+                eprintln!("{}", log_content);
+                if let Err(log_err) = service.logger.log(&log_content) {
+                    eprintln!("记录日志失败: {}", log_err);
+                }
+                let failure = CheckinFailure::new(CheckinError::new(e.category, format!("服务端不可达: {}", e)), Vec::new());
+                for account in instance.accounts()?.flatten().filter(|account| !account.disabled) {
+                    let report = if checked_in.contains(&instance.key(&account.email)) {
+                        AccountReport::skipped(&account.email, "今日已签到")
+                    } else {
+                        AccountReport::failed(&account.email, &failure)
+                    };
+                    reports.push(emit_result(events, report.with_provider(instance.name.as_deref())));
+                }
+                continue;
+            }
+        }
+        let pipeline = Pipeline {
+            provider: instance.name.clone(),
+            service,
+            history: history.take().unwrap_or_else(|| HistoryStore::new(&config.history_file)),
+            tz,
+            run_state: run_state.take(),
+            state: Some(store.clone()),
+            jitter: config.account_jitter.clone(),
+            spread: spread.clone(),
+            started: run_started,
+            hooks: Hooks::new(config),
+            notify_on: config.notify_on,
+            failure_threshold: config.failure_threshold,
+        };
+
+        let accounts = match &mut shuffler {
+            Some(rng) => instance.shuffled_accounts(rng)?,
+            None => instance.accounts()?,
+        };
+        // cookie 过期提醒对停用和今日已签到的账户同样发出
+        let accounts = futures::stream::iter(accounts).then(|account| {
+            let (pipeline, cookie_tracker, instance) = (&pipeline, &cookie_tracker, &instance);
+            async move {
+                if let (Some(tracker), Ok(account)) = (cookie_tracker, &account) {
+                    let key = instance.key(&account.email);
+                    match tracker.check(&key, account, today) {
+                        Ok(Some(reminder)) => pipeline.remind(&key, &account.email, &reminder).await,
+                        Ok(None) => {}
+                        Err(e) => eprintln!("读取账户状态失败: {}", e),
+                    }
+                }
+                account
+            }
+        }).filter_map(|account| future::ready(match account {
+            Ok(account) if account.disabled => {
+                reports.push(emit_result(events, AccountReport::skipped(&account.email, "已停用").with_provider(instance.name.as_deref())));
+                None
+            }
+            Ok(account) if checked_in.contains(&instance.key(&account.email)) => {
+                console::progress(&format!("[{}] 账户 {} 今日已签到，跳过",
+                    chrono::Local::now().format("%Y-%m-%d %H:%M:%S"),
+                    instance.key(&account.email)));
+                reports.push(emit_result(events, AccountReport::skipped(&account.email, "今日已签到").with_provider(instance.name.as_deref())));
+                None
+            }
+            Ok(account) if shared.contains_key(&instance.key(&account.email)) => {
+                let others = &shared[&instance.key(&account.email)];
+                let error = CheckinError::new(ErrorCategory::InvalidCookie,
+                    format!("与账户 {} 使用了相同的 cookie，未签到（shared_cookie: refuse）", others));
+                reports.push(emit_result(events, AccountReport::failed(&account.email, &CheckinFailure::new(error, Vec::new()))
+                    .with_provider(instance.name.as_deref())));
+                None
+            }
+            Ok(account) if pipeline.is_flagged(&instance.key(&account.email)) => {
+                console::progress(&format!("[{}] 账户 {} 等待人工验证，跳过（处理后执行 web account clear-flag {}）",
+                    chrono::Local::now().format("%Y-%m-%d %H:%M:%S"),
+                    instance.key(&account.email), instance.key(&account.email)));
+                reports.push(emit_result(events, AccountReport::skipped(&account.email, "等待人工验证").with_provider(instance.name.as_deref())));
+                None
+            }
+            Ok(account) if pipeline.run_state.as_ref().is_some_and(|state| state.is_completed(&instance.key(&account.email))) => {
+                reports.push(emit_result(events, AccountReport::skipped(&account.email, "上次运行已处理").with_provider(instance.name.as_deref())));
+                None
+            }
+            Ok(account) => Some(account),
+            Err(e) => {
+                eprintln!("{}", e);
+                None
+            }
+        }));
+
+        let accounts = accounts.take_while(|_| future::ready(!shutdown.is_cancelled()));
+        let processed: Vec<AccountReport> = accounts
+            .map(|account| pipeline.run_account(account))
+            .buffer_unordered(config.concurrency_limit())
+            .collect()
+            .await;
+        reports.extend(processed);
+
+        history = Some(pipeline.history);
+        run_state = pipeline.run_state;
+    }
+
+    let mut regressions = None;
+    if shutdown.is_cancelled() {
+        console::progress(&format!("[{}] 运行已中断，已处理 {} 个账户，下次运行将继续",
+            chrono::Local::now().format("%Y-%m-%d %H:%M:%S"),
+            reports.len()));
+    } else {
+        if let Some(run_state) = &run_state {
+            run_state.finish()?;
+        }
+        match report_regressions(config, today) {
+            Ok(summary) => regressions = summary,
+            Err(e) => eprintln!("比较运行结果失败: {}", e),
+        }
+    }
+
+    Ok(RunReport {
+        started_at: started_at.format("%Y-%m-%d %H:%M:%S").to_string(),
+        finished_at: chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string(),
+        accounts: reports,
+        notifications: Vec::new(),
+        regressions,
+    })
+}
+
+// 本次运行中已完成（含续跑前）和尚未签到的账户数（停用和今日已签到的账户不计），以及共用 cookie 的账户
+struct AccountScan {
+    completed: usize,
+    pending: usize,
+    shared: Vec<Vec<String>>,
+}
+
+// 只读取一遍全部账户，按 cookie 摘要找出共用 cookie 的账户
+fn scan_accounts(config: &Config, checked_in: &HashSet<String>, run_state: Option<&RunStateStore>) -> Result<AccountScan, Box<dyn std::error::Error>> {
+    let (mut completed, mut pending) = (0, 0);
+    let mut owners = cookie_pool::Owners::default();
+    for instance in config.provider_instances() {
+        for account in instance.accounts()? {
+            let account = account?;
+            if account.disabled {
+                continue;
+            }
+            let key = instance.key(&account.email);
+            owners.add(&key, &account);
+            if run_state.is_some_and(|state| state.is_completed(&key)) {
+                completed += 1;
+            } else if !checked_in.contains(&key) {
+                pending += 1;
+            }
+        }
+    }
+    Ok(AccountScan { completed, pending, shared: owners.shared() })
+}
+
+// 复制粘贴时容易把同一个 cookie 填给多个账户：每次运行都记录警告；shared_cookie 为 refuse 时
+// 返回这些账户（账户键 -> 与之共用 cookie 的其他账户），本次不为它们签到
+fn shared_cookies(config: &Config, shared: Vec<Vec<String>>) -> HashMap<String, String> {
+    let mut refused = HashMap::new();
+    for accounts in shared {
+        let refuse = config.shared_cookie == SharedCookie::Refuse;
+        let log_content = format!("[{}] 警告: 账户 {} 使用了相同的 cookie，实际签到的是同一个账户{}",
+            chrono::Local::now().format("%Y-%m-%d %H:%M:%S"), accounts.join("、"),
+            if refuse { "，本次不为这些账户签到" } else { "（设置 shared_cookie: refuse 可拒绝签到）" });
+        eprintln!("{}", log_content);
+        if let Err(e) = config.file_logger().log(&log_content) {
+            eprintln!("记录日志失败: {}", e);
+        }
+        if refuse {
+            for key in &accounts {
+                let others: Vec<&str> = accounts.iter().filter(|other| *other != key).map(String::as_str).collect();
+                refused.insert(key.clone(), others.join("、"));
+            }
+        }
+    }
+    refused
+}
+
+// 不影响签到结果的问题：输出到标准错误并写入日志
+fn warn(logger: &impl Logger, message: &str) {
+    let log_content = format!("[{}] {}", chrono::Local::now().format("%Y-%m-%d %H:%M:%S"), message);
+    eprintln!("{}", log_content);
+    if let Err(log_err) = logger.log(&log_content) {
+        eprintln!("记录日志失败: {}", log_err);
+    }
+}
+
+// 在 run_account 之外确定结果（跳过、服务端不可达）的账户同样发送结果事件
+fn emit_result(events: &EventSender, report: AccountReport) -> AccountReport {
+    events.send(Event::Result { report: report.clone() });
+    report
+}
+
+impl Pipeline {
+    // 每个账户处理完成后推送一次通知并执行钩子（备用 cookie 的重试不单独通知）
+    pub async fn run_account(&self, account: Account) -> AccountReport {
+        let report = self.checkin_account(account.clone()).await;
+        if let Some(notification) = Notification::from_report(&report, self.service.messages()).filter(|_| self.should_notify(&report)) {
+            self.service.notify(&notification).await;
+        }
+        self.hooks.run(&account, &report, self.service.logger.as_ref()).await;
+        self.service.events().send(Event::Result { report: report.clone() });
+        report
+    }
+
+    // 单个账户处理中的 panic 只影响该账户：记录为该账户的失败，其他账户照常进行
+    async fn checkin_account(&self, account: Account) -> AccountReport {
+        let email = account.email.clone();
+        let started = Instant::now();
+        match AssertUnwindSafe(self.process(account)).catch_unwind().await {
+            Ok(report) => report,
+            Err(panic) => {
+                let message = panic.downcast_ref::<&str>().map(|message| message.to_string())
+                    .or_else(|| panic.downcast_ref::<String>().cloned())
+                    .unwrap_or_else(|| "unknown panic".to_string());
+                let key = account_key(self.provider.as_deref(), &email);
+                if let Some(state) = &self.run_state {
+                    if let Err(e) = state.complete(&key) {
+                        eprintln!("保存运行状态失败: {}", e);
+                    }
+                }
+                let failure = CheckinFailure::new(
+                    CheckinError::new(ErrorCategory::Internal, format!("内部错误: {}", message)),
+                    Vec::new(),
+                );
+                self.fail(&email, &key, failure, started.elapsed()).await
+            }
+        }
+    }
+
+    async fn process(&self, account: Account) -> AccountReport {
+        let provider = self.provider.as_deref();
+        let key = account_key(provider, &account.email);
+        if !self.wait_offset(&key).await {
+            return AccountReport::skipped(&account.email, "运行已取消").with_provider(provider);
+        }
+        let started = Instant::now();
+        let attempts_used = match self.run_state.as_ref().map(|state| state.attempts_used(&key)) {
+            Some(Ok(attempts)) => attempts,
+            Some(Err(e)) => {
+                eprintln!("读取账户状态失败: {}", e);
+                0
+            }
+            None => 0,
+        };
+        let cookies = match self.state.as_ref().map(|store| store.load(&key)) {
+            Some(Ok(state)) => cookie_pool::ordered(&account, &state),
+            Some(Err(e)) => {
+                eprintln!("读取账户状态失败: {}", e);
+                cookie_pool::all(&account)
+            }
+            None => cookie_pool::all(&account),
+        };
+        let mut result = None;
+        for (index, (label, cookie)) in cookies.iter().enumerate() {
+            let candidate = Account {
+                cookie: (*cookie).clone(),
+                ..account.clone()
+            };
+            let outcome = self.service.checkin(&candidate, attempts_used, |attempts| {
+                if let Some(state) = &self.run_state {
+                    if let Err(e) = state.record_attempts(&key, attempts) {
+                        eprintln!("保存运行状态失败: {}", e);
+                    }
+                }
+            }).await;
+            let rejected = matches!(&outcome, Err(failure) if failure.error.category == ErrorCategory::InvalidCookie);
+            if outcome.is_ok() || rejected {
+                self.update_state(&key, |state| cookie_pool::record(state, &account, cookie, !rejected));
+            }
+            if rejected && index + 1 < cookies.len() {
+                console::progress(&format!("[{}] 账户 {} 的 cookie {} 已失效，改用下一个 cookie",
+                    chrono::Local::now().format("%Y-%m-%d %H:%M:%S"), key, label));
+                continue;
+            }
+            result = Some(outcome);
+            break;
+        }
+        let result = result.expect("account has at least one cookie");
+        if matches!(&result, Err(failure) if failure.error.category == ErrorCategory::Cancelled) {
+            return AccountReport::skipped(&account.email, "运行已取消").with_provider(provider);
+        }
+        if let Some(state) = &self.run_state {
+            if let Err(e) = state.complete(&key) {
+                eprintln!("保存运行状态失败: {}", e);
+            }
+        }
+
+        match result {
+            Ok(result) => {
+                self.update_state(&key, |state| {
+                    state.last_success = Some(chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string());
+                    state.failure_streak = 0;
+                });
+                let duration = started.elapsed();
+                let entry = HistoryEntry::from_result(provider, &account.email, &result, duration, self.tz);
+                if let Err(e) = self.history.append(&entry) {
+                    eprintln!("记录历史失败: {}", e);
+                }
+                AccountReport::success(&account.email, &result).with_provider(provider).with_duration(duration)
+            }
+            Err(failure) => self.fail(&account.email, &key, failure, started.elapsed()).await,
+        }
+    }
+
+    async fn fail(&self, email: &str, key: &str, failure: CheckinFailure, duration: Duration) -> AccountReport {
+        let provider = self.provider.as_deref();
+        let message = failure.to_string();
+        let error_log = format!("[{}] {}",
+            chrono::Local::now().format("%Y-%m-%d %H:%M:%S"),
+            MessageVars {
+                account: key,
+                email,
+                provider,
+                status: Status::Failed.as_str(),
+                message: &message,
+                change: None,
+                balance: None,
+            }.render(&self.service.messages().failure_log));
+        eprintln!("{}", error_log);
+        if let Err(log_err) = self.service.logger.log(&error_log) {
+            eprintln!("记录日志失败: {}", log_err);
+        }
+        self.update_state(key, |state| state.failure_streak += 1);
+        match failure.error.classification {
+            Some(classification) if classification.outcome == Outcome::VerificationRequired => {
+                self.park(key, email, &failure.error.message, classification.policy.notify).await;
+            }
+            Some(classification) if classification.policy.notify => {
+                self.remind(key, email, &classification.outcome.describe(key)).await;
+            }
+            _ => {}
+        }
+        let entry = HistoryEntry::from_failure(provider, email, &failure, duration, self.tz);
+        if let Err(e) = self.history.append(&entry) {
+            eprintln!("记录历史失败: {}", e);
+        }
+        AccountReport::failed(email, &failure).with_provider(provider).with_duration(duration)
+    }
+
+    // 等到该账户的签到偏移时间（spread_minutes 分到的时刻加上 account_jitter 的偏移）；运行被取消时返回 false
+    async fn wait_offset(&self, key: &str) -> bool {
+        let mut offset = self.spread.as_ref().map_or(Duration::ZERO, |spread| spread.next_offset());
+        if let (Some(jitter), Some(store)) = (&self.jitter, &self.state) {
+            let today = clock::provider_today(self.tz);
+            match store.update(key, |state| jitter::advance(state, jitter, today, &mut rand::thread_rng())) {
+                Ok(secs) => offset += Duration::from_secs(secs),
+                Err(e) => eprintln!("保存账户状态失败: {}", e),
+            }
+        }
+        let remaining = (self.started + offset).saturating_duration_since(Instant::now());
+        if remaining.is_zero() {
+            return true;
+        }
+        console::progress(&format!("[{}] 账户 {} 将在 {} 秒后签到",
+            chrono::Local::now().format("%Y-%m-%d %H:%M:%S"), key, remaining.as_secs()));
+        self.service.pause(remaining).await.is_ok()
+    }
+
+    // notify_on 为 summary 时不逐账户通知，为 failure 时不通知成功；失败通知在连续失败次数
+    // （failure_streak，已含本次）达到 failure_threshold 后才发送，偶发失败只记录日志
+    fn should_notify(&self, report: &AccountReport) -> bool {
+        match (self.notify_on, report.status) {
+            (NotifyOn::Summary, _) | (NotifyOn::Failure, Status::Success) => false,
+            (_, Status::Failed) if self.failure_threshold > 1 => {
+                let Some(store) = &self.state else {
+                    return true;
+                };
+                match store.load(&account_key(self.provider.as_deref(), &report.email)) {
+                    Ok(state) => state.failure_streak >= self.failure_threshold,
+                    Err(e) => {
+                        eprintln!("读取账户状态失败: {}", e);
+                        true
+                    }
+                }
+            }
+            _ => true,
+        }
+    }
+
+    fn is_flagged(&self, key: &str) -> bool {
+        let Some(store) = &self.state else {
+            return false;
+        };
+        match store.load(key) {
+            Ok(state) => state.flag.is_some(),
+            Err(e) => {
+                eprintln!("读取账户状态失败: {}", e);
+                false
+            }
+        }
+    }
+
+    fn update_state(&self, key: &str, f: impl FnOnce(&mut AccountState)) {
+        if let Some(store) = &self.state {
+            if let Err(e) = store.update(key, f) {
+                eprintln!("保存账户状态失败: {}", e);
+            }
+        }
+    }
+
+    // 输出并写入日志，同时通过逐账户通知渠道发送（不受 notify_on 限制）
+    async fn remind(&self, key: &str, email: &str, reminder: &str) {
+        let log_content = format!("[{}] 提醒: {}",
+            chrono::Local::now().format("%Y-%m-%d %H:%M:%S"), reminder);
+        console::progress(&log_content);
+        if let Err(e) = self.service.logger.log(&log_content) {
+            eprintln!("记录日志失败: {}", e);
+        }
+        self.service.notify(&Notification::reminder(key, email, self.provider.as_deref(), reminder)).await;
+    }
+
+    // 标记后后续运行会跳过该账户；已有标记时（如多个配置或进程同时签到该账户）保留原标记，不重复提醒
+    async fn park(&self, key: &str, email: &str, reason: &str, notify: bool) {
+        let Some(store) = &self.state else {
+            return;
+        };
+        let flag = AttentionFlag {
+            flagged_at: chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string(),
+            reason: reason.to_string(),
+        };
+        let parked = store.update(key, |state| {
+            let parked = state.flag.is_none();
+            state.flag.get_or_insert(flag);
+            parked
+        });
+        let parked = match parked {
+            Ok(parked) => parked,
+            Err(e) => {
+                eprintln!("保存待处理账户失败: {}", e);
+                return;
+            }
+        };
+        if notify && parked {
+            self.remind(key, email, &format!("{}，请在浏览器中登录完成验证并更新 cookie，然后执行 web account clear-flag {} 恢复自动签到",
+                Outcome::VerificationRequired.describe(key), key)).await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{doubles::MemoryNotifier, logger::FileLogger, state_store::FileStateStore};
+
+    fn pipeline(dir: &std::path::Path, notifier: &MemoryNotifier) -> Pipeline {
+        let logger = Box::new(FileLogger::new(dir.join("checkin.log")));
+        Pipeline {
+            provider: None,
+            service: CheckinService::new(reqwest::Client::new(), logger, 1, 0)
+                .with_notifiers(Rc::new(vec![notifier.channel(true)])),
+            history: HistoryStore::new(dir.join("history.jsonl")),
+            tz: chrono_tz::Asia::Shanghai,
+            run_state: None,
+            state: Some(Arc::new(FileStateStore::open(dir.join("state.json")).unwrap())),
+            jitter: None,
+            spread: None,
+            started: Instant::now(),
+            hooks: Hooks::default(),
+            notify_on: NotifyOn::default(),
+            failure_threshold: 1,
+        }
+    }
+
+    #[tokio::test]
+    async fn parked_accounts_are_reminded_once() {
+        let dir = std::env::temp_dir().join(format!("web-park-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let notifier = MemoryNotifier::new("test");
+        let pipeline = pipeline(&dir, &notifier);
+
+        pipeline.park("a@example.com", "a@example.com", "需要验证码", true).await;
+        pipeline.park("a@example.com", "a@example.com", "仍需要验证码", true).await;
+        let notifications = notifier.notifications();
+        assert_eq!(notifications.len(), 1);
+        assert_eq!(notifications[0].email, "a@example.com");
+        assert!(notifications[0].text().contains("web account clear-flag a@example.com"), "{}", notifications[0].text());
+        let flag = pipeline.state.as_ref().unwrap().load("a@example.com").unwrap().flag.unwrap();
+        assert_eq!(flag.reason, "需要验证码");
+        assert!(pipeline.is_flagged("a@example.com"));
+
+        // 不提醒的策略只标记
+        pipeline.park("b@example.com", "b@example.com", "需要验证码", false).await;
+        assert!(pipeline.is_flagged("b@example.com"));
+        assert_eq!(notifier.notifications().len(), 1);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,44 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+#[derive(Clone, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct SecretString(String);
+
+impl SecretString {
+    pub fn new(value: impl Into<String>) -> Self {
+        Self(value.into())
+    }
+
+    pub fn expose(&self) -> &str {
+        &self.0
+    }
+
+    // 将文本中出现的整段 cookie 以及其中每个 name=value 的值替换掉
+    pub fn redact(&self, text: &str) -> String {
+        let mut redacted = text.to_string();
+        if self.0.is_empty() {
+            return redacted;
+        }
+        redacted = redacted.replace(self.0.as_str(), "[REDACTED]");
+        for part in self.0.split(';') {
+            let value = part.split_once('=').map_or(part, |(_, value)| value).trim();
+            if value.len() >= 6 {
+                redacted = redacted.replace(value, "[REDACTED]");
+            }
+        }
+        redacted
+    }
+}
+
+impl fmt::Debug for SecretString {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("SecretString(***)")
+    }
+}
+
+impl From<String> for SecretString {
+    fn from(value: String) -> Self {
+        Self(value)
+    }
+}
@@ -0,0 +1,274 @@
+use serde::{Deserialize, Serialize};
+use std::{cell::RefCell, rc::Rc};
+use tokio::{
+    net::{TcpListener, TcpStream},
+    sync::Notify,
+    task::LocalSet,
+    time::Duration,
+};
+use tokio_util::sync::CancellationToken;
+
+use crate::{
+    cli::ServeArgs,
+    config::Config,
+    daemon_lock::DaemonLock,
+    http::{self, Request, Response},
+    results::RunReport,
+    run_state::{self, Progress},
+    runner, shutdown, systemd, trigger,
+};
+
+struct ServerState {
+    config_path: String,
+    profile: Option<String>,
+    state_file: String,
+    running: RefCell<bool>,
+    last_report: RefCell<Option<RunReport>>,
+    last_error: RefCell<Option<String>>,
+    idle: Notify,
+    shutdown: CancellationToken,
+}
+
+// GET /status 的响应；web status 通过 trigger.socket 查询时按此解析
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ServerStatus {
+    pub running: bool,
+    pub progress: Option<Progress>,
+    pub last_report: Option<RunReport>,
+    pub last_error: Option<String>,
+}
+
+impl ServerStatus {
+    pub fn to_text(&self) -> String {
+        let mut text = if self.running { "正在签到".to_string() } else { "空闲".to_string() };
+        if let Some(progress) = &self.progress {
+            text.push_str(&format!("\n{}", progress.to_text()));
+        }
+        if let Some(report) = &self.last_report {
+            text.push_str(&format!("\n上次运行结束于 {}，{}", report.finished_at, report.summary().to_text()));
+        }
+        if let Some(error) = &self.last_error {
+            text.push_str(&format!("\n上次运行失败: {}", error));
+        }
+        text
+    }
+}
+
+pub async fn run(config_path: &str, profile: Option<String>, args: &ServeArgs) -> Result<(), Box<dyn std::error::Error>> {
+    let config = Config::load_from_file(config_path, profile.as_deref())?;
+    let listen = args.listen.clone().unwrap_or_else(|| config.trigger.listen.clone());
+    let _lock = DaemonLock::acquire(&config.trigger.lock_file, args.takeover, Duration::from_secs(args.takeover_timeout)).await?;
+    // 配置了 trigger.socket 时只在套接字上提供服务，除非同时用 --listen 指定了 TCP 地址
+    let listener = match (&args.listen, &config.trigger.socket) {
+        (None, Some(_)) => None,
+        _ => Some(TcpListener::bind(&listen).await?),
+    };
+    #[cfg(unix)]
+    let socket = config.trigger.socket.as_deref().map(bind_socket).transpose()?;
+    let shutdown = shutdown::install();
+    if listener.is_some() && config.trigger.secret.is_none() {
+        eprintln!("警告: 未配置 trigger.secret，任何能访问 {} 的人都可以触发签到", listen);
+    }
+    if listener.is_some() {
+        println!("[{}] 触发服务已启动: http://{}",
+            chrono::Local::now().format("%Y-%m-%d %H:%M:%S"), listen);
+    }
+    if let Some(path) = &config.trigger.socket {
+        println!("[{}] 触发服务已启动: unix:{}",
+            chrono::Local::now().format("%Y-%m-%d %H:%M:%S"), path);
+    }
+    systemd::notify("READY=1");
+    systemd::spawn_watchdog(shutdown.clone());
+
+    let state = Rc::new(ServerState {
+        config_path: config_path.to_string(),
+        profile,
+        state_file: config.state_file.clone(),
+        running: RefCell::new(false),
+        last_report: RefCell::new(None),
+        last_error: RefCell::new(None),
+        idle: Notify::new(),
+        shutdown: shutdown.clone(),
+    });
+
+    LocalSet::new()
+        .run_until(async move {
+            #[cfg(unix)]
+            let socket = socket.map(|(listener, path)| tokio::task::spawn_local(serve_socket(listener, path, state.clone())));
+            loop {
+                let stream = tokio::select! {
+                    accepted = accept(&listener) => accepted?,
+                    _ = shutdown.cancelled() => break,
+                };
+                let state = state.clone();
+                tokio::task::spawn_local(async move {
+                    let _ = http::serve_connection(stream, |request| handle(state.clone(), request)).await;
+                });
+            }
+
+            // 不再接受新的触发；等进行中的运行完成后函数返回，锁随之释放，接手的新进程才会开始监听
+            systemd::notify("STOPPING=1");
+            drop(listener);
+            #[cfg(unix)]
+            if let Some(socket) = socket {
+                let _ = socket.await;
+            }
+            let idle = state.idle.notified();
+            if *state.running.borrow() {
+                println!("[{}] 等待进行中的运行完成后退出",
+                    chrono::Local::now().format("%Y-%m-%d %H:%M:%S"));
+                idle.await;
+            }
+            println!("[{}] 触发服务已停止", chrono::Local::now().format("%Y-%m-%d %H:%M:%S"));
+            Ok(())
+        })
+        .await
+}
+
+async fn handle(state: Rc<ServerState>, request: Request) -> Response {
+    match (request.method.as_str(), request.path.as_str()) {
+        ("GET", "/status") => status(&state),
+        ("POST", "/trigger") => trigger_run(state, &request),
+        (_, "/status") | (_, "/trigger") => Response::text(405, "method not allowed"),
+        _ => Response::text(404, "not found"),
+    }
+}
+
+// 只监听套接字时 TCP 一侧永远不会有连接
+async fn accept(listener: &Option<TcpListener>) -> std::io::Result<TcpStream> {
+    match listener {
+        Some(listener) => listener.accept().await.map(|(stream, _)| stream),
+        None => std::future::pending().await,
+    }
+}
+
+// 持有锁文件时残留的套接字文件只可能来自已退出的进程，直接删除后重新创建；创建后改为 0600，只有同一用户可以连接
+#[cfg(unix)]
+fn bind_socket(path: &str) -> Result<(tokio::net::UnixListener, String), Box<dyn std::error::Error>> {
+    match std::fs::remove_file(path) {
+        Ok(()) => {}
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+        Err(e) => return Err(format!("failed to remove stale socket {}: {}", path, e).into()),
+    }
+    let listener = tokio::net::UnixListener::bind(path)
+        .map_err(|e| format!("failed to bind trigger.socket {}: {}", path, e))?;
+    crate::perms::restrict(path)?;
+    Ok((listener, path.to_string()))
+}
+
+// 与 TCP 相同的接口；停止时删除套接字文件
+#[cfg(unix)]
+async fn serve_socket(listener: tokio::net::UnixListener, path: String, state: Rc<ServerState>) {
+    loop {
+        let stream = tokio::select! {
+            accepted = listener.accept() => match accepted {
+                Ok((stream, _)) => stream,
+                Err(e) => {
+                    eprintln!("[{}] 接受 {} 上的连接失败，停止在该套接字上提供服务: {}",
+                        chrono::Local::now().format("%Y-%m-%d %H:%M:%S"), path, e);
+                    break;
+                }
+            },
+            _ = state.shutdown.cancelled() => break,
+        };
+        let state = state.clone();
+        tokio::task::spawn_local(async move {
+            let _ = http::serve_connection(stream, |request| handle(state.clone(), request)).await;
+        });
+    }
+    drop(listener);
+    let _ = std::fs::remove_file(&path);
+}
+
+// web status：serve 在 trigger.socket 上运行时查询其状态；未配置套接字、套接字不存在或无人监听（进程已退出）时返回 None
+pub async fn socket_status(config: &Config) -> Result<Option<ServerStatus>, Box<dyn std::error::Error>> {
+    #[cfg(unix)]
+    if let Some(path) = &config.trigger.socket {
+        let stream = match tokio::net::UnixStream::connect(path).await {
+            Ok(stream) => stream,
+            Err(e) if matches!(e.kind(), std::io::ErrorKind::NotFound | std::io::ErrorKind::ConnectionRefused) => return Ok(None),
+            Err(e) => return Err(format!("failed to connect to {}: {}", path, e).into()),
+        };
+        let (status, body) = http::get(stream, "/status").await
+            .map_err(|e| format!("failed to query {}: {}", path, e))?;
+        if status != 200 {
+            return Err(format!("{} returned {}: {}", path, status, String::from_utf8_lossy(&body)).into());
+        }
+        return Ok(Some(serde_json::from_slice(&body)?));
+    }
+    #[cfg(not(unix))]
+    let _ = config;
+    Ok(None)
+}
+
+fn status(state: &ServerState) -> Response {
+    let body = ServerStatus {
+        running: *state.running.borrow(),
+        progress: run_state::progress(&state.state_file).unwrap_or_else(|e| {
+            eprintln!("读取运行状态失败: {}", e);
+            None
+        }),
+        last_report: state.last_report.borrow().clone(),
+        last_error: state.last_error.borrow().clone(),
+    };
+    match serde_json::to_string(&body) {
+        Ok(body) => Response::json(200, body),
+        Err(e) => Response::text(500, format!("failed to serialize status: {}", e)),
+    }
+}
+
+fn trigger_run(state: Rc<ServerState>, request: &Request) -> Response {
+    let config = match Config::load_from_file(&state.config_path, state.profile.as_deref()) {
+        Ok(config) => config,
+        Err(e) => return Response::text(500, format!("failed to load config: {}", e)),
+    };
+    if let Some(secret) = &config.trigger.secret {
+        if let Err(e) = trigger::verify_signature(request, secret, config.trigger.signature_tolerance) {
+            eprintln!("[{}] 拒绝触发请求: {}", chrono::Local::now().format("%Y-%m-%d %H:%M:%S"), e);
+            return Response::text(401, "invalid signature");
+        }
+    }
+    if let Err(e) = runner::ensure_allowed_window(&config, false) {
+        eprintln!("[{}] 拒绝触发请求: {}", chrono::Local::now().format("%Y-%m-%d %H:%M:%S"), e);
+        return Response::text(403, e.to_string());
+    }
+    let deferred = match runner::maintenance_remaining(&config) {
+        Ok(deferred) => deferred,
+        Err(e) => return Response::text(500, format!("failed to check maintenance windows: {}", e)),
+    };
+    if state.running.replace(true) {
+        return Response::text(409, "a run is already in progress");
+    }
+
+    tokio::task::spawn_local(async move {
+        if let Some(delay) = deferred {
+            println!("[{}] 服务端维护中，签到推迟 {} 秒后执行",
+                chrono::Local::now().format("%Y-%m-%d %H:%M:%S"), delay.as_secs());
+            tokio::select! {
+                _ = tokio::time::sleep(delay) => {}
+                _ = state.shutdown.cancelled() => {
+                    println!("[{}] 服务停止，取消推迟的签到",
+                        chrono::Local::now().format("%Y-%m-%d %H:%M:%S"));
+                    state.running.replace(false);
+                    state.idle.notify_waiters();
+                    return;
+                }
+            }
+        }
+        let result = runner::execute(&config, false, &CancellationToken::new()).await;
+        match result {
+            Ok(report) => {
+                *state.last_report.borrow_mut() = Some(report);
+                *state.last_error.borrow_mut() = None;
+            }
+            Err(e) => *state.last_error.borrow_mut() = Some(e.to_string()),
+        }
+        state.running.replace(false);
+        state.idle.notify_waiters();
+    });
+    let body = serde_json::json!({
+        "accepted": true,
+        "deferred_secs": deferred.map(|delay| delay.as_secs()),
+    });
+    Response::json(202, body.to_string())
+}
@@ -1,14 +1,148 @@
-use crate::{config::Account, logger::Logger};
-use chrono;
-use reqwest;
-use serde_json;
-use tokio::time::{sleep, Duration};
+use crate::{
+    artifacts::{Har, HarEntry},
+    backoff::{BackoffStrategy, Constant},
+    catalog::{ErrorCatalog, Outcome},
+    clock,
+    config::{Account, MessageTemplates},
+    console,
+    error::{CheckinError, ErrorCategory},
+    events::{Event, EventSender},
+    fields::Fields,
+    fingerprint,
+    logger::Logger,
+    network::NetworkWait,
+    notifier::{self, Channel, Notification},
+    rate_limit::RateLimiter,
+    redact::BodyFilter,
+    results::Status,
+    secret::SecretString,
+    template::{self, MessageVars},
+};
+use chrono_tz::Tz;
+use reqwest::header::{self, HeaderMap, HeaderValue};
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, fmt, rc::Rc, sync::Arc};
+use tokio::time::{sleep, Duration, Instant};
+use tokio_util::sync::CancellationToken;
+
+pub const DEFAULT_BASE_URL: &str = "https://glados.rocks";
+
+const DEFAULT_BODY_TEMPLATE: &str = r#"{"token":"{{token}}"}"#;
+const DEFAULT_TOKEN: &str = "glados.one";
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct StageTimings {
+    pub request: Duration,
+    pub parse: Duration,
+    pub log: Duration,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct AccountStatus {
+    pub left_days: Option<String>,
+    pub points: Option<String>,
+    // 服务端返回时才有：套餐名称（没有 plan 时为 vip 等级）和邮箱是否已验证
+    pub plan: Option<String>,
+    pub email_verified: Option<bool>,
+}
+
+// 积分流水中的一条记录；time 为服务端返回的毫秒时间戳
+#[derive(Debug, Clone, Default)]
+pub struct PointRecord {
+    pub time: Option<i64>,
+    pub business: Option<String>,
+    pub change: Option<String>,
+    pub balance: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Attempt {
+    pub at: String,
+    pub attempt: u32,
+    pub latency_ms: u64,
+    pub error_category: Option<ErrorCategory>,
+    pub error: Option<String>,
+    // 本次失败后到下一次尝试前实际等待的时间
+    #[serde(default)]
+    pub backoff_ms: u64,
+    // capture_headers 选中的响应头（名称为小写），用于与服务端的故障记录对照
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub headers: BTreeMap<String, String>,
+    // 响应中缺少或类型不符的字段，非空时说明服务端可能修改了响应格式
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub schema_issues: Vec<String>,
+}
+
+// 所有尝试的重试等待时间合计
+pub fn backoff_ms(attempts: &[Attempt]) -> u64 {
+    attempts.iter().map(|attempt| attempt.backoff_ms).sum()
+}
+
+// 写入日志的响应头说明，如 "（响应头 cf-ray=8a1b2c, server=cloudflare）"；没有时为空
+pub fn describe_headers(headers: &BTreeMap<String, String>) -> String {
+    if headers.is_empty() {
+        return String::new();
+    }
+    let pairs: Vec<String> = headers.iter().map(|(name, value)| format!("{}={}", name, value)).collect();
+    format!("（响应头 {}）", pairs.join(", "))
+}
+
+#[derive(Debug, Clone)]
+pub struct CheckinResult {
+    pub message: String,
+    pub change: Option<String>,
+    pub balance: Option<String>,
+    pub list: Vec<serde_json::Value>,
+    pub timings: StageTimings,
+    pub attempts: Vec<Attempt>,
+}
+
+#[derive(Debug)]
+pub struct CheckinFailure {
+    pub error: CheckinError,
+    pub attempts: Vec<Attempt>,
+    pub retries_truncated: bool,
+}
+
+impl CheckinFailure {
+    pub fn new(error: CheckinError, attempts: Vec<Attempt>) -> Self {
+        Self {
+            error,
+            attempts,
+            retries_truncated: false,
+        }
+    }
+}
+
+impl fmt::Display for CheckinFailure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.error.fmt(f)
+    }
+}
+
+impl std::error::Error for CheckinFailure {}
 
 pub struct CheckinService {
     client: reqwest::Client,
     pub logger: Box<dyn Logger>,
+    notifiers: Rc<Vec<Channel>>,
+    messages: MessageTemplates,
+    events: EventSender,
+    network_wait: Option<Arc<NetworkWait>>,
     max_retries: u32,
-    retry_delay: u64,
+    backoff: Box<dyn BackoffStrategy>,
+    base_url: String,
+    checkin_url: String,
+    randomize_fingerprint: bool,
+    rate_limiter: Option<Arc<RateLimiter>>,
+    body_filter: BodyFilter,
+    capture_headers: Vec<String>,
+    shutdown: Option<CancellationToken>,
+    tz: Tz,
+    error_catalog: ErrorCatalog,
+    account_deadline: Option<Duration>,
+    run_deadline: Option<Instant>,
+    har: Option<Rc<Har>>,
 }
 
 impl CheckinService {
@@ -16,76 +150,566 @@ impl CheckinService {
         Self {
             client,
             logger,
+            notifiers: Rc::new(Vec::new()),
+            messages: MessageTemplates::default(),
+            events: EventSender::default(),
+            network_wait: None,
             max_retries,
-            retry_delay,
+            backoff: Box::new(Constant { base: Duration::from_secs(retry_delay) }),
+            base_url: DEFAULT_BASE_URL.to_string(),
+            checkin_url: format!("{}/api/user/checkin", DEFAULT_BASE_URL),
+            randomize_fingerprint: false,
+            rate_limiter: None,
+            body_filter: BodyFilter::default(),
+            capture_headers: Vec::new(),
+            shutdown: None,
+            tz: chrono_tz::Asia::Shanghai,
+            error_catalog: ErrorCatalog::default(),
+            account_deadline: None,
+            run_deadline: None,
+            har: None,
         }
     }
 
-    pub async fn checkin(&self, account: &Account) -> Result<(), Box<dyn std::error::Error>> {
-        let mut retries = 0;
+    pub fn with_notifiers(mut self, notifiers: Rc<Vec<Channel>>) -> Self {
+        self.notifiers = notifiers;
+        self
+    }
+
+    pub fn with_messages(mut self, messages: MessageTemplates) -> Self {
+        self.messages = messages;
+        self
+    }
+
+    pub fn with_events(mut self, events: EventSender) -> Self {
+        self.events = events;
+        self
+    }
+
+    pub fn events(&self) -> &EventSender {
+        &self.events
+    }
+
+    pub fn messages(&self) -> &MessageTemplates {
+        &self.messages
+    }
+
+    pub async fn notify(&self, notification: &Notification) {
+        notifier::notify(&self.notifiers, notification, self.logger.as_ref()).await;
+    }
+
+    pub fn with_network_wait(mut self, network_wait: Option<Arc<NetworkWait>>) -> Self {
+        self.network_wait = network_wait;
+        self
+    }
+
+    // 网络错误时确认是否整体断网，断网期间等待恢复；恢复后返回 true，本次尝试不计入重试
+    async fn wait_for_network(&self, error: &CheckinError, recoveries: u64) -> Result<bool, CheckinError> {
+        let Some(network_wait) = self.network_wait.as_ref().filter(|_| error.category == ErrorCategory::Network) else {
+            return Ok(false);
+        };
+        network_wait.wait(recoveries, self.shutdown.as_ref(), |message| {
+            let log_content = format!("[{}] {}", chrono::Local::now().format("%Y-%m-%d %H:%M:%S"), message);
+            console::progress(&log_content);
+            if let Err(e) = self.logger.log(&log_content) {
+                eprintln!("记录日志失败: {}", e);
+            }
+        }).await
+    }
+
+    // 运行开始前确认服务端可达（经过配置的代理）：收到任何 HTTP 响应（含 4xx/5xx）即视为可达，
+    // 只有连接失败或超时才算不可达；配置了 network_wait 时先等待网络恢复
+    pub async fn preflight(&self, timeout: Duration) -> Result<(), CheckinError> {
         loop {
-            match self.try_checkin(account).await {
+            let recoveries = self.network_wait.as_ref().map_or(0, |network_wait| network_wait.recoveries());
+            let error = match self.client.head(&self.base_url).timeout(timeout).send().await {
                 Ok(_) => return Ok(()),
-                Err(e) => {
+                Err(e) => CheckinError::from(e),
+            };
+            if !self.wait_for_network(&error, recoveries).await? {
+                return Err(error);
+            }
+        }
+    }
+
+    pub fn with_deadlines(mut self, account_deadline: Option<Duration>, run_deadline: Option<Instant>) -> Self {
+        self.account_deadline = account_deadline;
+        self.run_deadline = run_deadline;
+        self
+    }
+
+    pub fn with_error_catalog(mut self, error_catalog: ErrorCatalog) -> Self {
+        self.error_catalog = error_catalog;
+        self
+    }
+
+    pub fn with_timezone(mut self, tz: Tz) -> Self {
+        self.tz = tz;
+        self
+    }
+
+    pub fn with_body_filter(mut self, body_filter: BodyFilter) -> Self {
+        self.body_filter = body_filter;
+        self
+    }
+
+    pub fn with_har(mut self, har: Option<Rc<Har>>) -> Self {
+        self.har = har;
+        self
+    }
+
+    // 替换 new 中按 retry_delay 固定等待的默认策略
+    pub fn with_backoff(mut self, backoff: Box<dyn BackoffStrategy>) -> Self {
+        self.backoff = backoff;
+        self
+    }
+
+    pub fn with_capture_headers(mut self, capture_headers: Vec<String>) -> Self {
+        self.capture_headers = capture_headers;
+        self
+    }
+
+    pub fn with_rate_limiter(mut self, rate_limiter: Option<Arc<RateLimiter>>) -> Self {
+        self.rate_limiter = rate_limiter;
+        self
+    }
+
+    pub fn with_shutdown(mut self, shutdown: Option<CancellationToken>) -> Self {
+        self.shutdown = shutdown;
+        self
+    }
+
+    pub async fn pause(&self, duration: Duration) -> Result<(), CheckinError> {
+        match &self.shutdown {
+            Some(shutdown) => tokio::select! {
+                _ = sleep(duration) => Ok(()),
+                _ = shutdown.cancelled() => Err(CheckinError::new(ErrorCategory::Cancelled, "运行已取消")),
+            },
+            None => {
+                sleep(duration).await;
+                Ok(())
+            }
+        }
+    }
+
+    async fn throttle(&self) -> Result<(), CheckinError> {
+        let Some(rate_limiter) = &self.rate_limiter else {
+            return Ok(());
+        };
+        let mut notified = false;
+        while let Some(wait) = rate_limiter.try_acquire() {
+            if !notified {
+                console::progress(&format!("[{}] 已达到每小时 {} 次请求上限，等待 {:.0} 秒",
+                    chrono::Local::now().format("%Y-%m-%d %H:%M:%S"),
+                    rate_limiter.capacity(), wait.as_secs_f64()));
+                notified = true;
+            }
+            self.pause(wait).await?;
+        }
+        Ok(())
+    }
+
+    pub fn with_randomized_fingerprint(mut self, enabled: bool) -> Self {
+        self.randomize_fingerprint = enabled;
+        self
+    }
+
+    pub fn with_base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.base_url = base_url.into().trim_end_matches('/').to_string();
+        self.checkin_url = format!("{}/api/user/checkin", self.base_url);
+        self
+    }
+
+    fn headers(&self, account: &Account) -> Result<HeaderMap, Box<dyn std::error::Error>> {
+        let mut headers = HeaderMap::with_capacity(2);
+        headers.insert(header::CONTENT_TYPE, HeaderValue::from_static("application/json"));
+        headers.insert(header::COOKIE, account.cookie.expose().parse()?);
+        if self.randomize_fingerprint {
+            return Ok(fingerprint::randomize(headers, &self.base_url));
+        }
+        Ok(headers)
+    }
+
+    fn body(&self, account: &Account) -> String {
+        let date = clock::provider_today(self.tz).format("%Y-%m-%d").to_string();
+        // 请求体按 JSON 发送，替换的值需要转义
+        let email = template::json_escape(&account.email);
+        let token = template::json_escape(account.token.as_deref().unwrap_or(DEFAULT_TOKEN));
+        template::render(account.body_template.as_deref().unwrap_or(DEFAULT_BODY_TEMPLATE), &[
+            ("email", &email),
+            ("token", &token),
+            ("date", &date),
+        ])
+    }
+
+    pub async fn checkin(
+        &self,
+        account: &Account,
+        attempts_used: u32,
+        on_failed_attempt: impl Fn(u32),
+    ) -> Result<CheckinResult, CheckinFailure> {
+        let mut attempts = Vec::new();
+        let headers = match self.headers(account) {
+            Ok(headers) => headers,
+            Err(e) => {
+                let error = CheckinError::new(ErrorCategory::InvalidCookie, account.cookie.redact(&e.to_string()));
+                return Err(CheckinFailure::new(error, attempts));
+            }
+        };
+        let body = self.body(account);
+        let deadline = match (self.account_deadline.map(|timeout| Instant::now() + timeout), self.run_deadline) {
+            (Some(account), Some(run)) => Some(account.min(run)),
+            (account, run) => account.or(run),
+        };
+        let mut retries = attempts_used;
+        loop {
+            let at = chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string();
+            let recoveries = self.network_wait.as_ref().map_or(0, |network_wait| network_wait.recoveries());
+            let started = Instant::now();
+            let mut captured = BTreeMap::new();
+            let mut schema_issues = Vec::new();
+            let outcome = self.try_checkin(account, &headers, &body, &mut captured, &mut schema_issues).await;
+            self.warn_schema(account, "签到", &schema_issues);
+            let mut attempt = Attempt {
+                at,
+                attempt: retries + 1,
+                latency_ms: started.elapsed().as_millis() as u64,
+                error_category: None,
+                error: None,
+                backoff_ms: 0,
+                headers: captured,
+                schema_issues,
+            };
+            match outcome {
+                Ok(mut result) => {
+                    self.events.send(Event::Attempt { email: account.email.clone(), attempt: attempt.clone() });
+                    attempts.push(attempt);
+                    result.attempts = attempts;
+                    return Ok(result);
+                }
+                Err(mut e) => {
+                    match self.wait_for_network(&e, recoveries).await {
+                        Ok(true) => continue,
+                        Ok(false) => {}
+                        Err(error) => return Err(CheckinFailure::new(error, attempts)),
+                    }
+                    e.message = account.cookie.redact(&e.message);
+                    attempt.error_category = Some(e.category);
+                    attempt.error = Some(e.message.clone());
+                    self.events.send(Event::Attempt { email: account.email.clone(), attempt: attempt.clone() });
+                    let response_headers = describe_headers(&attempt.headers);
+                    attempts.push(attempt);
+                    if !e.retryable() {
+                        return Err(CheckinFailure::new(e, attempts));
+                    }
                     retries += 1;
+                    on_failed_attempt(retries);
                     if retries >= self.max_retries {
-                        let error_log = format!("[{}] 账户 {} 签到失败 (重试{}次后): {}",
+                        let error_log = format!("[{}] 账户 {} 签到失败 (重试{}次后): {}{}",
+                            chrono::Local::now().format("%Y-%m-%d %H:%M:%S"),
+                            account.email, retries, e, response_headers);
+                        eprintln!("{}", error_log);
+                        if let Err(log_err) = self.logger.log(&error_log) {
+                            eprintln!("记录日志失败: {}", log_err);
+                        }
+                        return Err(CheckinFailure::new(e, attempts));
+                    }
+                    // 下一次尝试（等待 + 与本次相近的请求耗时）赶不上截止时间时不再重试
+                    let delay = self.backoff.delay(retries);
+                    if deadline.is_some_and(|deadline| Instant::now() + delay + started.elapsed() > deadline) {
+                        let error_log = format!("[{}] 账户 {} 签到失败，剩余时间不足以再次重试（已尝试{}次，剩余{}次重试未执行）: {}{}",
                             chrono::Local::now().format("%Y-%m-%d %H:%M:%S"),
-                            account.email, retries, e);
+                            account.email, retries, self.max_retries - retries, e, response_headers);
                         eprintln!("{}", error_log);
-                        self.logger.log(&error_log)?;
-                        return Err(e);
+                        if let Err(log_err) = self.logger.log(&error_log) {
+                            eprintln!("记录日志失败: {}", log_err);
+                        }
+                        let mut failure = CheckinFailure::new(e, attempts);
+                        failure.retries_truncated = true;
+                        return Err(failure);
+                    }
+                    let retry_log = format!("[{}] 账户 {} 签到失败，{} 秒后重试: {}{}",
+                        chrono::Local::now().format("%Y-%m-%d %H:%M:%S"),
+                        account.email, delay.as_secs(), e, response_headers);
+                    if let Err(log_err) = self.logger.log(&retry_log) {
+                        eprintln!("记录日志失败: {}", log_err);
+                    }
+                    let paused = Instant::now();
+                    let result = self.pause(delay).await;
+                    if let Some(attempt) = attempts.last_mut() {
+                        attempt.backoff_ms = paused.elapsed().as_millis() as u64;
+                    }
+                    if let Err(error) = result {
+                        return Err(CheckinFailure::new(error, attempts));
                     }
-                    sleep(Duration::from_secs(self.retry_delay)).await;
                 }
             }
         }
     }
 
-    async fn try_checkin(&self, account: &Account) -> Result<(), Box<dyn std::error::Error>> {
-        let mut headers = reqwest::header::HeaderMap::new();
-        headers.insert("cookie", account.cookie.parse()?);
-
-        let data = r#"{
-    "token": "glados.one"
-}"#;
+    fn request(&self, headers: &HeaderMap, body: &str) -> reqwest::RequestBuilder {
+        self.client.request(reqwest::Method::POST, &self.checkin_url)
+            .headers(headers.clone())
+            .body(body.to_string())
+    }
 
-        let json: serde_json::Value = serde_json::from_str(&data)?;
+    // 构造与签到时相同的请求但不发送（web show-request）
+    pub fn checkin_request(&self, account: &Account) -> Result<reqwest::Request, Box<dyn std::error::Error>> {
+        Ok(self.request(&self.headers(account)?, &self.body(account)).build()?)
+    }
 
-        let request = self.client.request(reqwest::Method::POST, "https://glados.rocks/api/user/checkin")
-            .headers(headers)
-            .json(&json);
+    // captured 收到响应后填入选中的响应头，请求失败或响应解析失败时同样保留；schema_issues 填入响应中缺少或类型不符的字段
+    async fn try_checkin(
+        &self,
+        account: &Account,
+        headers: &HeaderMap,
+        body: &str,
+        captured: &mut BTreeMap<String, String>,
+        schema_issues: &mut Vec<String>,
+    ) -> Result<CheckinResult, CheckinError> {
+        let request = self.request(headers, body);
 
-        let response = request.send().await?;
+        self.throttle().await?;
+        let started_at = chrono::Local::now();
+        let started = Instant::now();
+        let record = |response: Result<(reqwest::StatusCode, &HeaderMap, String), String>| {
+            if let Some(har) = &self.har {
+                let secrets = [account.cookie.clone(), SecretString::new(account.token.as_deref().unwrap_or_default())];
+                har.record(HarEntry {
+                    account: &account.email,
+                    started: started_at,
+                    elapsed: started.elapsed(),
+                    url: &self.checkin_url,
+                    headers,
+                    body,
+                    secrets: &secrets,
+                    response,
+                });
+            }
+        };
+        let response = match request.send().await {
+            Ok(response) => response,
+            Err(e) => {
+                record(Err(account.cookie.redact(&e.to_string())));
+                return Err(e.into());
+            }
+        };
+        *captured = self.captured_headers(response.headers());
         let status = response.status();
-        let body = response.text().await?;
+        let response_headers = response.headers().clone();
+        let body = match response.bytes().await {
+            Ok(body) => body,
+            Err(e) => {
+                record(Err(account.cookie.redact(&e.to_string())));
+                return Err(e.into());
+            }
+        };
+        record(Ok((status, &response_headers, self.body_filter.redact(&body))));
+        let mut timings = StageTimings {
+            request: started.elapsed(),
+            ..Default::default()
+        };
 
-        let response_json: serde_json::Value = match serde_json::from_str(&body) {
+        let started = Instant::now();
+        let response_json: serde_json::Value = match serde_json::from_slice(&body) {
             Ok(json) => json,
             Err(e) => {
-                return Err(format!("响应解析失败: {}\n响应内容: {}", e, body).into());
+                return Err(CheckinError::new(ErrorCategory::Parse,
+                    format!("响应解析失败: {}\n响应内容: {}", e, self.body_filter.sanitize(&body))));
             }
         };
         
-        if response_json["code"].as_i64().unwrap_or(0) == 1 {
-            let message = response_json["message"].as_str().unwrap_or("No message");
-            
-            if let Some(first_item) = response_json["list"].as_array().and_then(|arr| arr.first()) {
-                let change = first_item["change"].as_str().unwrap_or("0").split('.').next().unwrap_or("0");
-                let balance = first_item["balance"].as_str().unwrap_or("0").split('.').next().unwrap_or("0");
-                
-                let log_content = format!("[{}] Account: {}, Message: {}, Change: {}, Balance: {}", 
-                    chrono::Local::now().format("%Y-%m-%d %H:%M:%S"),
-                    account.email, message, change, balance);
-                
-                println!("{}", log_content);
-                self.logger.log(&log_content)?
-            }
+        let fields = Fields::new(&response_json);
+        if fields.i64("code") == Some(1) {
+            let list = fields.optional_array("list").cloned().unwrap_or_default();
+            let message = fields.str("message").unwrap_or("No message");
+            // 部分套餐的响应中 list 为空或缺少 change/balance，此时仍记录成功，缺少的值记为未知
+            let number = |field: &str| fields.optional_str(field)
+                .map(|value| value.split('.').next().unwrap_or(value).to_string());
+            let change = number("list.0.change");
+            let balance = number("list.0.balance");
+            *schema_issues = fields.into_issues();
+            timings.parse = started.elapsed();
+            let mut result = CheckinResult {
+                message: message.to_string(),
+                change,
+                balance,
+                list,
+                timings,
+                attempts: Vec::new(),
+            };
+
+            // 服务实例名称在这里不可用，{{account}} 即邮箱
+            let log_content = format!("[{}] {}",
+                chrono::Local::now().format("%Y-%m-%d %H:%M:%S"),
+                MessageVars {
+                    account: &account.email,
+                    email: &account.email,
+                    provider: None,
+                    status: Status::Success.as_str(),
+                    message,
+                    change: result.change.as_deref(),
+                    balance: result.balance.as_deref(),
+                }.render(&self.messages.success_log));
+
+            let started = Instant::now();
+            console::progress(&log_content);
+            self.logger.log(&log_content)?;
+            result.timings.log = started.elapsed();
+            Ok(result)
         } else {
-            let error_message = response_json["message"].as_str().unwrap_or("未知错误");
-            return Err(format!("签到失败 - HTTP状态码: {}, 错误信息: {}", status, error_message).into());
+            let error_message = fields.str("message").unwrap_or("未知错误");
+            *schema_issues = fields.into_issues();
+            let message = format!("签到失败 - HTTP状态码: {}, 错误信息: {}", status, error_message);
+            match self.error_catalog.classify(status.as_u16(), &response_json) {
+                Some(classification) if classification.outcome == Outcome::AlreadyCheckedIn => Ok(CheckinResult {
+                    message: error_message.to_string(),
+                    change: None,
+                    balance: None,
+                    list: Vec::new(),
+                    timings,
+                    attempts: Vec::new(),
+                }),
+                Some(classification) => Err(CheckinError::classified(classification, message)),
+                None => Err(CheckinError::new(ErrorCategory::Provider, message)),
+            }
         }
+    }
 
-        Ok(())
+    // 名称不区分大小写，以 * 结尾时按前缀匹配；set-cookie 含会话信息，始终不记录
+    fn captured_headers(&self, headers: &HeaderMap) -> BTreeMap<String, String> {
+        let mut captured: BTreeMap<String, String> = BTreeMap::new();
+        for (name, value) in headers {
+            let name = name.as_str();
+            if name == header::SET_COOKIE.as_str() {
+                continue;
+            }
+            let selected = self.capture_headers.iter().any(|pattern| {
+                let pattern = pattern.to_ascii_lowercase();
+                match pattern.strip_suffix('*') {
+                    Some(prefix) => name.starts_with(prefix),
+                    None => name == pattern,
+                }
+            });
+            if selected {
+                let value = String::from_utf8_lossy(value.as_bytes());
+                captured.entry(name.to_string())
+                    .and_modify(|existing| {
+                        existing.push_str(", ");
+                        existing.push_str(&value);
+                    })
+                    .or_insert_with(|| value.into_owned());
+            }
+        }
+        captured
+    }
+
+    pub async fn status(&self, account: &Account) -> Result<AccountStatus, Box<dyn std::error::Error>> {
+        self.query_status(account).await.map_err(|e| redacted(account, e))
+    }
+
+    async fn query_status(&self, account: &Account) -> Result<AccountStatus, Box<dyn std::error::Error>> {
+        let headers = self.headers(account)?;
+        let status = self.get_json("/api/user/status", &headers).await?;
+        let points = self.get_json("/api/user/points", &headers).await?;
+        let number = |value: &str| value.split('.').next().unwrap_or(value).to_string();
+        let status_fields = Fields::new(&status);
+        let points_fields = Fields::new(&points);
+        let result = AccountStatus {
+            left_days: status_fields.str("data.leftDays").map(number),
+            points: points_fields.str("points").map(number),
+            // plan、vip 和邮箱验证状态只在部分套餐中返回
+            plan: match status_fields.optional_str("data.plan") {
+                Some(plan) if !plan.is_empty() => Some(plan.to_string()),
+                _ => status_fields.optional("data.vip", "number", |vip| vip.is_number().then(|| format!("vip {}", vip))),
+            },
+            email_verified: ["data.emailVerified", "data.email_verified"].iter()
+                .find_map(|field| status_fields.optional(field, "boolean", |verified| match verified {
+                    serde_json::Value::Bool(verified) => Some(*verified),
+                    serde_json::Value::Number(verified) => Some(verified.as_i64() != Some(0)),
+                    _ => None,
+                })),
+        };
+        let mut issues = status_fields.into_issues();
+        issues.extend(points_fields.into_issues());
+        self.warn_schema(account, "状态查询", &issues);
+        Ok(result)
+    }
+
+    // 只请求状态接口，检查 cookie 是否有效（web doctor），返回剩余天数
+    pub async fn verify_cookie(&self, account: &Account) -> Result<Option<String>, Box<dyn std::error::Error>> {
+        self.query_left_days(account).await.map_err(|e| redacted(account, e))
     }
-}
\ No newline at end of file
+
+    async fn query_left_days(&self, account: &Account) -> Result<Option<String>, Box<dyn std::error::Error>> {
+        let headers = self.headers(account)?;
+        let status = self.get_json("/api/user/status", &headers).await?;
+        let fields = Fields::new(&status);
+        let left_days = fields.str("data.leftDays").map(|days| days.split('.').next().unwrap_or(days).to_string());
+        self.warn_schema(account, "状态查询", &fields.into_issues());
+        Ok(left_days)
+    }
+
+    // /api/user/points 返回的 history（只读查询，不签到），按时间从早到晚排列
+    pub async fn point_history(&self, account: &Account) -> Result<Vec<PointRecord>, Box<dyn std::error::Error>> {
+        self.query_point_history(account).await.map_err(|e| redacted(account, e))
+    }
+
+    async fn query_point_history(&self, account: &Account) -> Result<Vec<PointRecord>, Box<dyn std::error::Error>> {
+        let headers = self.headers(account)?;
+        let points = self.get_json("/api/user/points", &headers).await?;
+        let fields = Fields::new(&points);
+        let number = |path: String| fields.optional_str(&path).map(|number| number.split('.').next().unwrap_or(number).to_string());
+        let mut records: Vec<PointRecord> = (0..fields.array("history").map_or(0, Vec::len))
+            .map(|index| PointRecord {
+                time: fields.optional(&format!("history.{}.time", index), "integer", |time| {
+                    time.as_i64().or_else(|| time.as_str().and_then(|time| time.parse().ok()))
+                }),
+                business: fields.optional_str(&format!("history.{}.business", index)).map(str::to_string),
+                change: number(format!("history.{}.change", index)),
+                balance: number(format!("history.{}.balance", index)),
+            })
+            .collect();
+        self.warn_schema(account, "积分记录查询", &fields.into_issues());
+        records.sort_by_key(|record| record.time);
+        Ok(records)
+    }
+
+    async fn get_json(&self, path: &str, headers: &HeaderMap) -> Result<serde_json::Value, Box<dyn std::error::Error>> {
+        let url = format!("{}{}", self.base_url, path);
+        self.throttle().await?;
+        let response = self.client.get(&url).headers(headers.clone()).send().await?;
+        let status = response.status();
+        let body = response.bytes().await?;
+        let json: serde_json::Value = serde_json::from_slice(&body)
+            .map_err(|e| format!("响应解析失败: {}\n响应内容: {}", e, self.body_filter.sanitize(&body)))?;
+        let fields = Fields::new(&json);
+        if fields.i64("code") != Some(0) {
+            let error_message = fields.str("message").unwrap_or("未知错误");
+            let issues = fields.into_issues();
+            if issues.is_empty() {
+                return Err(format!("查询失败 - HTTP状态码: {}, 错误信息: {}", status, error_message).into());
+            }
+            return Err(format!("查询失败 - HTTP状态码: {}, 错误信息: {}（响应格式与预期不符: {}）",
+                status, error_message, issues.join("；")).into());
+        }
+        Ok(json)
+    }
+
+    // 响应中缺少字段或类型不符时写一条警告，而不是静默按缺省值处理
+    fn warn_schema(&self, account: &Account, request: &str, issues: &[String]) {
+        if issues.is_empty() {
+            return;
+        }
+        let warning = format!("[{}] 警告: 账户 {} 的{}响应与预期格式不符，服务端可能修改了接口: {}",
+            chrono::Local::now().format("%Y-%m-%d %H:%M:%S"), account.email, request, issues.join("；"));
+        eprintln!("{}", warning);
+        if let Err(log_err) = self.logger.log(&warning) {
+            eprintln!("记录日志失败: {}", log_err);
+        }
+    }
+}
+
+fn redacted(account: &Account, e: Box<dyn std::error::Error>) -> Box<dyn std::error::Error> {
+    account.cookie.redact(&e.to_string()).into()
+}
@@ -0,0 +1,40 @@
+use tokio::time::{Duration, Instant};
+use tokio_util::sync::CancellationToken;
+
+const ABORT_WINDOW: Duration = Duration::from_secs(3);
+
+// 第一次 Ctrl-C 取消运行（不再开始新的账户），窗口期内再按一次立即退出；SIGTERM 同样触发取消
+pub fn install() -> CancellationToken {
+    let token = CancellationToken::new();
+    #[cfg(unix)]
+    {
+        let cancel = token.clone();
+        tokio::spawn(async move {
+            use tokio::signal::unix::{signal, SignalKind};
+            if let Ok(mut sigterm) = signal(SignalKind::terminate()) {
+                if sigterm.recv().await.is_some() {
+                    eprintln!("[{}] 收到 SIGTERM，正在停止",
+                        chrono::Local::now().format("%Y-%m-%d %H:%M:%S"));
+                    cancel.cancel();
+                }
+            }
+        });
+    }
+    let cancel = token.clone();
+    tokio::spawn(async move {
+        let mut last_signal: Option<Instant> = None;
+        while tokio::signal::ctrl_c().await.is_ok() {
+            if last_signal.is_some_and(|at| at.elapsed() < ABORT_WINDOW) {
+                eprintln!("[{}] 再次收到中断信号，立即退出",
+                    chrono::Local::now().format("%Y-%m-%d %H:%M:%S"));
+                std::process::exit(130);
+            }
+            last_signal = Some(Instant::now());
+            cancel.cancel();
+            eprintln!("[{}] 收到中断信号，等待进行中的账户完成；{} 秒内再次按 Ctrl-C 立即退出",
+                chrono::Local::now().format("%Y-%m-%d %H:%M:%S"),
+                ABORT_WINDOW.as_secs());
+        }
+    });
+    token
+}
@@ -0,0 +1,14 @@
+use base64::{engine::general_purpose::STANDARD, Engine};
+use ed25519_dalek::{pkcs8::DecodePrivateKey, Signer, SigningKey};
+
+// 私钥为 PKCS#8 PEM（openssl genpkey -algorithm ed25519），签名以 base64 写入 <文件>.sig
+pub fn sign_file(key_path: &str, path: &str, content: &[u8]) -> Result<String, Box<dyn std::error::Error>> {
+    let pem = std::fs::read_to_string(key_path)
+        .map_err(|e| format!("failed to read signing_key {}: {}", key_path, e))?;
+    let key = SigningKey::from_pkcs8_pem(&pem)
+        .map_err(|e| format!("invalid signing_key {}: {}", key_path, e))?;
+    let signature = STANDARD.encode(key.sign(content).to_bytes());
+    let sig_path = format!("{}.sig", path);
+    std::fs::write(&sig_path, &signature)?;
+    Ok(sig_path)
+}
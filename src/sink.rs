@@ -0,0 +1,509 @@
+use base64::{
+    engine::general_purpose::{STANDARD, URL_SAFE_NO_PAD},
+    Engine,
+};
+use futures::future::LocalBoxFuture;
+use hmac::{Hmac, Mac};
+use ring::{
+    rand::SystemRandom,
+    signature::{self, RsaKeyPair},
+};
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use std::{
+    collections::BTreeMap,
+    path::Path,
+    sync::Mutex,
+    time::Duration,
+};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::TcpStream,
+};
+
+use crate::{
+    config::{Config, ReportFormat, SinkConfig},
+    console,
+    logger,
+    queue::DiskQueue,
+    redact,
+    results::RunReport,
+    runner,
+    secret::SecretString,
+    signing,
+};
+
+type HmacSha256 = Hmac<Sha256>;
+
+const SINK_TIMEOUT: Duration = Duration::from_secs(30);
+
+// 接收一次运行的结果；content 为 RunReport 的 JSON，所有输出共用同一份序列化结果
+pub trait ResultSink {
+    fn name(&self) -> String;
+    fn send<'a>(&'a self, report: &'a RunReport, content: &'a [u8]) -> LocalBoxFuture<'a, Result<(), Box<dyn std::error::Error>>>;
+}
+
+pub fn build(config: &Config) -> Result<Vec<Box<dyn ResultSink>>, Box<dyn std::error::Error>> {
+    let mut sinks: Vec<Box<dyn ResultSink>> = Vec::new();
+    for sink in config.result_sinks() {
+        sinks.push(match sink {
+            SinkConfig::File { path, format, signing_key } => Box::new(FileSink { path, format, signing_key }),
+            SinkConfig::Webhook { url, headers, queue } => Box::new(WebhookSink {
+                client: runner::http_client(config)?,
+                queue: queue.then(|| DiskQueue::new(
+                    Path::new(&config.sink_queue_dir).join(format!("webhook-{}", &hex::encode(Sha256::digest(url.as_bytes()))[..12])))),
+                url,
+                headers,
+            }),
+            SinkConfig::S3 { bucket, region, endpoint, prefix, access_key, secret_key } => Box::new(S3Sink {
+                client: runner::http_client(config)?,
+                endpoint: endpoint.unwrap_or_else(|| format!("https://s3.{}.amazonaws.com", region)),
+                bucket,
+                region,
+                prefix,
+                access_key,
+                secret_key,
+            }),
+            SinkConfig::Mqtt { host, port, topic, client_id, username, password, retain } => Box::new(MqttSink {
+                host,
+                port,
+                topic,
+                client_id,
+                username,
+                password,
+                retain,
+            }),
+            SinkConfig::Sqlite { path } => Box::new(SqliteSink::open(&path)?),
+            SinkConfig::GoogleSheets { credentials_file, spreadsheet_id, range, endpoint } => Box::new(GoogleSheetsSink {
+                client: runner::http_client(config)?,
+                credentials: ServiceAccount::load(&credentials_file)?,
+                spreadsheet_id,
+                range,
+                endpoint: endpoint.unwrap_or_else(|| "https://sheets.googleapis.com".to_string()),
+            }),
+        });
+    }
+    Ok(sinks)
+}
+
+// 依次写入所有输出，单个输出失败不影响其他输出，最后汇总返回错误
+pub async fn publish(sinks: &[Box<dyn ResultSink>], report: &RunReport) -> Result<(), Box<dyn std::error::Error>> {
+    if sinks.is_empty() {
+        return Ok(());
+    }
+    let content = serde_json::to_vec_pretty(report)?;
+    let mut failed = Vec::new();
+    for sink in sinks {
+        if let Err(e) = sink.send(report, &content).await {
+            eprintln!("[{}] 结果输出 {} 失败: {}",
+                chrono::Local::now().format("%Y-%m-%d %H:%M:%S"), sink.name(), e);
+            failed.push(sink.name());
+        }
+    }
+    if !failed.is_empty() {
+        return Err(format!("failed to publish results to {}", failed.join(", ")).into());
+    }
+    Ok(())
+}
+
+pub struct FileSink {
+    path: String,
+    format: ReportFormat,
+    signing_key: Option<String>,
+}
+
+impl ResultSink for FileSink {
+    fn name(&self) -> String {
+        format!("file:{}", self.path)
+    }
+
+    fn send<'a>(&'a self, report: &'a RunReport, content: &'a [u8]) -> LocalBoxFuture<'a, Result<(), Box<dyn std::error::Error>>> {
+        Box::pin(async move {
+            let html;
+            let content = match self.format {
+                ReportFormat::Json => content,
+                ReportFormat::Html => {
+                    html = report.to_html();
+                    html.as_bytes()
+                }
+            };
+            logger::ensure_parent_dir(Path::new(&self.path))?;
+            std::fs::write(&self.path, content)?;
+            if let Some(key_path) = &self.signing_key {
+                signing::sign_file(key_path, &self.path, content)?;
+            }
+            Ok(())
+        })
+    }
+}
+
+pub struct WebhookSink {
+    client: reqwest::Client,
+    url: String,
+    headers: BTreeMap<String, String>,
+    queue: Option<DiskQueue>,
+}
+
+impl WebhookSink {
+    async fn post(&self, content: &[u8]) -> Result<(), Box<dyn std::error::Error>> {
+        let mut request = self.client.post(&self.url)
+            .timeout(SINK_TIMEOUT)
+            .header("Content-Type", "application/json")
+            .body(content.to_vec());
+        for (name, value) in &self.headers {
+            request = request.header(name, value);
+        }
+        request.send().await
+            .and_then(reqwest::Response::error_for_status)
+            .map_err(|e| e.without_url())?;
+        Ok(())
+    }
+
+    // 先按顺序补发积压的结果，全部成功后才发送本次结果，保证接收端看到的顺序与运行顺序一致
+    async fn deliver(&self, content: &[u8]) -> Result<(), Box<dyn std::error::Error>> {
+        let Some(queue) = &self.queue else {
+            return self.post(content).await;
+        };
+        let pending = queue.pending()?;
+        let mut delivered = 0;
+        let mut result = Ok(());
+        for path in &pending {
+            if let Err(e) = self.post(&std::fs::read(path)?).await {
+                result = Err(e);
+                break;
+            }
+            std::fs::remove_file(path)?;
+            delivered += 1;
+        }
+        if delivered > 0 {
+            console::progress(&format!("[{}] 已向 {} 补发 {} 条之前发送失败的结果",
+                chrono::Local::now().format("%Y-%m-%d %H:%M:%S"), self.name(), delivered));
+        }
+        if result.is_ok() {
+            result = self.post(content).await;
+        }
+        result.or_else(|e| {
+            let pending = queue.push(content)?;
+            Err(format!("{}; queued for retry ({} pending)", e, pending).into())
+        })
+    }
+}
+
+impl ResultSink for WebhookSink {
+    fn name(&self) -> String {
+        format!("webhook:{}", redact::origin(&self.url))
+    }
+
+    fn send<'a>(&'a self, _report: &'a RunReport, content: &'a [u8]) -> LocalBoxFuture<'a, Result<(), Box<dyn std::error::Error>>> {
+        Box::pin(self.deliver(content))
+    }
+}
+
+// 使用 AWS Signature V4 以 path-style 上传对象，兼容 MinIO 等 S3 接口
+pub struct S3Sink {
+    client: reqwest::Client,
+    endpoint: String,
+    bucket: String,
+    region: String,
+    prefix: String,
+    access_key: String,
+    secret_key: SecretString,
+}
+
+impl S3Sink {
+    fn object_key(&self) -> String {
+        format!("{}results-{}.json", self.prefix, chrono::Utc::now().format("%Y%m%dT%H%M%SZ"))
+    }
+
+    fn authorization(&self, url: &reqwest::Url, host: &str, amz_date: &str, payload_hash: &str) -> Result<String, Box<dyn std::error::Error>> {
+        let date = &amz_date[..8];
+        let signed_headers = "host;x-amz-content-sha256;x-amz-date";
+        let canonical_request = format!(
+            "PUT\n{}\n\nhost:{}\nx-amz-content-sha256:{}\nx-amz-date:{}\n\n{}\n{}",
+            url.path(), host, payload_hash, amz_date, signed_headers, payload_hash);
+        let scope = format!("{}/{}/s3/aws4_request", date, self.region);
+        let string_to_sign = format!("AWS4-HMAC-SHA256\n{}\n{}\n{}",
+            amz_date, scope, hex::encode(Sha256::digest(canonical_request.as_bytes())));
+
+        let mut key = format!("AWS4{}", self.secret_key.expose()).into_bytes();
+        for part in [date, self.region.as_str(), "s3", "aws4_request", string_to_sign.as_str()] {
+            let mut mac = HmacSha256::new_from_slice(&key).map_err(|e| e.to_string())?;
+            mac.update(part.as_bytes());
+            key = mac.finalize().into_bytes().to_vec();
+        }
+        Ok(format!("AWS4-HMAC-SHA256 Credential={}/{}, SignedHeaders={}, Signature={}",
+            self.access_key, scope, signed_headers, hex::encode(key)))
+    }
+}
+
+impl ResultSink for S3Sink {
+    fn name(&self) -> String {
+        format!("s3:{}/{}", self.bucket, self.prefix)
+    }
+
+    fn send<'a>(&'a self, _report: &'a RunReport, content: &'a [u8]) -> LocalBoxFuture<'a, Result<(), Box<dyn std::error::Error>>> {
+        Box::pin(async move {
+            let mut url = reqwest::Url::parse(&self.endpoint)?;
+            url.path_segments_mut()
+                .map_err(|_| format!("invalid s3 endpoint {}", self.endpoint))?
+                .pop_if_empty()
+                .push(&self.bucket)
+                .extend(self.object_key().split('/'));
+            let host = match (url.host_str(), url.port()) {
+                (Some(host), Some(port)) => format!("{}:{}", host, port),
+                (Some(host), None) => host.to_string(),
+                (None, _) => return Err(format!("invalid s3 endpoint {}", self.endpoint).into()),
+            };
+            let amz_date = chrono::Utc::now().format("%Y%m%dT%H%M%SZ").to_string();
+            let payload_hash = hex::encode(Sha256::digest(content));
+            let authorization = self.authorization(&url, &host, &amz_date, &payload_hash)?;
+            self.client.put(url)
+                .timeout(SINK_TIMEOUT)
+                .header("Content-Type", "application/json")
+                .header("x-amz-content-sha256", payload_hash)
+                .header("x-amz-date", amz_date)
+                .header("Authorization", authorization)
+                .body(content.to_vec())
+                .send()
+                .await?
+                .error_for_status()?;
+            Ok(())
+        })
+    }
+}
+
+// MQTT 3.1.1，QoS 0，明文 TCP；每次运行建立一次连接并在发布后断开
+pub struct MqttSink {
+    host: String,
+    port: u16,
+    topic: String,
+    client_id: String,
+    username: Option<String>,
+    password: Option<SecretString>,
+    retain: bool,
+}
+
+impl MqttSink {
+    fn connect_packet(&self) -> Vec<u8> {
+        let mut flags = 0x02;
+        let mut body = Vec::new();
+        put_str(&mut body, "MQTT");
+        body.push(4);
+        body.push(0);
+        body.extend_from_slice(&60u16.to_be_bytes());
+        put_str(&mut body, &self.client_id);
+        if let Some(username) = &self.username {
+            flags |= 0x80;
+            put_str(&mut body, username);
+        }
+        if let Some(password) = &self.password {
+            flags |= 0x40;
+            put_str(&mut body, password.expose());
+        }
+        body[7] = flags;
+        packet(0x10, &body)
+    }
+
+    fn publish_packet(&self, content: &[u8]) -> Vec<u8> {
+        let mut body = Vec::new();
+        put_str(&mut body, &self.topic);
+        body.extend_from_slice(content);
+        packet(if self.retain { 0x31 } else { 0x30 }, &body)
+    }
+
+    async fn publish(&self, content: &[u8]) -> Result<(), Box<dyn std::error::Error>> {
+        let mut stream = TcpStream::connect((self.host.as_str(), self.port)).await?;
+        stream.write_all(&self.connect_packet()).await?;
+        let mut connack = [0u8; 4];
+        stream.read_exact(&mut connack).await?;
+        if connack[0] != 0x20 || connack[3] != 0 {
+            return Err(format!("mqtt broker refused connection (return code {})", connack[3]).into());
+        }
+        stream.write_all(&self.publish_packet(content)).await?;
+        stream.write_all(&[0xE0, 0x00]).await?;
+        stream.flush().await?;
+        Ok(())
+    }
+}
+
+impl ResultSink for MqttSink {
+    fn name(&self) -> String {
+        format!("mqtt:{}:{}/{}", self.host, self.port, self.topic)
+    }
+
+    fn send<'a>(&'a self, _report: &'a RunReport, content: &'a [u8]) -> LocalBoxFuture<'a, Result<(), Box<dyn std::error::Error>>> {
+        Box::pin(async move {
+            tokio::time::timeout(SINK_TIMEOUT, self.publish(content)).await
+                .map_err(|_| format!("mqtt broker {}:{} timed out", self.host, self.port))?
+        })
+    }
+}
+
+fn put_str(buffer: &mut Vec<u8>, value: &str) {
+    buffer.extend_from_slice(&(value.len() as u16).to_be_bytes());
+    buffer.extend_from_slice(value.as_bytes());
+}
+
+fn packet(header: u8, body: &[u8]) -> Vec<u8> {
+    let mut packet = vec![header];
+    let mut remaining = body.len();
+    loop {
+        let mut byte = (remaining % 128) as u8;
+        remaining /= 128;
+        if remaining > 0 {
+            byte |= 0x80;
+        }
+        packet.push(byte);
+        if remaining == 0 {
+            break;
+        }
+    }
+    packet.extend_from_slice(body);
+    packet
+}
+
+// 每次运行一行，report 列保存完整 JSON
+pub struct SqliteSink {
+    path: String,
+    connection: Mutex<rusqlite::Connection>,
+}
+
+impl SqliteSink {
+    pub fn open(path: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        logger::ensure_parent_dir(Path::new(path))?;
+        let connection = rusqlite::Connection::open(path)?;
+        connection.busy_timeout(Duration::from_secs(5))?;
+        connection.pragma_update(None, "journal_mode", "WAL")?;
+        connection.execute(
+            "CREATE TABLE IF NOT EXISTS run_results (id INTEGER PRIMARY KEY, started_at TEXT NOT NULL, finished_at TEXT NOT NULL, report TEXT NOT NULL)",
+            [],
+        )?;
+        Ok(Self {
+            path: path.to_string(),
+            connection: Mutex::new(connection),
+        })
+    }
+}
+
+impl ResultSink for SqliteSink {
+    fn name(&self) -> String {
+        format!("sqlite:{}", self.path)
+    }
+
+    fn send<'a>(&'a self, report: &'a RunReport, content: &'a [u8]) -> LocalBoxFuture<'a, Result<(), Box<dyn std::error::Error>>> {
+        Box::pin(async move {
+            self.connection.lock().unwrap_or_else(|e| e.into_inner()).execute(
+                "INSERT INTO run_results (started_at, finished_at, report) VALUES (?1, ?2, ?3)",
+                (&report.started_at, &report.finished_at, std::str::from_utf8(content)?),
+            )?;
+            Ok(())
+        })
+    }
+}
+
+#[derive(Deserialize)]
+struct ServiceAccount {
+    client_email: String,
+    private_key: SecretString,
+    #[serde(default = "default_token_uri")]
+    token_uri: String,
+}
+
+fn default_token_uri() -> String {
+    "https://oauth2.googleapis.com/token".to_string()
+}
+
+impl ServiceAccount {
+    fn load(path: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        let content = std::fs::read_to_string(path)
+            .map_err(|e| format!("failed to read credentials_file {}: {}", path, e))?;
+        Ok(serde_json::from_str(&content)
+            .map_err(|e| format!("invalid service account key {}: {}", path, e))?)
+    }
+
+    // OAuth 2.0 JWT bearer 授权：用服务账号私钥（RS256）签名断言换取 access token
+    async fn access_token(&self, client: &reqwest::Client) -> Result<String, Box<dyn std::error::Error>> {
+        let now = chrono::Utc::now().timestamp();
+        let header = URL_SAFE_NO_PAD.encode(r#"{"alg":"RS256","typ":"JWT"}"#);
+        let claims = URL_SAFE_NO_PAD.encode(serde_json::json!({
+            "iss": self.client_email,
+            "scope": "https://www.googleapis.com/auth/spreadsheets",
+            "aud": self.token_uri,
+            "iat": now,
+            "exp": now + 3600,
+        }).to_string());
+        let message = format!("{}.{}", header, claims);
+
+        let der: String = self.private_key.expose().lines().filter(|line| !line.starts_with("-----")).collect();
+        let key = RsaKeyPair::from_pkcs8(&STANDARD.decode(der.trim())?)
+            .map_err(|e| format!("invalid service account private_key: {}", e))?;
+        let mut signature = vec![0; key.public().modulus_len()];
+        key.sign(&signature::RSA_PKCS1_SHA256, &SystemRandom::new(), message.as_bytes(), &mut signature)
+            .map_err(|e| format!("failed to sign service account assertion: {}", e))?;
+        let assertion = format!("{}.{}", message, URL_SAFE_NO_PAD.encode(signature));
+
+        let response: serde_json::Value = client.post(&self.token_uri)
+            .timeout(SINK_TIMEOUT)
+            .form(&[("grant_type", "urn:ietf:params:oauth:grant-type:jwt-bearer"), ("assertion", assertion.as_str())])
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+        Ok(response["access_token"].as_str().ok_or("token response has no access_token")?.to_string())
+    }
+}
+
+// 每个账户追加一行：结束时间、服务实例、邮箱、状态、变化、余额、信息
+pub struct GoogleSheetsSink {
+    client: reqwest::Client,
+    credentials: ServiceAccount,
+    spreadsheet_id: String,
+    range: String,
+    endpoint: String,
+}
+
+impl ResultSink for GoogleSheetsSink {
+    fn name(&self) -> String {
+        format!("google_sheets:{}", self.spreadsheet_id)
+    }
+
+    fn send<'a>(&'a self, report: &'a RunReport, _content: &'a [u8]) -> LocalBoxFuture<'a, Result<(), Box<dyn std::error::Error>>> {
+        Box::pin(async move {
+            let values: Vec<serde_json::Value> = report.accounts.iter()
+                .map(|account| serde_json::json!([
+                    report.finished_at,
+                    account.provider.as_deref().unwrap_or(""),
+                    account.email,
+                    account.status.as_str(),
+                    account.change.as_deref().unwrap_or(""),
+                    account.balance.as_deref().unwrap_or(""),
+                    account.error.as_ref().or(account.message.as_ref()).map_or("", String::as_str),
+                    account.attempts.len(),
+                    account.duration_ms,
+                    account.backoff_ms,
+                ]))
+                .collect();
+            if values.is_empty() {
+                return Ok(());
+            }
+            let token = self.credentials.access_token(&self.client).await?;
+            let mut url = reqwest::Url::parse(&self.endpoint)?;
+            url.path_segments_mut()
+                .map_err(|_| format!("invalid sheets endpoint {}", self.endpoint))?
+                .pop_if_empty()
+                .extend(["v4", "spreadsheets", &self.spreadsheet_id, "values", &format!("{}:append", self.range)]);
+            url.query_pairs_mut()
+                .append_pair("valueInputOption", "RAW")
+                .append_pair("insertDataOption", "INSERT_ROWS");
+            self.client.post(url)
+                .timeout(SINK_TIMEOUT)
+                .bearer_auth(token)
+                .json(&serde_json::json!({ "values": values }))
+                .send()
+                .await?
+                .error_for_status()?;
+            Ok(())
+        })
+    }
+}
@@ -0,0 +1,179 @@
+use base64::{engine::general_purpose::STANDARD, Engine};
+use std::{sync::Arc, time::Duration};
+use tokio::{
+    io::{AsyncBufReadExt, AsyncRead, AsyncWrite, AsyncWriteExt, BufReader},
+    net::TcpStream,
+};
+use tokio_rustls::{rustls, TlsConnector};
+
+use crate::{config::SmtpTls, secret::SecretString};
+
+const SMTP_TIMEOUT: Duration = Duration::from_secs(30);
+
+pub struct SmtpServer {
+    pub host: String,
+    pub port: u16,
+    pub tls: SmtpTls,
+    pub username: Option<String>,
+    pub password: Option<SecretString>,
+}
+
+pub struct Message {
+    pub from: String,
+    pub to: Vec<String>,
+    pub subject: String,
+    pub text: String,
+    pub html: String,
+}
+
+trait Stream: AsyncRead + AsyncWrite + Unpin + Send {}
+
+impl<T: AsyncRead + AsyncWrite + Unpin + Send> Stream for T {}
+
+struct Connection {
+    stream: BufReader<Box<dyn Stream>>,
+}
+
+impl Connection {
+    async fn reply(&mut self) -> Result<(u16, String), Box<dyn std::error::Error>> {
+        let mut text = String::new();
+        loop {
+            let mut line = String::new();
+            if self.stream.read_line(&mut line).await? == 0 {
+                return Err("SMTP server closed the connection".into());
+            }
+            let code = line.get(..3).and_then(|code| code.parse().ok())
+                .ok_or_else(|| format!("invalid SMTP reply: {}", line.trim_end()))?;
+            text.push_str(line.get(4..).unwrap_or_default().trim_end());
+            text.push('\n');
+            if line.as_bytes().get(3) != Some(&b'-') {
+                return Ok((code, text));
+            }
+        }
+    }
+
+    // 错误信息只带命令名，避免 AUTH 参数中的凭据出现在日志里
+    async fn command(&mut self, command: &str, expected: u16) -> Result<String, Box<dyn std::error::Error>> {
+        self.stream.get_mut().write_all(format!("{}\r\n", command).as_bytes()).await?;
+        self.stream.get_mut().flush().await?;
+        self.expect(command.split(' ').next().unwrap_or(command), expected).await
+    }
+
+    async fn expect(&mut self, step: &str, expected: u16) -> Result<String, Box<dyn std::error::Error>> {
+        let (code, text) = self.reply().await?;
+        if code != expected {
+            return Err(format!("SMTP {} failed: {} {}", step, code, text.trim_end()).into());
+        }
+        Ok(text)
+    }
+}
+
+pub async fn send(server: &SmtpServer, message: &Message) -> Result<(), Box<dyn std::error::Error>> {
+    tokio::time::timeout(SMTP_TIMEOUT, deliver(server, message)).await
+        .map_err(|_| format!("SMTP {}:{} timed out", server.host, server.port))?
+}
+
+async fn deliver(server: &SmtpServer, message: &Message) -> Result<(), Box<dyn std::error::Error>> {
+    let tcp = TcpStream::connect((server.host.as_str(), server.port)).await?;
+    let stream: Box<dyn Stream> = match server.tls {
+        SmtpTls::Tls => Box::new(tls_connect(&server.host, tcp).await?),
+        SmtpTls::Starttls | SmtpTls::None => Box::new(tcp),
+    };
+    let mut connection = Connection { stream: BufReader::new(stream) };
+    connection.expect("greeting", 220).await?;
+    let mut capabilities = connection.command("EHLO localhost", 250).await?;
+
+    if server.tls == SmtpTls::Starttls {
+        connection.command("STARTTLS", 220).await?;
+        // 服务端在 220 之后才开始 TLS 握手，此时缓冲区中不应有剩余数据
+        let stream = connection.stream.into_inner();
+        connection = Connection { stream: BufReader::new(Box::new(tls_connect(&server.host, stream).await?)) };
+        capabilities = connection.command("EHLO localhost", 250).await?;
+    }
+
+    if let Some(username) = &server.username {
+        if !capabilities.lines().any(|line| line.to_ascii_uppercase().starts_with("AUTH")) {
+            return Err("SMTP server does not offer AUTH".into());
+        }
+        let password = server.password.as_ref().map_or("", SecretString::expose);
+        let credentials = STANDARD.encode(format!("\0{}\0{}", username, password));
+        connection.command(&format!("AUTH PLAIN {}", credentials), 235).await?;
+    }
+
+    connection.command(&format!("MAIL FROM:<{}>", address(&message.from)), 250).await?;
+    for recipient in &message.to {
+        connection.command(&format!("RCPT TO:<{}>", address(recipient)), 250).await?;
+    }
+    connection.command("DATA", 354).await?;
+    let mut data = String::new();
+    for line in render(message).lines() {
+        // 以 . 开头的行需要转义（RFC 5321 4.5.2）
+        if line.starts_with('.') {
+            data.push('.');
+        }
+        data.push_str(line);
+        data.push_str("\r\n");
+    }
+    data.push_str(".\r\n");
+    connection.stream.get_mut().write_all(data.as_bytes()).await?;
+    connection.stream.get_mut().flush().await?;
+    connection.expect("DATA", 250).await?;
+    let _ = connection.command("QUIT", 221).await;
+    Ok(())
+}
+
+async fn tls_connect<S: AsyncRead + AsyncWrite + Unpin>(host: &str, stream: S) -> Result<tokio_rustls::client::TlsStream<S>, Box<dyn std::error::Error>> {
+    let mut roots = rustls::RootCertStore::empty();
+    roots.add_trust_anchors(webpki_roots::TLS_SERVER_ROOTS.iter().map(|anchor| {
+        rustls::OwnedTrustAnchor::from_subject_spki_name_constraints(anchor.subject, anchor.spki, anchor.name_constraints)
+    }));
+    let config = rustls::ClientConfig::builder()
+        .with_safe_defaults()
+        .with_root_certificates(roots)
+        .with_no_client_auth();
+    let server_name = rustls::ServerName::try_from(host).map_err(|e| format!("invalid SMTP host '{}': {}", host, e))?;
+    Ok(TlsConnector::from(Arc::new(config)).connect(server_name, stream).await?)
+}
+
+// "名称 <addr@example.com>" 形式只取尖括号内的地址
+fn address(mailbox: &str) -> &str {
+    match (mailbox.rfind('<'), mailbox.rfind('>')) {
+        (Some(start), Some(end)) if start < end => &mailbox[start + 1..end],
+        _ => mailbox.trim(),
+    }
+}
+
+// 非 ASCII 的显示名称同样需要 RFC 2047 编码
+fn header_mailbox(mailbox: &str) -> String {
+    match mailbox.rfind('<') {
+        Some(start) if !mailbox[..start].is_ascii() => {
+            format!("=?UTF-8?B?{}?= {}", STANDARD.encode(mailbox[..start].trim()), &mailbox[start..])
+        }
+        _ => mailbox.to_string(),
+    }
+}
+
+// 纯文本与 HTML 两个版本，正文均为 base64，主题按 RFC 2047 编码以支持中文
+fn render(message: &Message) -> String {
+    let boundary = format!("web-{:016x}", rand::random::<u64>());
+    let mut mail = format!(
+        "From: {}\nTo: {}\nSubject: =?UTF-8?B?{}?=\nDate: {}\nMessage-ID: <{}.{:08x}@web>\nMIME-Version: 1.0\n\
+         Content-Type: multipart/alternative; boundary=\"{}\"\n\n",
+        header_mailbox(&message.from),
+        message.to.iter().map(|to| header_mailbox(to)).collect::<Vec<_>>().join(", "),
+        STANDARD.encode(&message.subject),
+        chrono::Local::now().to_rfc2822(),
+        chrono::Utc::now().timestamp(),
+        rand::random::<u32>(),
+        boundary);
+    for (content_type, body) in [("text/plain", &message.text), ("text/html", &message.html)] {
+        mail.push_str(&format!("--{}\nContent-Type: {}; charset=utf-8\nContent-Transfer-Encoding: base64\n\n", boundary, content_type));
+        let encoded = STANDARD.encode(body);
+        for chunk in encoded.as_bytes().chunks(76) {
+            mail.push_str(std::str::from_utf8(chunk).unwrap_or_default());
+            mail.push('\n');
+        }
+    }
+    mail.push_str(&format!("--{}--\n", boundary));
+    mail
+}
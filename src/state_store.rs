@@ -0,0 +1,277 @@
+use serde::{Deserialize, Serialize};
+use rusqlite::OptionalExtension;
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use crate::{
+    config::{StateBackend, StateStoreConfig},
+    logger, perms,
+};
+
+const BUSY_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AttentionFlag {
+    pub flagged_at: String,
+    pub reason: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CookieRecord {
+    pub hash: String,
+    pub first_seen: String,
+    pub last_reminded: Option<String>,
+}
+
+// 账户签到时间相对运行开始的偏移，date 为最近一次计算的日期（服务端时区）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RunOffset {
+    pub secs: u64,
+    pub date: String,
+}
+
+// 单个 cookie（以 sha256 区分）的登录结果，只统计登录失效类失败
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CookieHealth {
+    #[serde(default)]
+    pub last_success: Option<String>,
+    #[serde(default)]
+    pub last_failure: Option<String>,
+    #[serde(default)]
+    pub failure_streak: u32,
+}
+
+// 按账户（account_key）保存的持久状态，各后端只负责整条记录的读写
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AccountState {
+    #[serde(default)]
+    pub last_success: Option<String>,
+    #[serde(default)]
+    pub failure_streak: u32,
+    #[serde(default)]
+    pub retries_used: u32,
+    #[serde(default)]
+    pub flag: Option<AttentionFlag>,
+    #[serde(default)]
+    pub cookie: Option<CookieRecord>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub cookie_health: BTreeMap<String, CookieHealth>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub run_offset: Option<RunOffset>,
+}
+
+pub trait StateStore: Send + Sync {
+    fn get(&self, key: &str) -> Result<Option<AccountState>, Box<dyn std::error::Error>>;
+    fn put(&self, key: &str, state: &AccountState) -> Result<(), Box<dyn std::error::Error>>;
+    fn all(&self) -> Result<BTreeMap<String, AccountState>, Box<dyn std::error::Error>>;
+
+    // 读取-修改-写入；支持事务的后端应覆盖此方法，避免与其他进程的写入互相覆盖
+    fn modify(&self, key: &str, f: &mut dyn FnMut(&mut AccountState)) -> Result<(), Box<dyn std::error::Error>> {
+        let mut state = self.get(key)?.unwrap_or_default();
+        f(&mut state);
+        self.put(key, &state)
+    }
+}
+
+impl dyn StateStore {
+    pub fn load(&self, key: &str) -> Result<AccountState, Box<dyn std::error::Error>> {
+        Ok(self.get(key)?.unwrap_or_default())
+    }
+
+    pub fn update<T>(&self, key: &str, f: impl FnOnce(&mut AccountState) -> T) -> Result<T, Box<dyn std::error::Error>> {
+        let mut f = Some(f);
+        let mut value = None;
+        self.modify(key, &mut |state| value = f.take().map(|f| f(state)))?;
+        Ok(value.expect("modify must call the update closure"))
+    }
+}
+
+pub fn open(config: &StateStoreConfig) -> Result<Arc<dyn StateStore>, Box<dyn std::error::Error>> {
+    Ok(match config.backend {
+        StateBackend::File => Arc::new(FileStateStore::open(config.path())?),
+        StateBackend::Sqlite => Arc::new(SqliteStateStore::open(config.path())?),
+        StateBackend::Redis => Arc::new(RedisStateStore::open(config.url.as_deref().unwrap_or_default(), &config.key)?),
+    })
+}
+
+pub struct FileStateStore {
+    file_path: PathBuf,
+    states: Mutex<BTreeMap<String, AccountState>>,
+}
+
+impl FileStateStore {
+    pub fn open(file_path: impl Into<PathBuf>) -> Result<Self, Box<dyn std::error::Error>> {
+        let file_path = file_path.into();
+        let states = read_states(&file_path)?;
+        Ok(Self {
+            file_path,
+            states: Mutex::new(states),
+        })
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, BTreeMap<String, AccountState>> {
+        self.states.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl StateStore for FileStateStore {
+    fn get(&self, key: &str) -> Result<Option<AccountState>, Box<dyn std::error::Error>> {
+        Ok(self.lock().get(key).cloned())
+    }
+
+    // 写入前重新读取文件，保留其他进程期间写入的其他账户；多个进程频繁并发写入请使用 sqlite 后端
+    fn put(&self, key: &str, state: &AccountState) -> Result<(), Box<dyn std::error::Error>> {
+        let mut states = self.lock();
+        *states = read_states(&self.file_path)?;
+        states.insert(key.to_string(), state.clone());
+        let content = serde_json::to_vec_pretty(&*states)?;
+        let tmp_path = self.file_path.with_extension("tmp");
+        logger::ensure_parent_dir(&self.file_path)?;
+        perms::write_private(&tmp_path, content)?;
+        std::fs::rename(&tmp_path, &self.file_path)?;
+        Ok(())
+    }
+
+    fn all(&self) -> Result<BTreeMap<String, AccountState>, Box<dyn std::error::Error>> {
+        Ok(self.lock().clone())
+    }
+}
+
+fn read_states(file_path: &Path) -> Result<BTreeMap<String, AccountState>, Box<dyn std::error::Error>> {
+    match std::fs::read_to_string(file_path) {
+        Ok(content) => Ok(serde_json::from_str(&content)
+            .map_err(|e| format!("failed to parse {}: {}", file_path.display(), e))?),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(BTreeMap::new()),
+        Err(e) => Err(e.into()),
+    }
+}
+
+pub struct SqliteStateStore {
+    connection: Mutex<rusqlite::Connection>,
+}
+
+impl SqliteStateStore {
+    pub fn open(path: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        logger::ensure_parent_dir(path.as_ref())?;
+        let connection = rusqlite::Connection::open(path)?;
+        // 状态中有 cookie 的哈希和账户信息；WAL 和 shm 文件由 SQLite 按数据库文件的权限创建
+        perms::restrict(path)?;
+        // WAL 模式下读者不阻塞写者（web status 等查询不会卡住正在进行的运行），写冲突时等待而不是立即报错
+        connection.busy_timeout(BUSY_TIMEOUT)?;
+        connection.pragma_update(None, "journal_mode", "WAL")?;
+        connection.pragma_update(None, "synchronous", "NORMAL")?;
+        connection.execute(
+            "CREATE TABLE IF NOT EXISTS account_state (key TEXT PRIMARY KEY, state TEXT NOT NULL)",
+            [],
+        )?;
+        Ok(Self {
+            connection: Mutex::new(connection),
+        })
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, rusqlite::Connection> {
+        self.connection.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl StateStore for SqliteStateStore {
+    fn get(&self, key: &str) -> Result<Option<AccountState>, Box<dyn std::error::Error>> {
+        let connection = self.lock();
+        let mut statement = connection.prepare_cached("SELECT state FROM account_state WHERE key = ?1")?;
+        let mut rows = statement.query([key])?;
+        match rows.next()? {
+            Some(row) => Ok(Some(serde_json::from_str(&row.get::<_, String>(0)?)?)),
+            None => Ok(None),
+        }
+    }
+
+    fn put(&self, key: &str, state: &AccountState) -> Result<(), Box<dyn std::error::Error>> {
+        self.lock().execute(
+            "INSERT INTO account_state (key, state) VALUES (?1, ?2) ON CONFLICT(key) DO UPDATE SET state = excluded.state",
+            (key, serde_json::to_string(state)?),
+        )?;
+        Ok(())
+    }
+
+    fn modify(&self, key: &str, f: &mut dyn FnMut(&mut AccountState)) -> Result<(), Box<dyn std::error::Error>> {
+        let mut connection = self.lock();
+        let transaction = connection.transaction_with_behavior(rusqlite::TransactionBehavior::Immediate)?;
+        let state: Option<String> = transaction
+            .query_row("SELECT state FROM account_state WHERE key = ?1", [key], |row| row.get(0))
+            .optional()?;
+        let mut state: AccountState = match state {
+            Some(state) => serde_json::from_str(&state)?,
+            None => AccountState::default(),
+        };
+        f(&mut state);
+        transaction.execute(
+            "INSERT INTO account_state (key, state) VALUES (?1, ?2) ON CONFLICT(key) DO UPDATE SET state = excluded.state",
+            (key, serde_json::to_string(&state)?),
+        )?;
+        transaction.commit()?;
+        Ok(())
+    }
+
+    fn all(&self) -> Result<BTreeMap<String, AccountState>, Box<dyn std::error::Error>> {
+        let connection = self.lock();
+        let mut statement = connection.prepare_cached("SELECT key, state FROM account_state")?;
+        let mut rows = statement.query([])?;
+        let mut states = BTreeMap::new();
+        while let Some(row) = rows.next()? {
+            states.insert(row.get(0)?, serde_json::from_str(&row.get::<_, String>(1)?)?);
+        }
+        Ok(states)
+    }
+}
+
+// 所有账户保存在同一个 hash 中，field 为 account_key，value 为 JSON
+pub struct RedisStateStore {
+    connection: Mutex<redis::Connection>,
+    key: String,
+}
+
+impl RedisStateStore {
+    pub fn open(url: &str, key: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        let connection = redis::Client::open(url)?.get_connection()?;
+        Ok(Self {
+            connection: Mutex::new(connection),
+            key: key.to_string(),
+        })
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, redis::Connection> {
+        self.connection.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl StateStore for RedisStateStore {
+    fn get(&self, key: &str) -> Result<Option<AccountState>, Box<dyn std::error::Error>> {
+        let value: Option<String> = redis::cmd("HGET").arg(&self.key).arg(key).query(&mut *self.lock())?;
+        match value {
+            Some(value) => Ok(Some(serde_json::from_str(&value)?)),
+            None => Ok(None),
+        }
+    }
+
+    fn put(&self, key: &str, state: &AccountState) -> Result<(), Box<dyn std::error::Error>> {
+        redis::cmd("HSET")
+            .arg(&self.key)
+            .arg(key)
+            .arg(serde_json::to_string(state)?)
+            .query::<()>(&mut *self.lock())?;
+        Ok(())
+    }
+
+    fn all(&self) -> Result<BTreeMap<String, AccountState>, Box<dyn std::error::Error>> {
+        let values: BTreeMap<String, String> = redis::cmd("HGETALL").arg(&self.key).query(&mut *self.lock())?;
+        let mut states = BTreeMap::new();
+        for (key, value) in values {
+            states.insert(key, serde_json::from_str(&value)?);
+        }
+        Ok(states)
+    }
+}
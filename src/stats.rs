@@ -0,0 +1,226 @@
+use chrono::{NaiveDate, TimeZone};
+use futures::StreamExt;
+use serde::Serialize;
+use std::collections::{BTreeMap, VecDeque};
+use tokio_util::sync::CancellationToken;
+
+use crate::{
+    cli::{HistoryArgs, OutputArgs},
+    clock,
+    config::Config,
+    history::HistoryStore,
+    output::{self, TableRow},
+    results::Status,
+    runner,
+};
+
+#[derive(Debug, Serialize)]
+pub struct HistoryRow {
+    pub time: String,
+    pub account: String,
+    pub status: Status,
+    pub change: Option<String>,
+    pub balance: Option<String>,
+    pub attempts: usize,
+    pub duration_ms: u64,
+    pub backoff_ms: u64,
+    pub message: String,
+}
+
+impl TableRow for HistoryRow {
+    const HEADERS: &'static [&'static str] = &["time", "account", "status", "change", "balance", "attempts", "duration", "backoff", "message"];
+
+    fn cells(&self) -> Vec<String> {
+        vec![
+            self.time.clone(),
+            self.account.clone(),
+            self.status.as_str().to_string(),
+            output::cell(self.change.as_deref()),
+            output::cell(self.balance.as_deref()),
+            self.attempts.to_string(),
+            output::seconds(self.duration_ms),
+            output::seconds(self.backoff_ms),
+            self.message.clone(),
+        ]
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct PointRow {
+    pub time: String,
+    pub account: String,
+    pub business: Option<String>,
+    pub change: Option<String>,
+    pub balance: Option<String>,
+}
+
+impl TableRow for PointRow {
+    const HEADERS: &'static [&'static str] = &["time", "account", "business", "change", "balance"];
+
+    fn cells(&self) -> Vec<String> {
+        vec![
+            self.time.clone(),
+            self.account.clone(),
+            output::cell(self.business.as_deref()),
+            output::cell(self.change.as_deref()),
+            output::cell(self.balance.as_deref()),
+        ]
+    }
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct StatsRow {
+    pub account: String,
+    pub runs: u32,
+    pub succeeded: u32,
+    pub failed: u32,
+    pub success_rate: f64,
+    pub total_change: i64,
+    pub last_success: Option<String>,
+    // 记录了耗时的运行的平均耗时；旧版本写入的历史没有耗时，不计入
+    pub avg_duration_ms: Option<u64>,
+    // 账户已用 account remove --archive 归档
+    pub archived: bool,
+    #[serde(skip)]
+    timed: (u64, u64),
+}
+
+impl TableRow for StatsRow {
+    const HEADERS: &'static [&'static str] = &["account", "runs", "succeeded", "failed", "success_rate", "total_change", "last_success", "avg_duration", "archived"];
+
+    fn cells(&self) -> Vec<String> {
+        vec![
+            self.account.clone(),
+            self.runs.to_string(),
+            self.succeeded.to_string(),
+            self.failed.to_string(),
+            format!("{:.1}%", self.success_rate * 100.0),
+            self.total_change.to_string(),
+            output::cell(self.last_success.as_deref()),
+            self.avg_duration_ms.map_or_else(|| "-".to_string(), output::seconds),
+            if self.archived { "yes" } else { "no" }.to_string(),
+        ]
+    }
+}
+
+pub async fn history(config: &Config, args: &HistoryArgs) -> Result<(), Box<dyn std::error::Error>> {
+    // --days N 包含今天在内的 N 天（按签到重置时区）
+    let today = clock::provider_today(config.reset_timezone()?);
+    let since = args.days.map(|days| today - chrono::Days::new(days.saturating_sub(1).into()));
+    if args.remote {
+        return point_history(config, args, since).await;
+    }
+    let since = since.map(|since| since.format("%Y-%m-%d").to_string());
+    let mut rows = VecDeque::new();
+    HistoryStore::new(&config.history_file).for_each(|entry| {
+        let account = entry.key();
+        if args.account.as_ref().is_some_and(|filter| *filter != account && *filter != entry.email) {
+            return;
+        }
+        if since.as_ref().is_some_and(|since| entry.date < *since) {
+            return;
+        }
+        rows.push_back(HistoryRow {
+            time: entry.time,
+            account,
+            status: entry.status,
+            change: entry.change,
+            balance: entry.balance,
+            attempts: entry.attempts.len(),
+            duration_ms: entry.duration_ms,
+            backoff_ms: entry.backoff_ms,
+            message: entry.message,
+        });
+        if rows.len() > args.limit {
+            rows.pop_front();
+        }
+    })?;
+    output::print(rows.make_contiguous(), args.output.format)
+}
+
+async fn point_history(config: &Config, args: &HistoryArgs, since: Option<NaiveDate>) -> Result<(), Box<dyn std::error::Error>> {
+    let tz = config.reset_timezone()?;
+    let shutdown = CancellationToken::new();
+    let mut rows = Vec::new();
+    let mut matched = false;
+    for instance in config.provider_instances() {
+        let accounts: Vec<_> = instance.accounts()?.collect::<Result<Vec<_>, _>>()?.into_iter()
+            .filter(|account| args.account.as_ref().is_none_or(|filter| *filter == instance.key(&account.email) || *filter == account.email))
+            .collect();
+        if accounts.is_empty() {
+            continue;
+        }
+        matched = true;
+        let service = runner::build_service(config, &instance, &shutdown).await?;
+        let histories: Vec<_> = futures::stream::iter(&accounts)
+            .map(|account| {
+                let service = &service;
+                async move { (account, service.point_history(account).await) }
+            })
+            .buffered(config.concurrency_limit())
+            .collect()
+            .await;
+
+        for (account, history) in histories {
+            let key = instance.key(&account.email);
+            let records = match history {
+                Ok(records) => records,
+                Err(e) => {
+                    eprintln!("查询账户 {} 的积分流水失败: {}", key, e);
+                    continue;
+                }
+            };
+            let records: Vec<_> = records.into_iter()
+                .map(|record| (record.time.and_then(|time| tz.timestamp_millis_opt(time).single()), record))
+                .filter(|(time, _)| since.is_none_or(|since| time.is_some_and(|time| time.date_naive() >= since)))
+                .collect();
+            let skip = records.len().saturating_sub(args.limit);
+            rows.extend(records.into_iter().skip(skip).map(|(time, record)| PointRow {
+                time: time.map_or_else(|| "-".to_string(), |time| time.format("%Y-%m-%d %H:%M:%S").to_string()),
+                account: key.clone(),
+                business: record.business,
+                change: record.change,
+                balance: record.balance,
+            }));
+        }
+    }
+    if let (Some(account), false) = (&args.account, matched) {
+        return Err(format!("account '{}' not found", account).into());
+    }
+    output::print(&rows, args.output.format)
+}
+
+pub fn run(config: &Config, args: &OutputArgs) -> Result<(), Box<dyn std::error::Error>> {
+    let mut stats: BTreeMap<String, StatsRow> = BTreeMap::new();
+    HistoryStore::new(&config.history_file).for_each(|entry| {
+        let account = entry.key();
+        let row = stats.entry(account.clone()).or_insert_with(|| StatsRow {
+            account,
+            ..Default::default()
+        });
+        row.runs += 1;
+        if entry.duration_ms > 0 {
+            row.timed.0 += 1;
+            row.timed.1 += entry.duration_ms;
+        }
+        match entry.status {
+            Status::Success => {
+                row.succeeded += 1;
+                row.total_change += entry.change.as_deref().and_then(|change| change.parse::<i64>().ok()).unwrap_or(0);
+                row.last_success = Some(entry.time);
+            }
+            Status::Failed => row.failed += 1,
+            Status::Skipped => {}
+        }
+    })?;
+    let rows: Vec<StatsRow> = stats.into_values()
+        .map(|mut row| {
+            row.success_rate = if row.runs == 0 { 0.0 } else { row.succeeded as f64 / row.runs as f64 };
+            let (count, total) = row.timed;
+            row.avg_duration_ms = (count > 0).then(|| total / count);
+            row.archived = config.archived_accounts.iter().any(|archived| archived.key() == row.account);
+            row
+        })
+        .collect();
+    output::print(&rows, args.format)
+}
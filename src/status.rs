@@ -0,0 +1,140 @@
+use futures::StreamExt;
+use serde::Serialize;
+use tokio_util::sync::CancellationToken;
+
+use crate::{
+    cli::StatusArgs,
+    config::Config,
+    cookie_pool,
+    history::HistoryStore,
+    output::{self, TableRow},
+    results::Status,
+    run_state,
+    runner,
+    serve,
+    state_store,
+};
+
+#[derive(Debug, Serialize)]
+pub struct StatusRow {
+    pub account: String,
+    pub last_run: Option<String>,
+    pub status: Option<Status>,
+    pub change: Option<String>,
+    pub balance: Option<String>,
+    pub dead_cookies: Vec<String>,
+}
+
+impl TableRow for StatusRow {
+    const HEADERS: &'static [&'static str] = &["account", "last_run", "status", "change", "balance", "dead_cookies"];
+
+    fn cells(&self) -> Vec<String> {
+        vec![
+            self.account.clone(),
+            output::cell(self.last_run.as_deref()),
+            output::cell(self.status.as_ref().map(Status::as_str)),
+            output::cell(self.change.as_deref()),
+            output::cell(self.balance.as_deref()),
+            output::cell(Some(self.dead_cookies.join(",")).filter(|cookies| !cookies.is_empty()).as_deref()),
+        ]
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct RemoteStatusRow {
+    pub account: String,
+    pub left_days: Option<String>,
+    pub plan: Option<String>,
+    pub email_verified: Option<bool>,
+    pub points: Option<String>,
+    pub error: Option<String>,
+}
+
+impl TableRow for RemoteStatusRow {
+    const HEADERS: &'static [&'static str] = &["account", "left_days", "plan", "email_verified", "points", "error"];
+
+    fn cells(&self) -> Vec<String> {
+        vec![
+            self.account.clone(),
+            output::cell(self.left_days.as_deref()),
+            output::cell(self.plan.as_deref()),
+            output::cell(self.email_verified.map(|verified| if verified { "yes" } else { "no" })),
+            output::cell(self.points.as_deref()),
+            self.error.clone().unwrap_or_default(),
+        ]
+    }
+}
+
+pub async fn run(config: &Config, args: &StatusArgs) -> Result<(), Box<dyn std::error::Error>> {
+    let mut instances = Vec::new();
+    for instance in config.provider_instances() {
+        let accounts: Vec<_> = instance.accounts()?.collect::<Result<_, _>>()?;
+        instances.push((instance, accounts));
+    }
+
+    if !args.remote {
+        let latest = HistoryStore::new(&config.history_file).latest()?;
+        let states = state_store::open(&config.state_store)?.all()?;
+        let mut rows = Vec::new();
+        for (instance, accounts) in &instances {
+            for account in accounts {
+                let key = instance.key(&account.email);
+                let entry = latest.get(&key);
+                rows.push(StatusRow {
+                    dead_cookies: states.get(&key).map(|state| cookie_pool::dead(account, state)).unwrap_or_default(),
+                    last_run: entry.map(|entry| entry.time.clone()),
+                    status: entry.map(|entry| entry.status),
+                    change: entry.and_then(|entry| entry.change.clone()),
+                    balance: entry.and_then(|entry| entry.balance.clone()),
+                    account: key,
+                });
+            }
+        }
+        output::print(&rows, args.output.format)?;
+        // 进度写到标准错误，不影响 json/csv 输出；serve 在 trigger.socket 上运行时以其状态为准
+        if let Some(server) = serve::socket_status(config).await? {
+            eprintln!("触发服务: {}", server.to_text());
+        } else if let Some(progress) = run_state::progress(&config.state_file)? {
+            eprintln!("{}", progress.to_text());
+        }
+        return Ok(());
+    }
+
+    let shutdown = CancellationToken::new();
+    let mut rows = Vec::new();
+    for (instance, accounts) in &instances {
+        let service = runner::build_service(config, instance, &shutdown).await?;
+
+        let statuses: Vec<_> = futures::stream::iter(accounts)
+            .map(|account| {
+                let service = &service;
+                async move { (account, service.status(account).await) }
+            })
+            .buffered(config.concurrency_limit())
+            .collect()
+            .await;
+
+        for (account, status) in statuses {
+            let account = instance.key(&account.email);
+            rows.push(match status {
+                Ok(status) => RemoteStatusRow {
+                    account,
+                    left_days: status.left_days,
+                    plan: status.plan,
+                    email_verified: status.email_verified,
+                    points: status.points,
+                    error: None,
+                },
+                Err(e) => RemoteStatusRow {
+                    account,
+                    left_days: None,
+                    plan: None,
+                    email_verified: None,
+                    points: None,
+                    error: Some(e.to_string()),
+                },
+            });
+        }
+    }
+    output::print(&rows, args.output.format)
+}
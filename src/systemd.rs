@@ -0,0 +1,58 @@
+use tokio::time::Duration;
+use tokio_util::sync::CancellationToken;
+
+// sd_notify 协议：向 $NOTIFY_SOCKET（Unix 数据报套接字，@ 开头为抽象命名空间）发送 KEY=VALUE 文本。
+// 不在 systemd（Type=notify）下运行时环境变量不存在，所有调用均为空操作
+#[cfg(unix)]
+pub fn notify(state: &str) {
+    use std::os::unix::net::UnixDatagram;
+    let Some(path) = std::env::var_os("NOTIFY_SOCKET") else {
+        return;
+    };
+    let result = UnixDatagram::unbound().and_then(|socket| {
+        let path = path.to_string_lossy();
+        match path.strip_prefix('@') {
+            #[cfg(target_os = "linux")]
+            Some(name) => {
+                use std::os::linux::net::SocketAddrExt;
+                let address = std::os::unix::net::SocketAddr::from_abstract_name(name.as_bytes())?;
+                socket.send_to_addr(state.as_bytes(), &address)
+            }
+            _ => socket.send_to(state.as_bytes(), path.as_ref()),
+        }
+    });
+    if let Err(e) = result {
+        eprintln!("[{}] 通知 systemd 失败: {}", chrono::Local::now().format("%Y-%m-%d %H:%M:%S"), e);
+    }
+}
+
+#[cfg(not(unix))]
+pub fn notify(_state: &str) {}
+
+// 配置了 WatchdogSec= 时按一半间隔发送 WATCHDOG=1，直到 shutdown 被取消；
+// 进程卡死（运行时不再调度任务）时 systemd 超时后按 Restart= 重启服务
+pub fn spawn_watchdog(shutdown: CancellationToken) {
+    let Some(interval) = watchdog_interval() else {
+        return;
+    };
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval / 2);
+        loop {
+            tokio::select! {
+                _ = ticker.tick() => notify("WATCHDOG=1"),
+                _ = shutdown.cancelled() => break,
+            }
+        }
+    });
+}
+
+// WATCHDOG_PID 存在且不是本进程时说明变量是从父进程继承的，不应由本进程发送
+fn watchdog_interval() -> Option<Duration> {
+    if let Ok(pid) = std::env::var("WATCHDOG_PID") {
+        if pid.parse::<u32>().ok() != Some(std::process::id()) {
+            return None;
+        }
+    }
+    let usec = std::env::var("WATCHDOG_USEC").ok()?.parse::<u64>().ok().filter(|&usec| usec > 0)?;
+    Some(Duration::from_micros(usec))
+}
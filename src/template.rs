@@ -0,0 +1,139 @@
+// 请求体模板：{{name}} 形式的占位符在发送前替换为对应值
+pub const PLACEHOLDERS: &[&str] = &["email", "token", "date"];
+
+// 通知 webhook 的请求体模板
+pub const NOTIFICATION_PLACEHOLDERS: &[&str] = &["account", "email", "provider", "status", "message", "change", "balance", "time", "text"];
+
+// 日志行和通知文本模板；缺少的变化/余额显示为 -
+pub const MESSAGE_PLACEHOLDERS: &[&str] = &["account", "email", "provider", "status", "message", "change", "balance", "time"];
+
+pub fn render(template: &str, vars: &[(&str, &str)]) -> String {
+    let mut rendered = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find("{{") {
+        let Some(end) = rest[start..].find("}}") else {
+            break;
+        };
+        let name = &rest[start + 2..start + end];
+        rendered.push_str(&rest[..start]);
+        match vars.iter().find(|(key, _)| *key == name) {
+            Some((_, value)) => rendered.push_str(value),
+            None => rendered.push_str(&rest[start..start + end + 2]),
+        }
+        rest = &rest[start + end + 2..];
+    }
+    rendered.push_str(rest);
+    rendered
+}
+
+// 转义为 JSON 字符串内容（不含两侧引号），用于替换 JSON 模板中引号内的占位符
+pub fn json_escape(value: &str) -> String {
+    let quoted = serde_json::Value::from(value).to_string();
+    quoted[1..quoted.len() - 1].to_string()
+}
+
+pub fn validate(template: &str, placeholders: &[&str]) -> Result<(), String> {
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        if !rest[start..].starts_with("{{") {
+            // {email} 这样的单花括号不会被替换，按原样发送
+            let after = &rest[start + 1..];
+            if let Some((name, _)) = after.split_once('}').filter(|(name, _)| placeholders.contains(name)) {
+                return Err(format!("placeholder {{{}}} is not substituted, write it as {{{{{}}}}}", name, name));
+            }
+            rest = after;
+            continue;
+        }
+        let end = rest[start..].find("}}").ok_or("unclosed placeholder")?;
+        let name = &rest[start + 2..start + end];
+        if !placeholders.contains(&name) {
+            return Err(format!("unknown placeholder {{{{{}}}}}, expected one of {}", name, placeholders.join(", ")));
+        }
+        rest = &rest[start + end + 2..];
+    }
+    Ok(())
+}
+
+// 日志行和通知文本模板的取值
+pub struct MessageVars<'a> {
+    pub account: &'a str,
+    pub email: &'a str,
+    pub provider: Option<&'a str>,
+    pub status: &'a str,
+    pub message: &'a str,
+    pub change: Option<&'a str>,
+    pub balance: Option<&'a str>,
+}
+
+impl MessageVars<'_> {
+    pub fn render(&self, template: &str) -> String {
+        let time = chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string();
+        render(template, &[
+            ("account", self.account),
+            ("email", self.email),
+            ("provider", self.provider.unwrap_or("")),
+            ("status", self.status),
+            ("message", self.message),
+            ("change", self.change.unwrap_or("-")),
+            ("balance", self.balance.unwrap_or("-")),
+            ("time", &time),
+        ])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn render_replaces_known_placeholders() {
+        assert_eq!(render("{{email}} {{date}}/{{email}}", &[("email", "a@example.com"), ("date", "2024-10-15")]), "a@example.com 2024-10-15/a@example.com");
+        assert_eq!(render("no placeholders", &[("email", "a")]), "no placeholders");
+    }
+
+    #[test]
+    fn render_keeps_unknown_and_unclosed_placeholders() {
+        assert_eq!(render("{{email}} {{other}}", &[("email", "a")]), "a {{other}}");
+        assert_eq!(render("{{email}} {{email", &[("email", "a")]), "a {{email");
+        // 替换后的值中的占位符不会再被替换
+        assert_eq!(render("{{email}}", &[("email", "{{token}}"), ("token", "secret")]), "{{token}}");
+    }
+
+    #[test]
+    fn json_escape_keeps_rendered_body_valid() {
+        assert_eq!(json_escape(r#"a"b\c"#), r#"a\"b\\c"#);
+        let token = json_escape("x\",\"admin\":true,\"y");
+        let body = render(r#"{"token":"{{token}}"}"#, &[("token", &token)]);
+        let value: serde_json::Value = serde_json::from_str(&body).unwrap();
+        assert_eq!(value, serde_json::json!({"token": "x\",\"admin\":true,\"y"}));
+    }
+
+    #[test]
+    fn validate_placeholders() {
+        assert!(validate(r#"{"token":"{{token}}","email":"{{email}}"}"#, PLACEHOLDERS).is_ok());
+        assert!(validate("{{status}}", PLACEHOLDERS).unwrap_err().contains("{{status}}"));
+        assert_eq!(validate("{{email", PLACEHOLDERS).unwrap_err(), "unclosed placeholder");
+    }
+
+    #[test]
+    fn validate_rejects_single_brace_placeholders() {
+        assert_eq!(validate(r#"{"text":"{email} {balance}"}"#, NOTIFICATION_PLACEHOLDERS).unwrap_err(),
+            "placeholder {email} is not substituted, write it as {{email}}");
+        // JSON 的花括号和未知名称不受影响
+        assert!(validate(r#"{"text":"{{email}} {note}","nested":{"a":1}}"#, NOTIFICATION_PLACEHOLDERS).is_ok());
+    }
+
+    #[test]
+    fn message_vars_default_missing_values() {
+        let vars = MessageVars {
+            account: "main/a@example.com",
+            email: "a@example.com",
+            provider: None,
+            status: "success",
+            message: "ok",
+            change: None,
+            balance: Some("101"),
+        };
+        assert_eq!(vars.render("{{account}} [{{provider}}] {{status}} {{change}} {{balance}} {{message}}"), "main/a@example.com [] success - 101 ok");
+    }
+}
@@ -0,0 +1,115 @@
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+
+use crate::http::Request;
+
+type HmacSha256 = Hmac<Sha256>;
+
+// 支持 GitHub 风格（X-Hub-Signature-256: sha256=<hex>）和 Stripe 风格（X-Signature: t=<unix>,v1=<hex>，签名内容为 "<t>.<body>"）
+pub fn verify_signature(request: &Request, secret: &str, tolerance_secs: i64) -> Result<(), String> {
+    if let Some(header) = request.header("x-hub-signature-256") {
+        let signature = header.strip_prefix("sha256=").ok_or("malformed X-Hub-Signature-256")?;
+        return verify_hex(secret, &[&request.body], signature);
+    }
+
+    if let Some(header) = request.header("x-signature") {
+        let mut timestamp = None;
+        let mut signatures = Vec::new();
+        for part in header.split(',') {
+            match part.trim().split_once('=') {
+                Some(("t", value)) => timestamp = value.parse::<i64>().ok(),
+                Some(("v1", value)) => signatures.push(value),
+                _ => {}
+            }
+        }
+        let timestamp = timestamp.ok_or("X-Signature missing timestamp")?;
+        // t 由请求方提供，极端值相减会溢出
+        let skew = chrono::Utc::now().timestamp().checked_sub(timestamp).map(i64::unsigned_abs);
+        if skew.is_none_or(|skew| skew > tolerance_secs as u64) {
+            return Err("signature timestamp outside tolerance".to_string());
+        }
+        let prefix = format!("{}.", timestamp);
+        return signatures
+            .iter()
+            .find_map(|signature| verify_hex(secret, &[prefix.as_bytes(), &request.body], signature).ok())
+            .ok_or_else(|| "signature mismatch".to_string());
+    }
+
+    Err("missing signature header".to_string())
+}
+
+fn verify_hex(secret: &str, parts: &[&[u8]], signature: &str) -> Result<(), String> {
+    let expected = hex::decode(signature).map_err(|_| "signature is not valid hex".to_string())?;
+    let mut mac = HmacSha256::new_from_slice(secret.as_bytes()).map_err(|e| e.to_string())?;
+    for part in parts {
+        mac.update(part);
+    }
+    mac.verify_slice(&expected).map_err(|_| "signature mismatch".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SECRET: &str = "trigger-secret";
+
+    fn sign(parts: &[&[u8]]) -> String {
+        let mut mac = HmacSha256::new_from_slice(SECRET.as_bytes()).unwrap();
+        for part in parts {
+            mac.update(part);
+        }
+        hex::encode(mac.finalize().into_bytes())
+    }
+
+    fn request(header: &str, value: String) -> Request {
+        Request {
+            method: "POST".to_string(),
+            path: "/trigger".to_string(),
+            headers: vec![(header.to_string(), value)],
+            body: br#"{"run":true}"#.to_vec(),
+        }
+    }
+
+    fn stripe(timestamp: i64, signature: &str) -> Request {
+        request("X-Signature", format!("t={},v1={}", timestamp, signature))
+    }
+
+    #[test]
+    fn github_signature() {
+        let body = br#"{"run":true}"#;
+        assert_eq!(verify_signature(&request("X-Hub-Signature-256", format!("sha256={}", sign(&[body]))), SECRET, 300), Ok(()));
+        assert_eq!(verify_signature(&request("x-hub-signature-256", format!("sha256={}", sign(&[b"other"]))), SECRET, 300), Err("signature mismatch".to_string()));
+        assert_eq!(verify_signature(&request("X-Hub-Signature-256", sign(&[body])), SECRET, 300), Err("malformed X-Hub-Signature-256".to_string()));
+        assert_eq!(verify_signature(&request("X-Hub-Signature-256", "sha256=zz".to_string()), SECRET, 300), Err("signature is not valid hex".to_string()));
+    }
+
+    #[test]
+    fn timestamped_signature() {
+        let now = chrono::Utc::now().timestamp();
+        let signed = |timestamp: i64| sign(&[format!("{}.", timestamp).as_bytes(), br#"{"run":true}"#]);
+        assert_eq!(verify_signature(&stripe(now, &signed(now)), SECRET, 300), Ok(()));
+        assert_eq!(verify_signature(&stripe(now - 299, &signed(now - 299)), SECRET, 300), Ok(()));
+        // 时间戳不能替换：签名包含时间戳
+        assert_eq!(verify_signature(&stripe(now - 10, &signed(now)), SECRET, 300), Err("signature mismatch".to_string()));
+        // 轮换密钥期间可以有多个 v1
+        let rotated = request("X-Signature", format!("t={},v1={},v1={}", now, "00".repeat(32), signed(now)));
+        assert_eq!(verify_signature(&rotated, SECRET, 300), Ok(()));
+    }
+
+    #[test]
+    fn timestamp_tolerance() {
+        let now = chrono::Utc::now().timestamp();
+        let signed = |timestamp: i64| sign(&[format!("{}.", timestamp).as_bytes(), br#"{"run":true}"#]);
+        let outside = Err("signature timestamp outside tolerance".to_string());
+        assert_eq!(verify_signature(&stripe(now - 301, &signed(now - 301)), SECRET, 300), outside);
+        assert_eq!(verify_signature(&stripe(now + 301, &signed(now + 301)), SECRET, 300), outside);
+        assert_eq!(verify_signature(&stripe(i64::MIN, &signed(i64::MIN)), SECRET, 300), outside);
+        assert_eq!(verify_signature(&request("X-Signature", format!("v1={}", signed(now))), SECRET, 300), Err("X-Signature missing timestamp".to_string()));
+    }
+
+    #[test]
+    fn missing_signature() {
+        let unsigned = Request { method: "POST".to_string(), path: "/trigger".to_string(), headers: Vec::new(), body: Vec::new() };
+        assert_eq!(verify_signature(&unsigned, SECRET, 300), Err("missing signature header".to_string()));
+    }
+}